@@ -0,0 +1,768 @@
+//! Evaluation of programs by walking their AST.
+pub mod value;
+
+use crate::module::{Module, ModuleError};
+use crate::parse::ast::{
+    self, BinaryOp, ComprehensionClause, Expression, Pattern, Program, Statement, VarWithValue,
+};
+use crate::parse::span::{GetSpan, Span};
+use crate::types::structure::Flex;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::io::Write;
+use std::rc::Rc;
+use value::{Callable, Env, RecGroup, Value};
+
+#[cfg(test)]
+mod tests;
+
+/// A runtime error, along with the span of the expression that caused it.
+pub type EvalError = (String, Span);
+
+pub struct Interpreter<W: Write> {
+    /// Variables in scope at the top level
+    globals: Env,
+    /// Variables bound by top-level lets, which other modules can import
+    exports: HashMap<String, Value>,
+    /// Map from the name of each module available for import to its members
+    modules: HashMap<String, Env>,
+    /// Map from enumeration name to the names of its variants
+    enumerations: HashMap<String, Vec<String>>,
+    /// Where `print` writes to
+    output: W,
+}
+
+impl<W: Write> Interpreter<W> {
+    pub fn new(output: W) -> Self {
+        let mut enumerations = HashMap::new();
+        // Builtins
+        enumerations.insert(
+            "bool".to_string(),
+            vec!["true".to_string(), "false".to_string()],
+        );
+        Self {
+            globals: prelude(),
+            exports: HashMap::new(),
+            modules: HashMap::new(),
+            enumerations,
+            output,
+        }
+    }
+
+    /// Run each statement of a program in order.
+    pub fn run_program(&mut self, prog: &Program) -> Result<(), EvalError> {
+        for stmt in prog {
+            self.run_statement(stmt)?;
+        }
+        Ok(())
+    }
+
+    /// Run the modules of a program, which must be in dependency order. Each
+    /// module is run once, before the modules that import it.
+    pub fn run_modules(&mut self, modules: &[Module]) -> Result<(), ModuleError> {
+        let mut exports: Vec<Env> = vec![];
+        for module in modules {
+            self.globals = prelude();
+            self.modules = module
+                .imports
+                .iter()
+                .map(|(name, imported)| (name.clone(), exports[*imported].clone()))
+                .collect();
+            self.run_program(&module.program)?;
+            exports.push(Rc::new(std::mem::take(&mut self.exports)));
+        }
+        Ok(())
+    }
+
+    pub fn run_statement(&mut self, stmt: &Statement) -> Result<(), EvalError> {
+        match stmt {
+            // A top-level let without `in` binds its variable for the rest of
+            // the program
+            Statement::Expression(Expression::Let(varwithval, None, _)) => {
+                self.bind_globals(std::slice::from_ref(varwithval))?;
+            }
+            Statement::Expression(Expression::LetGroup(group, None, _)) => {
+                self.bind_globals(group)?;
+            }
+            Statement::Expression(expr) => {
+                let globals = self.globals.clone();
+                self.eval_expr(expr, &globals)?;
+            }
+            Statement::EnumDecl { name, variants, .. } => {
+                let variant_names = variants.iter().map(|v| v.0.clone()).collect();
+                self.enumerations.insert(name.0.clone(), variant_names);
+            }
+            // Aliases only matter to the type checker
+            Statement::TypeAlias { .. } => {}
+            Statement::StructDecl { span, .. }
+            | Statement::InterfaceDecl { span, .. }
+            | Statement::InterfaceImpl { span, .. } => {
+                return Err((
+                    "Structs and sigs are not supported by the interpreter yet".to_string(),
+                    span.clone(),
+                ));
+            }
+            Statement::Import { path, span } => {
+                let name = &path.last().unwrap().0;
+                let Some(members) = self.modules.get(name) else {
+                    return Err((format!("Unknown module {}", name), span.clone()));
+                };
+                let module = Value::Module(members.clone());
+                Rc::make_mut(&mut self.globals).insert(name.clone(), module);
+            }
+            Statement::Open {
+                module: (name, _),
+                span,
+            } => {
+                let Some(Value::Module(members)) = self.globals.get(name) else {
+                    return Err((
+                        format!("Module {} must be imported before it is opened", name),
+                        span.clone(),
+                    ));
+                };
+                let members = members.clone();
+                Rc::make_mut(&mut self.globals).extend(
+                    members
+                        .iter()
+                        .map(|(member, value)| (member.clone(), value.clone())),
+                );
+            }
+            // Fixities only matter to the parser
+            Statement::Fixity { .. } => {}
+            Statement::Error(msg, span) => return Err((msg.clone(), span.clone())),
+        }
+        Ok(())
+    }
+
+    /// Call the entry point of a program that has been run, with the
+    /// command-line arguments, returning the exit code. A program without
+    /// `main` exits with 0.
+    pub fn run_main(&mut self, prog: &Program, args: &[String]) -> Result<i32, EvalError> {
+        let Some(varwithval) = ast::main_binding(prog) else {
+            return Ok(0);
+        };
+        let span = varwithval.name.1.clone();
+        let main = self.globals[ast::MAIN].clone();
+        let args = Value::List(args.iter().cloned().map(Value::String).collect());
+        match self.apply(main, vec![args], span.clone())? {
+            Value::Int(code) => i32::try_from(code).map_err(|_| {
+                (
+                    format!("main returned {}, which is not a valid exit code", code),
+                    span,
+                )
+            }),
+            value => Err((
+                format!("Expected main to return an int, but got {}", value),
+                span,
+            )),
+        }
+    }
+
+    /// Bind the names of a top-level let in the globals and exports.
+    fn bind_globals(&mut self, group: &[VarWithValue]) -> Result<(), EvalError> {
+        let globals = self.globals.clone();
+        for (name, value) in self.eval_bindings(group, &globals)? {
+            Rc::make_mut(&mut self.globals).insert(name.clone(), value.clone());
+            self.exports.insert(name, value);
+        }
+        Ok(())
+    }
+
+    /// The value of a variable in the global environment.
+    pub fn global(&self, name: &str) -> Option<&Value> {
+        self.globals.get(name)
+    }
+
+    pub fn eval_expr(&mut self, expr: &Expression, env: &Env) -> Result<Value, EvalError> {
+        match expr {
+            Expression::Nothing(_) => Ok(Value::Nothing),
+            Expression::IntLiteral(i, _) => Ok(Value::Int(*i)),
+            Expression::FloatLiteral(f, _) => Ok(Value::Float(*f)),
+            Expression::StringLiteral(s, _) => Ok(Value::String(s.clone())),
+            Expression::List(exprs, _) => Ok(Value::List(self.eval_exprs(exprs, env)?)),
+            Expression::Tuple(exprs, _) => Ok(Value::Tuple(self.eval_exprs(exprs, env)?)),
+            Expression::ListComprehension {
+                expr: elem_expr,
+                clauses,
+                span: _,
+            } => {
+                let mut elems = vec![];
+                self.eval_comprehension(elem_expr, clauses, env, &mut elems)?;
+                Ok(Value::List(elems))
+            }
+            Expression::Record(fields, _, _) => {
+                let mut values = std::collections::BTreeMap::new();
+                for (name, (field_expr, _)) in fields {
+                    values.insert(name.clone(), self.eval_expr(field_expr, env)?);
+                }
+                Ok(Value::Record(values))
+            }
+            Expression::Identifier(name, _) => match env.get(name) {
+                Some(value) => Ok(value.clone()),
+                None => Err((format!("Unbound variable {}", name), expr.get_span())),
+            },
+            Expression::BinaryOp(bop, _) => Ok(builtin(Callable::BinaryOp(bop.clone()))),
+            Expression::Print(_) => Ok(builtin(Callable::Print)),
+            Expression::Fail(_) => Ok(builtin(Callable::Fail)),
+            Expression::Not(_) => Ok(builtin(Callable::Not)),
+            Expression::EnumVariant {
+                enum_id,
+                variant,
+                field,
+                span: _,
+            } => {
+                let field = self.eval_expr(field, env)?;
+                Ok(Value::Variant(
+                    enum_id.clone(),
+                    variant.clone(),
+                    Some(Box::new(field)),
+                ))
+            }
+            Expression::Projection(base, member, _) => self.eval_projection(base, member, env),
+            // The right operand of a connective is only evaluated if the left
+            // does not decide the result
+            Expression::FuncApplication(func, args, _)
+                if matches!(
+                    **func,
+                    Expression::BinaryOp(BinaryOp::And | BinaryOp::Or, _)
+                ) && args.len() == 2 =>
+            {
+                let lhs = self.eval_bool(&args[0], env)?;
+                match (&**func, lhs) {
+                    (Expression::BinaryOp(BinaryOp::And, _), false) => Ok(Value::from_bool(false)),
+                    (Expression::BinaryOp(BinaryOp::Or, _), true) => Ok(Value::from_bool(true)),
+                    _ => Ok(Value::from_bool(self.eval_bool(&args[1], env)?)),
+                }
+            }
+            Expression::FuncApplication(func, args, _) => {
+                let func_value = self.eval_expr(func, env)?;
+                let arg_values = self.eval_exprs(args, env)?;
+                self.apply(func_value, arg_values, expr.get_span())
+            }
+            Expression::Match {
+                matchand,
+                arms,
+                span: _,
+            } => {
+                let value = self.eval_expr(matchand, env)?;
+                for (pat, arm_expr, _) in arms {
+                    let mut arm_env = env.clone();
+                    if self.match_pattern(pat, &value, &mut arm_env)? {
+                        return self.eval_expr(arm_expr, &arm_env);
+                    }
+                }
+                Err((
+                    format!("No pattern matched the value {}", value),
+                    expr.get_span(),
+                ))
+            }
+            Expression::If {
+                cond,
+                then_expr,
+                else_expr,
+                span: _,
+            } => {
+                if self.eval_bool(cond, env)? {
+                    self.eval_expr(then_expr, env)
+                } else {
+                    self.eval_expr(else_expr, env)
+                }
+            }
+            Expression::Lambda {
+                arg, expr: body, ..
+            } => Ok(Value::Function(
+                Rc::new(Callable::Closure {
+                    params: vec![arg.clone()],
+                    body: body.clone(),
+                    env: env.clone(),
+                    group: None,
+                }),
+                vec![],
+            )),
+            Expression::Let(varwithval, in_expr, _) => {
+                self.eval_let(std::slice::from_ref(varwithval), in_expr, env)
+            }
+            Expression::LetGroup(group, in_expr, _) => self.eval_let(group, in_expr, env),
+            Expression::Block(exprs, result, _) => self.eval_block(exprs, result, env),
+            // `parse_program` resolves every chain
+            Expression::InfixChain(..) => Err((
+                "Infix expression was not resolved by precedence".to_string(),
+                expr.get_span(),
+            )),
+            Expression::MethodAccess(..)
+            | Expression::NamedArgsFuncApp(..)
+            | Expression::Set(..) => Err((
+                "Methods and named arguments are not supported by the interpreter yet".to_string(),
+                expr.get_span(),
+            )),
+        }
+    }
+
+    fn eval_exprs(&mut self, exprs: &[Expression], env: &Env) -> Result<Vec<Value>, EvalError> {
+        exprs.iter().map(|e| self.eval_expr(e, env)).collect()
+    }
+
+    /// Evaluate a boolean expression, such as a guard.
+    fn eval_bool(&mut self, expr: &Expression, env: &Env) -> Result<bool, EvalError> {
+        let value = self.eval_expr(expr, env)?;
+        value.as_bool().ok_or_else(|| {
+            (
+                format!("Expected a bool but got {}", value),
+                expr.get_span(),
+            )
+        })
+    }
+
+    /// Evaluate a let, binding each name of its group in the in expression.
+    /// Without an `in`, a let evaluates to the value bound last.
+    fn eval_let(
+        &mut self,
+        group: &[VarWithValue],
+        in_expr: &Option<Box<Expression>>,
+        env: &Env,
+    ) -> Result<Value, EvalError> {
+        let mut bindings = self.eval_bindings(group, env)?;
+        match in_expr {
+            Some(in_expr) => {
+                let mut in_env = env.clone();
+                Rc::make_mut(&mut in_env).extend(bindings);
+                self.eval_expr(in_expr, &in_env)
+            }
+            None => Ok(bindings.pop().unwrap().1),
+        }
+    }
+
+    /// Evaluate the expressions of a block in order. A let without `in` binds
+    /// its names in the block env, for the expressions after it.
+    fn eval_block(
+        &mut self,
+        exprs: &[Expression],
+        result: &Option<Box<Expression>>,
+        env: &Env,
+    ) -> Result<Value, EvalError> {
+        let mut block_env = env.clone();
+        for expr in exprs {
+            let group = match expr {
+                Expression::Let(varwithval, None, _) => std::slice::from_ref(varwithval),
+                Expression::LetGroup(group, None, _) => &group[..],
+                _ => {
+                    self.eval_expr(expr, &block_env)?;
+                    continue;
+                }
+            };
+            let bindings = self.eval_bindings(group, &block_env)?;
+            Rc::make_mut(&mut block_env).extend(bindings);
+        }
+        match result {
+            Some(result) => self.eval_expr(result, &block_env),
+            None => Ok(Value::Nothing),
+        }
+    }
+
+    /// The values bound by a group of let bindings, in order. Without `rec`,
+    /// every value is evaluated before any name of the group is bound. With
+    /// `rec`, the functions of the group are bound in the bodies of all of
+    /// them and in the values of the other members.
+    fn eval_bindings(
+        &mut self,
+        group: &[VarWithValue],
+        env: &Env,
+    ) -> Result<Vec<(String, Value)>, EvalError> {
+        if !group.iter().any(|varwithval| varwithval.rec) {
+            return group
+                .iter()
+                .map(|varwithval| {
+                    Ok((
+                        varwithval.name.0.clone(),
+                        self.eval_binding(varwithval, env)?,
+                    ))
+                })
+                .collect();
+        }
+
+        let functions = group.iter().filter_map(binding_function).collect();
+        let rec_group = Rc::new(RecGroup {
+            functions,
+            env: env.clone(),
+        });
+        let mut group_env = env.clone();
+        rec_group.bind_all(&mut group_env);
+
+        let mut bindings = vec![];
+        for varwithval in group {
+            let name = &varwithval.name.0;
+            let value = match binding_function(varwithval) {
+                Some(_) => group_env[name].clone(),
+                None => self.eval_expr(&varwithval.expr, &group_env)?,
+            };
+            bindings.push((name.clone(), value));
+        }
+        Ok(bindings)
+    }
+
+    /// The value bound by a let that is not recursive. Bindings with arguments
+    /// are functions.
+    fn eval_binding(&mut self, varwithval: &VarWithValue, env: &Env) -> Result<Value, EvalError> {
+        if varwithval.args.is_empty() {
+            return self.eval_expr(&varwithval.expr, env);
+        }
+        Ok(Value::Function(
+            Rc::new(Callable::Closure {
+                params: varwithval.args.iter().map(|a| a.0.clone()).collect(),
+                body: varwithval.expr.clone(),
+                env: env.clone(),
+                group: None,
+            }),
+            vec![],
+        ))
+    }
+
+    /// Evaluate the element expression of a list comprehension once for each
+    /// combination of generated values that passes the guards, in order.
+    fn eval_comprehension(
+        &mut self,
+        elem_expr: &Expression,
+        clauses: &[ComprehensionClause],
+        env: &Env,
+        elems: &mut Vec<Value>,
+    ) -> Result<(), EvalError> {
+        let Some((clause, rest)) = clauses.split_first() else {
+            elems.push(self.eval_expr(elem_expr, env)?);
+            return Ok(());
+        };
+        match clause {
+            ComprehensionClause::Generator(name, source, _) => {
+                let Value::List(values) = self.eval_expr(source, env)? else {
+                    return Err((
+                        format!("Generator for {} must draw from a list", name),
+                        source.get_span(),
+                    ));
+                };
+                for value in values {
+                    let mut inner_env = env.clone();
+                    Rc::make_mut(&mut inner_env).insert(name.clone(), value);
+                    self.eval_comprehension(elem_expr, rest, &inner_env, elems)?;
+                }
+            }
+            ComprehensionClause::Guard(guard) => {
+                if self.eval_bool(guard, env)? {
+                    self.eval_comprehension(elem_expr, rest, env, elems)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Evaluate `<base>.<member>`, which is either an enum variant without a
+    /// field, the field of a record, or the member of a module.
+    fn eval_projection(
+        &mut self,
+        base: &Expression,
+        member: &String,
+        env: &Env,
+    ) -> Result<Value, EvalError> {
+        if let Expression::Identifier(name, _) = base {
+            if !env.contains_key(name) {
+                if let Some(variants) = self.enumerations.get(name) {
+                    if !variants.contains(member) {
+                        return Err((
+                            format!("Enum {} has no variant {}", name, member),
+                            base.get_span(),
+                        ));
+                    }
+                    return Ok(Value::Variant(name.clone(), member.clone(), None));
+                }
+            }
+        }
+        match self.eval_expr(base, env)? {
+            Value::Record(mut fields) if fields.contains_key(member) => {
+                Ok(fields.remove(member).unwrap())
+            }
+            Value::Module(members) if members.contains_key(member) => {
+                Ok(members.get(member).unwrap().clone())
+            }
+            value => Err((
+                format!("Value {} has no field {}", value, member),
+                base.get_span(),
+            )),
+        }
+    }
+
+    /// Apply a function to arguments. Functions are curried, so they can be
+    /// given fewer arguments than they take, or more if they return a function.
+    pub fn apply(&mut self, func: Value, args: Vec<Value>, span: Span) -> Result<Value, EvalError> {
+        let Value::Function(callable, mut applied) = func else {
+            return Err((
+                format!("Cannot apply {}, which is not a function", func),
+                span,
+            ));
+        };
+        let arity = callable.arity();
+        let mut args = args.into_iter();
+        applied.extend(args.by_ref().take(arity - applied.len()));
+        if applied.len() < arity {
+            return Ok(Value::Function(callable, applied));
+        }
+
+        let result = self.call(&callable, applied, span.clone())?;
+        let rest = args.collect::<Vec<_>>();
+        if rest.is_empty() {
+            Ok(result)
+        } else {
+            self.apply(result, rest, span)
+        }
+    }
+
+    /// Call a callable with exactly as many arguments as it takes.
+    fn call(
+        &mut self,
+        callable: &Rc<Callable>,
+        args: Vec<Value>,
+        span: Span,
+    ) -> Result<Value, EvalError> {
+        match &**callable {
+            Callable::Closure {
+                params,
+                body,
+                env,
+                group,
+            } => {
+                let mut call_env = env.clone();
+                if let Some(group) = group {
+                    group.bind_all(&mut call_env);
+                }
+                Rc::make_mut(&mut call_env).extend(params.iter().cloned().zip(args));
+                self.eval_expr(body, &call_env)
+            }
+            Callable::BinaryOp(bop) => {
+                let mut args = args.into_iter();
+                let (a, b) = (args.next().unwrap(), args.next().unwrap());
+                match bop {
+                    BinaryOp::Pipe => self.apply(b, vec![a], span),
+                    BinaryOp::ComposeForward => Ok(builtin(Callable::Compose(a, b))),
+                    BinaryOp::ComposeBackward => Ok(builtin(Callable::Compose(b, a))),
+                    _ => eval_binary_op(bop, a, b).map_err(|msg| (msg, span)),
+                }
+            }
+            Callable::Compose(first, second) => {
+                let result = self.apply(first.clone(), args, span.clone())?;
+                self.apply(second.clone(), vec![result], span)
+            }
+            Callable::Print => {
+                writeln!(self.output, "{}", args[0].to_output())
+                    .map_err(|e| (format!("Failed to print: {}", e), span))?;
+                Ok(Value::Nothing)
+            }
+            Callable::Fail => Err((args[0].to_output(), span)),
+            Callable::Not => match args[0].as_bool() {
+                Some(b) => Ok(Value::from_bool(!b)),
+                None => Err((format!("Expected a bool but got {}", args[0]), span)),
+            },
+        }
+    }
+
+    /// Match a value against a pattern, binding the variables of the pattern
+    /// in `env` if it matches.
+    fn match_pattern(
+        &mut self,
+        pat: &Pattern,
+        value: &Value,
+        env: &mut Env,
+    ) -> Result<bool, EvalError> {
+        let matched = match (pat, value) {
+            (Pattern::Wildcard(_), _) => true,
+            (Pattern::IntLiteral(i, _), Value::Int(j)) => i == j,
+            (Pattern::FloatLiteral(x, _), Value::Float(y)) => x == y,
+            (Pattern::StringLiteral(s, _), Value::String(t)) => s == t,
+            // Variants without fields are parsed as identifiers
+            (Pattern::Identifier(name, _), _) if self.is_variant(name) => {
+                matches!(value, Value::Variant(_, variant, None) if variant == name)
+            }
+            (Pattern::Identifier(name, _), _) => {
+                Rc::make_mut(env).insert(name.clone(), value.clone());
+                true
+            }
+            (Pattern::TypeVariant(name, field_pat, _), Value::Variant(_, variant, field)) => {
+                if name != variant {
+                    false
+                } else {
+                    match (field_pat, field) {
+                        (None, None) => true,
+                        (Some(field_pat), Some(field)) => {
+                            self.match_pattern(field_pat, field, env)?
+                        }
+                        _ => false,
+                    }
+                }
+            }
+            (Pattern::ListCons(head, tail, _), Value::List(values)) if !values.is_empty() => {
+                let vars = Rc::make_mut(env);
+                vars.insert(head.clone(), values[0].clone());
+                vars.insert(tail.clone(), Value::List(values[1..].to_vec()));
+                true
+            }
+            (Pattern::EmptyList(_), Value::List(values)) => values.is_empty(),
+            (Pattern::List(pats, _), Value::List(values))
+            | (Pattern::Tuple(pats, _), Value::Tuple(values)) => {
+                pats.len() == values.len() && self.match_all(pats.iter().zip(values), env)?
+            }
+            (Pattern::Record(field_pats, flex, _), Value::Record(fields)) => {
+                let fields_match = match flex {
+                    Flex::Exact | Flex::CollectExact => field_pats.len() == fields.len(),
+                    Flex::Permissive => true,
+                };
+                let mut pairs = vec![];
+                for (name, (field_pat, _)) in field_pats {
+                    match fields.get(name) {
+                        Some(field) => pairs.push((field_pat, field)),
+                        None => return Ok(false),
+                    }
+                }
+                fields_match && self.match_all(pairs, env)?
+            }
+            (Pattern::Union(pats, _), _) => {
+                for pat in pats {
+                    if self.match_pattern(pat, value, env)? {
+                        return Ok(true);
+                    }
+                }
+                false
+            }
+            (Pattern::Complement(pat, _), _) => {
+                // Nothing is bound by a pattern that did not match
+                let mut scratch_env = env.clone();
+                !self.match_pattern(pat, value, &mut scratch_env)?
+            }
+            (
+                Pattern::Guarded {
+                    pattern,
+                    guard,
+                    span: _,
+                },
+                _,
+            ) => {
+                let mut guard_env = env.clone();
+                if self.match_pattern(pattern, value, &mut guard_env)?
+                    && self.eval_bool(guard, &guard_env)?
+                {
+                    *env = guard_env;
+                    true
+                } else {
+                    false
+                }
+            }
+            (Pattern::Case(cond, _), _) => self.eval_bool(cond, env)?,
+            _ => false,
+        };
+        Ok(matched)
+    }
+
+    /// Match each value against its pattern, binding variables only if all match.
+    fn match_all<'a, I>(&mut self, pairs: I, env: &mut Env) -> Result<bool, EvalError>
+    where
+        I: IntoIterator<Item = (&'a Pattern, &'a Value)>,
+    {
+        let mut inner_env = env.clone();
+        for (pat, value) in pairs {
+            if !self.match_pattern(pat, value, &mut inner_env)? {
+                return Ok(false);
+            }
+        }
+        *env = inner_env;
+        Ok(true)
+    }
+
+    fn is_variant(&self, name: &String) -> bool {
+        self.enumerations
+            .values()
+            .any(|variants| variants.contains(name))
+    }
+}
+
+/// The variables every module starts with: the variants of bool, which can be
+/// written without naming the enum.
+fn prelude() -> Env {
+    Rc::new(HashMap::from([
+        ("true".to_string(), Value::from_bool(true)),
+        ("false".to_string(), Value::from_bool(false)),
+    ]))
+}
+
+/// The name, parameters, and body of a binding that defines a function,
+/// either with arguments or as a lambda.
+fn binding_function(varwithval: &VarWithValue) -> Option<(String, Vec<String>, Box<Expression>)> {
+    let name = varwithval.name.0.clone();
+    if !varwithval.args.is_empty() {
+        let params = varwithval.args.iter().map(|a| a.0.clone()).collect();
+        return Some((name, params, varwithval.expr.clone()));
+    }
+    match &*varwithval.expr {
+        Expression::Lambda { arg, expr, .. } => Some((name, vec![arg.clone()], expr.clone())),
+        _ => None,
+    }
+}
+
+fn builtin(callable: Callable) -> Value {
+    Value::Function(Rc::new(callable), vec![])
+}
+
+/// Evaluate a builtin binary operation on two values.
+fn eval_binary_op(bop: &BinaryOp, a: Value, b: Value) -> Result<Value, String> {
+    let result = match (bop, &a, &b) {
+        (BinaryOp::Add, Value::Int(x), Value::Int(y)) => x.checked_add(*y).map(Value::Int),
+        (BinaryOp::Sub, Value::Int(x), Value::Int(y)) => x.checked_sub(*y).map(Value::Int),
+        (BinaryOp::Mult, Value::Int(x), Value::Int(y)) => x.checked_mul(*y).map(Value::Int),
+        (BinaryOp::FloorDiv, Value::Int(_), Value::Int(0)) => {
+            return Err("Division by zero".to_string())
+        }
+        (BinaryOp::FloorDiv, Value::Int(x), Value::Int(y)) => {
+            x.checked_div(*y).map(|q| {
+                // Round towards negative infinity rather than zero
+                if x % y != 0 && (*x < 0) != (*y < 0) {
+                    Value::Int(q - 1)
+                } else {
+                    Value::Int(q)
+                }
+            })
+        }
+        (BinaryOp::FlAdd, Value::Float(x), Value::Float(y)) => Some(Value::Float(*x + *y)),
+        (BinaryOp::FlSub, Value::Float(x), Value::Float(y)) => Some(Value::Float(*x - *y)),
+        (BinaryOp::FlMult, Value::Float(x), Value::Float(y)) => Some(Value::Float(*x * *y)),
+        (BinaryOp::Div, Value::Float(x), Value::Float(y)) => Some(Value::Float(*x / *y)),
+        (BinaryOp::Lt | BinaryOp::Gt | BinaryOp::Leq | BinaryOp::Geq, _, _) => {
+            let ordering =
+                compare(&a, &b).ok_or_else(|| format!("Cannot compare {} and {}", a, b))?;
+            let result = match bop {
+                BinaryOp::Lt => ordering == Ordering::Less,
+                BinaryOp::Gt => ordering == Ordering::Greater,
+                BinaryOp::Leq => ordering != Ordering::Greater,
+                _ => ordering != Ordering::Less,
+            };
+            Some(Value::from_bool(result))
+        }
+        (BinaryOp::And | BinaryOp::Or, _, _) => match (a.as_bool(), b.as_bool()) {
+            (Some(x), Some(y)) if *bop == BinaryOp::And => Some(Value::from_bool(x && y)),
+            (Some(x), Some(y)) => Some(Value::from_bool(x || y)),
+            _ => return Err(format!("Invalid operands {} and {} for {:?}", a, b, bop)),
+        },
+        (BinaryOp::Eq, _, _) => Some(Value::from_bool(a == b)),
+        (BinaryOp::Neq, _, _) => Some(Value::from_bool(a != b)),
+        (BinaryOp::Cons, _, Value::List(tail)) => {
+            let mut values = vec![a.clone()];
+            values.extend(tail.iter().cloned());
+            Some(Value::List(values))
+        }
+        _ => return Err(format!("Invalid operands {} and {} for {:?}", a, b, bop)),
+    };
+    result.ok_or_else(|| format!("Integer overflow in {:?} of {} and {}", bop, a, b))
+}
+
+/// Order two values of the same primitive type.
+fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Int(x), Value::Int(y)) => Some(x.cmp(y)),
+        (Value::Float(x), Value::Float(y)) => Some(x.cmp(y)),
+        (Value::String(x), Value::String(y)) => Some(x.cmp(y)),
+        _ => None,
+    }
+}
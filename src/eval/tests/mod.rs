@@ -0,0 +1,4 @@
+use super::*;
+
+#[cfg(test)]
+mod test_eval;
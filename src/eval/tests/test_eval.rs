@@ -0,0 +1,295 @@
+use super::value::Value;
+use super::*;
+use crate::module::load_program;
+use crate::module::tests::fixture;
+use crate::parse::parse_program;
+
+/// Run a program, returning the interpreter and what the program printed.
+fn run(inp: &str) -> (Result<(), EvalError>, Interpreter<Vec<u8>>) {
+    let (prog, parse_errors) = parse_program(0, inp);
+    assert!(parse_errors.is_empty(), "{:?}", parse_errors);
+    let mut interpreter = Interpreter::new(vec![]);
+    let res = interpreter.run_program(&prog);
+    (res, interpreter)
+}
+
+/// Run a program, and return the value it binds to `result`.
+fn eval_result(inp: &str) -> Value {
+    let (res, interpreter) = run(inp);
+    assert!(res.is_ok(), "{:?}", res);
+    interpreter.global("result").unwrap().clone()
+}
+
+fn ints(values: &[i64]) -> Value {
+    Value::List(values.iter().map(|i| Value::Int(*i)).collect())
+}
+
+#[test]
+fn test_eval_expr() {
+    assert!(eval_result("let result = 1 + 2 * 3;") == Value::Int(7));
+    assert!(eval_result("let result = -7 / 2;") == Value::Int(-4));
+    assert!(eval_result("let f x y = x - y; let result = f 10 4;") == Value::Int(6));
+    // Partial application
+    assert!(eval_result("let f x y = x - y; let g = f 10; let result = g 3;") == Value::Int(7));
+    assert!(eval_result("let result = (\\x y -> x :: y) 1 [2];") == ints(&[1, 2]));
+    assert!(eval_result("let result = 1 :: 2 :: [3];") == ints(&[1, 2, 3]));
+    assert!(
+        eval_result("let result = (let x = 4 in (x, x));")
+            == Value::Tuple(vec![Value::Int(4), Value::Int(4)])
+    );
+    assert!(
+        eval_result(
+            "let rec fact n = match n { 0 => 1, n => n * (fact (n - 1)) }; let result = fact 5;"
+        ) == Value::Int(120)
+    );
+    assert!(eval_result("let r = {a: 1, b: \"b\"}; let result = r.a;") == Value::Int(1));
+    assert!(eval_result("let result = 1 < 2;") == Value::from_bool(true));
+
+    let (res, interpreter) = run("print \"hi\"; print [1.5, 2.0]; print (1, \"a\");");
+    assert!(res.is_ok());
+    assert!(String::from_utf8(interpreter.output).unwrap() == "hi\n[1.5, 2.0]\n(1, \"a\")\n");
+
+    assert!(run("fail \"oh no\";").0.unwrap_err().0 == "oh no");
+    assert!(run("1 / 0;").0.unwrap_err().0 == "Division by zero");
+    assert!(run("x;").0.unwrap_err().0 == "Unbound variable x");
+}
+
+#[test]
+fn test_eval_let_rec() {
+    let even_odd = "let rec even n = match n { 0 => bool.true, n => odd (n - 1) } \
+        and odd n = match n { 0 => bool.false, n => even (n - 1) };";
+    assert!(eval_result(&format!("{} let result = even 10;", even_odd)) == Value::from_bool(true));
+    assert!(eval_result(&format!("{} let result = odd 10;", even_odd)) == Value::from_bool(false));
+    // Members defined as lambdas can refer to the group too
+    assert!(
+        eval_result(
+            "let rec count = \\n -> match n { 0 => [], n => n :: (count (n - 1)) }; let result = count 3;"
+        ) == ints(&[3, 2, 1])
+    );
+    assert!(
+        eval_result(
+            "let result = (let rec f n = match n { 0 => 0, n => g n } and g n = f (n - 1) in f 5);"
+        ) == Value::Int(0)
+    );
+    // Without `rec`, members are bound after all of them are evaluated
+    assert!(eval_result("let x = 1; let result = (let x = 2 and y = x in y);") == Value::Int(1));
+    assert!(
+        run("let loop n = loop n; let result = loop 1;")
+            .0
+            .unwrap_err()
+            .0
+            == "Unbound variable loop"
+    );
+}
+
+#[test]
+fn test_eval_operators() {
+    let digits = "infixl 6 <+>; let (<+>) a b = a * 10 + b;";
+    assert!(eval_result(&format!("{} let result = 1 <+> 2 <+> 3;", digits)) == Value::Int(123));
+    assert!(eval_result(&format!("{} let result = 1 <+> 2 * 3;", digits)) == Value::Int(16));
+    assert!(
+        eval_result(&format!(
+            "{} let result = [(<+> 1) 2, (2 <+>) 1, (<+>) 3 4];",
+            digits
+        )) == ints(&[21, 21, 34])
+    );
+    assert!(
+        eval_result("infixr 5 ^^; let (^^) a b = a - b; let result = 10 ^^ 4 ^^ 1;")
+            == Value::Int(7)
+    );
+}
+
+#[test]
+fn test_eval_if() {
+    assert!(eval_result("let result = if 1 < 2 then 1 else 2;") == Value::Int(1));
+    assert!(eval_result("let result = if 1 > 2 then 1 else 2;") == Value::Int(2));
+    assert!(
+        eval_result(
+            "let rec fact n = if n == 0 then 1 else n * (fact (n - 1)); let result = fact 5;"
+        ) == Value::Int(120)
+    );
+    assert!(
+        eval_result("let result = [not (1 < 2), 1 < 2 && 2 < 3, 1 > 2 || 2 > 3];")
+            == Value::List(vec![
+                Value::from_bool(false),
+                Value::from_bool(true),
+                Value::from_bool(false)
+            ])
+    );
+    // The variants of bool can be written with or without the enum's name
+    assert!(eval_result("let result = if true then 1 else 2;") == Value::Int(1));
+    assert!(eval_result("let result = if false then 1 else 2;") == Value::Int(2));
+    assert!(
+        eval_result("let result = [true && false, false || true, true && bool.true];")
+            == Value::List(vec![
+                Value::from_bool(false),
+                Value::from_bool(true),
+                Value::from_bool(true)
+            ])
+    );
+    assert!(eval_result("let result = false && (fail \"no\");") == Value::from_bool(false));
+    // Only the chosen branch, and the operands that decide the result, are evaluated
+    assert!(eval_result("let result = if 1 < 2 then 1 else fail \"no\";") == Value::Int(1));
+    assert!(eval_result("let result = 1 > 2 && (fail \"no\");") == Value::from_bool(false));
+    assert!(eval_result("let result = 1 < 2 || (fail \"no\");") == Value::from_bool(true));
+    assert!(eval_result("let result = (||) (1 < 2) (1 > 2);") == Value::from_bool(true));
+    assert!(
+        run("let result = 1 < 2 && (fail \"yes\");")
+            .0
+            .unwrap_err()
+            .0
+            == "yes"
+    );
+}
+
+#[test]
+fn test_eval_block() {
+    let (res, interpreter) =
+        run("let x = 1; let result = { print x; let x = x + 1; print x; x * 10 }; print x;");
+    assert!(res.is_ok(), "{:?}", res);
+    assert!(interpreter.global("result") == Some(&Value::Int(20)));
+    assert!(String::from_utf8(interpreter.output).unwrap() == "1\n2\n1\n");
+    assert!(eval_result("let result = { print 1; };") == Value::Nothing);
+    assert!(
+        eval_result("let result = { let rec f n = if n == 0 then 0 else f (n - 1); f 3 };")
+            == Value::Int(0)
+    );
+    assert!(
+        run("let result = { let y = 1; y }; let z = y;")
+            .0
+            .unwrap_err()
+            .0
+            == "Unbound variable y"
+    );
+}
+
+/// Run a program, then call its main with `args`.
+fn run_main(inp: &str, args: &[&str]) -> Result<i32, EvalError> {
+    let (prog, parse_errors) = parse_program(0, inp);
+    assert!(parse_errors.is_empty(), "{:?}", parse_errors);
+    let mut interpreter = Interpreter::new(vec![]);
+    interpreter.run_program(&prog)?;
+    let args = args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+    interpreter.run_main(&prog, &args)
+}
+
+#[test]
+fn test_eval_main() {
+    let count = "let rec length xs = match xs { [] => 0, x :: rest => 1 + (length rest) };";
+    assert!(
+        run_main(
+            &format!("{} let main args = length args;", count),
+            &["a", "b"]
+        ) == Ok(2)
+    );
+    assert!(
+        run_main(
+            "let main args = match args { [\"hi\"] => 0, _ => 3 };",
+            &["hi"]
+        ) == Ok(0)
+    );
+    // Without main, the program exits successfully once it has run
+    assert!(run_main("let x = 1;", &[]) == Ok(0));
+
+    assert!(
+        run_main("let main args = 1.0;", &[])
+            == Err((
+                "Expected main to return an int, but got 1.0".to_string(),
+                Span::new(0, 4, 8)
+            ))
+    );
+    assert!(run_main("let main args = 99999999999;", &[])
+        .unwrap_err()
+        .0
+        .contains("not a valid exit code"));
+    assert!(run_main("let main = 0;", &[])
+        .unwrap_err()
+        .0
+        .contains("not a function"));
+}
+
+#[test]
+fn test_eval_pipeline() {
+    let defs = "let inc x = x + 1; let double x = x * 2;";
+    assert!(eval_result(&format!("{} let result = 3 |> inc |> double;", defs)) == Value::Int(8));
+    assert!(eval_result(&format!("{} let result = (inc >> double) 3;", defs)) == Value::Int(8));
+    assert!(eval_result(&format!("{} let result = (inc << double) 3;", defs)) == Value::Int(7));
+    assert!(
+        eval_result(&format!("{} let result = 3 |> inc >> double >> inc;", defs)) == Value::Int(9)
+    );
+    assert!(eval_result(&format!("{} let result = 3 |> ((+) 1);", defs)) == Value::Int(4));
+    assert!(eval_result(&format!("{} let result = 3 |> (+) 1 >> double;", defs)) == Value::Int(8));
+    assert!(
+        eval_result(&format!(
+            "{} let map f xs = [f x | x <- xs]; let result = [1, 2] |> map inc;",
+            defs
+        )) == Value::List(vec![Value::Int(2), Value::Int(3)])
+    );
+}
+
+#[test]
+fn test_eval_match() {
+    assert!(
+        eval_result("let result = match [1, 2, 3] { [] => 0, x :: xs => x };") == Value::Int(1)
+    );
+    assert!(
+        eval_result("enum Opt = Some with int | Empty; let result = match (Opt.Some with 3) { Empty => 0, Some with x => x };")
+            == Value::Int(3)
+    );
+    assert!(
+        eval_result(
+            "let result = match (1, 2) { (a, 1) => a, (a, b) if a > b => 0, (a, b) => b };"
+        ) == Value::Int(2)
+    );
+    assert!(
+        eval_result("let result = match bool.true { true => 1, false => 0 };") == Value::Int(1)
+    );
+    assert!(
+        run("match 3 { 1 => 1, 2 => 2 };").0.unwrap_err().0 == "No pattern matched the value 3"
+    );
+}
+
+#[test]
+fn test_eval_list_comprehension() {
+    assert!(eval_result("let result = [x * x | x <- [1, 2, 3]];") == ints(&[1, 4, 9]));
+    assert!(
+        eval_result("let f x = x + 1; let result = [f x | x <- [3, -1, 0, 2], x > 0];")
+            == ints(&[4, 3])
+    );
+    // Later generators vary fastest, and may depend on earlier ones
+    assert!(
+        eval_result("let result = [(x, y) | x <- [1, 2, 3], y <- [x, 10], x != 2];")
+            == Value::List(vec![
+                Value::Tuple(vec![Value::Int(1), Value::Int(1)]),
+                Value::Tuple(vec![Value::Int(1), Value::Int(10)]),
+                Value::Tuple(vec![Value::Int(3), Value::Int(3)]),
+                Value::Tuple(vec![Value::Int(3), Value::Int(10)]),
+            ])
+    );
+    assert!(eval_result("let result = [x | x <- []];") == ints(&[]));
+    assert!(
+        eval_result("let xss = [[1], [2, 3]]; let result = [x | xs <- xss, x <- xs];")
+            == ints(&[1, 2, 3])
+    );
+
+    assert!(run("[x | x <- 4];").0.unwrap_err().0 == "Generator for x must draw from a list");
+    assert!(run("[x | x <- [1], 4];").0.unwrap_err().0 == "Expected a bool but got 4");
+}
+
+#[test]
+fn test_eval_modules() {
+    let (_, modules, load_errors) = load_program(&fixture("main.wye")).unwrap();
+    assert!(load_errors.is_empty());
+    let mut interpreter = Interpreter::new(vec![]);
+    assert!(interpreter.run_modules(&modules).is_ok());
+    assert!(String::from_utf8(interpreter.output).unwrap() == "[2, 3]\n[\"hello\", \"wye\"]\n");
+
+    let (_, modules, _) = load_program(&fixture("cycle/a.wye")).unwrap();
+    let err = Interpreter::new(vec![]).run_modules(&modules).unwrap_err();
+    assert!(
+        err == (
+            "Unknown module a".to_string(),
+            Span::new(modules[0].file, 0, 8)
+        )
+    );
+}
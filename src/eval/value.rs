@@ -0,0 +1,155 @@
+use crate::parse::ast::{BinaryOp, Expression};
+use ordered_float::OrderedFloat;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::rc::Rc;
+
+/// Variables in scope during evaluation. Closures capture the environment
+/// they were created in, so it is shared, and copied only when extended.
+pub type Env = Rc<HashMap<String, Value>>;
+
+/// The result of evaluating an expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Nothing,
+    Int(i64),
+    Float(OrderedFloat<f64>),
+    String(String),
+    List(Vec<Value>),
+    Tuple(Vec<Value>),
+    Record(BTreeMap<String, Value>),
+    // Enum name, variant name, and field
+    Variant(String, String, Option<Box<Value>>),
+    // A function, along with the arguments it has been partially applied to
+    Function(Rc<Callable>, Vec<Value>),
+    // The members of an imported module
+    Module(Env),
+}
+
+/// Something that can be applied to arguments.
+#[derive(Debug, PartialEq)]
+pub enum Callable {
+    // A function defined in the program. Functions bound by `let rec` can
+    // refer to every function of their group in their body.
+    Closure {
+        params: Vec<String>,
+        body: Box<Expression>,
+        env: Env,
+        group: Option<Rc<RecGroup>>,
+    },
+    BinaryOp(BinaryOp),
+    // The composition of two functions, which applies the first then the second
+    Compose(Value, Value),
+    Print,
+    Fail,
+    Not,
+}
+
+/// The functions bound together by a `let rec`, along with the environment
+/// they were defined in.
+#[derive(Debug, PartialEq)]
+pub struct RecGroup {
+    // Name, parameters, and body of each function
+    pub functions: Vec<(String, Vec<String>, Box<Expression>)>,
+    pub env: Env,
+}
+
+impl RecGroup {
+    /// Bind every function of the group in `env`.
+    pub fn bind_all(self: &Rc<Self>, env: &mut Env) {
+        let vars = Rc::make_mut(env);
+        for (name, params, body) in &self.functions {
+            let closure = Callable::Closure {
+                params: params.clone(),
+                body: body.clone(),
+                env: self.env.clone(),
+                group: Some(self.clone()),
+            };
+            vars.insert(name.clone(), Value::Function(Rc::new(closure), vec![]));
+        }
+    }
+}
+
+impl Callable {
+    /// The number of arguments needed before the callable is evaluated.
+    pub fn arity(&self) -> usize {
+        match self {
+            Self::Closure { params, .. } => params.len(),
+            Self::BinaryOp(_) => 2,
+            Self::Compose(..) | Self::Print | Self::Fail | Self::Not => 1,
+        }
+    }
+}
+
+impl Value {
+    pub fn from_bool(b: bool) -> Self {
+        Self::Variant("bool".to_string(), b.to_string(), None)
+    }
+
+    /// The boolean this value represents, if it is a variant of bool.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Variant(enum_id, variant, None) if enum_id == "bool" => match variant.as_str() {
+                "true" => Some(true),
+                "false" => Some(false),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// The text written by `print`. Unlike `Display`, top-level strings are
+    /// written without quotes.
+    pub fn to_output(&self) -> String {
+        match self {
+            Self::String(s) => s.clone(),
+            _ => self.to_string(),
+        }
+    }
+}
+
+/// Write a sequence of values separated by commas.
+fn write_seq(f: &mut fmt::Formatter, values: &[Value]) -> fmt::Result {
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{}", value)?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Nothing => write!(f, "nothing"),
+            Self::Int(i) => write!(f, "{}", i),
+            Self::Float(x) => write!(f, "{:?}", x.0),
+            Self::String(s) => write!(f, "{:?}", s),
+            Self::List(values) => {
+                write!(f, "[")?;
+                write_seq(f, values)?;
+                write!(f, "]")
+            }
+            Self::Tuple(values) => {
+                write!(f, "(")?;
+                write_seq(f, values)?;
+                write!(f, ")")
+            }
+            Self::Record(fields) => {
+                write!(f, "{{")?;
+                for (i, (name, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", name, value)?;
+                }
+                write!(f, "}}")
+            }
+            Self::Variant(_, variant, None) => write!(f, "{}", variant),
+            Self::Variant(_, variant, Some(field)) => write!(f, "{} with {}", variant, field),
+            Self::Function(_, _) => write!(f, "<function>"),
+            Self::Module(_) => write!(f, "<module>"),
+        }
+    }
+}
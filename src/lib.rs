@@ -1,3 +1,5 @@
+pub mod eval;
+pub mod module;
 pub mod parse;
 pub mod types;
 
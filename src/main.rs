@@ -1,33 +1,104 @@
+use codespan_reporting::diagnostic::{Diagnostic, Label, Severity};
+use codespan_reporting::files::SimpleFiles;
+use codespan_reporting::term;
+use codespan_reporting::term::termcolor::{ColorChoice, StandardStream};
 use std::env;
-use std::fs;
 use std::path::Path;
-use wye::parse::grammar;
+use wye::eval::Interpreter;
+use wye::module::{self, ModuleError};
+use wye::parse::span::SourceMap;
 use wye::types::check;
 
+/// Print each error along with the source it points to, in the module it
+/// occurred in.
+fn report_errors(sources: &SourceMap, errors: &[ModuleError]) {
+    report(sources, errors, Severity::Error);
+}
+
+/// Print each diagnostic with the given severity, along with the source it
+/// points to.
+fn report(sources: &SourceMap, diagnostics: &[ModuleError], severity: Severity) {
+    // Files are added in order, so their ids match the ids in the source map
+    let mut files = SimpleFiles::new();
+    for file in sources.files() {
+        files.add(file.path.display().to_string(), &file.text);
+    }
+    let writer = StandardStream::stderr(ColorChoice::Auto);
+    let config = term::Config::default();
+
+    for (msg, span) in diagnostics {
+        let diagnostic = Diagnostic::new(severity)
+            .with_message(msg)
+            .with_labels(vec![Label::primary(span.file, span.start..span.end)]);
+        term::emit(&mut writer.lock(), &config, &files, &diagnostic)
+            .expect("Failed to write error");
+    }
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
-    if args.len() != 3 {
-        println!("usage: cargo run <command> <path to wye file>");
+    if args.len() < 3 || args.get(3).is_some_and(|sep| sep != "--") {
+        println!("usage: cargo run <command> <path to wye file> [-- <program arguments>]");
         std::process::exit(1);
     }
+    // Arguments after `--` are passed to the program's main
+    let program_args = args.get(4..).unwrap_or(&[]);
 
-    let wye_program =
-        fs::read_to_string(Path::new(&args[2])).expect("Failed to read provided file");
+    let path = &args[2];
     let action = &args[1];
 
-    let parser: grammar::ProgramParser = grammar::ProgramParser::new();
+    // Load the file along with every module it imports
+    let (sources, modules, load_errors) =
+        module::load_program(Path::new(path)).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        });
 
     match action.as_str() {
         "parse" | "p" => {
-            println!("{:?}", parser.parse(wye_program.as_str()));
+            println!("{:?}", modules.last().unwrap().program);
         }
         "typecheck" | "tc" => {
-            let parsed_prog = parser.parse(wye_program.as_str()).unwrap();
-            println!("{:?}", check::type_check_program(&parsed_prog));
+            // Statements that failed to parse are skipped by the checker
+            let result = check::type_check_modules(&modules);
+            if let Ok(warnings) = &result {
+                report(&sources, warnings, Severity::Warning);
+            }
+            println!("{:?}", result.map(|_| ()));
+        }
+        "run" | "r" => {
+            if load_errors.is_empty() {
+                // Only programs that are well typed are run
+                match check::type_check_modules(&modules) {
+                    Ok(warnings) => report(&sources, &warnings, Severity::Warning),
+                    Err(errors) => {
+                        report_errors(&sources, &errors);
+                        std::process::exit(1);
+                    }
+                }
+                let mut interpreter = Interpreter::new(std::io::stdout());
+                if let Err(err) = interpreter.run_modules(&modules) {
+                    report_errors(&sources, &[err]);
+                    std::process::exit(1);
+                }
+                let program = &modules.last().unwrap().program;
+                match interpreter.run_main(program, program_args) {
+                    Ok(code) => std::process::exit(code),
+                    Err(err) => {
+                        report_errors(&sources, &[err]);
+                        std::process::exit(1);
+                    }
+                }
+            }
         }
         _ => {
             println!("Unknown or unimplemented language action: {}", action);
             std::process::exit(1);
         }
     }
+
+    if !load_errors.is_empty() {
+        report_errors(&sources, &load_errors);
+        std::process::exit(1);
+    }
 }
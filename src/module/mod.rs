@@ -0,0 +1,170 @@
+//! Loading programs that are split across multiple files.
+//!
+//! Each `.wye` file is a module. A module imports another with
+//! `import <Id>(.<Id>)*`, where the path is resolved relative to the directory
+//! of the importing file, so `import util.List` in `src/main.wye` loads
+//! `src/util/List.wye` and binds it to `List`.
+use crate::parse::ast::{Program, Statement};
+use crate::parse::parse_program;
+use crate::parse::span::{FileId, SourceMap, Span};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[cfg(test)]
+pub(crate) mod tests;
+
+pub struct Module {
+    /// The name the module is bound to when imported
+    pub name: String,
+    /// The file of the module in the source map of the program
+    pub file: FileId,
+    pub program: Program,
+    /// Map from the names this module imports to their index in the list of
+    /// modules of the program
+    pub imports: HashMap<String, usize>,
+}
+
+/// An error in a module. The file of its span is the file of the module.
+pub type ModuleError = (String, Span);
+
+/// Load the module at `path` along with every module it imports,
+/// transitively. Modules are returned in dependency order, so each module
+/// comes after the modules it imports, and the module at `path` is last.
+/// Every module is loaded once, however many times it is imported.
+///
+/// The text of every module is returned in a source map, which the spans of
+/// the modules point into. Syntax errors and errors in imports are collected
+/// rather than stopping the loading of other modules. Only failing to read
+/// `path` itself is fatal.
+pub fn load_program(path: &Path) -> Result<(SourceMap, Vec<Module>, Vec<ModuleError>), String> {
+    let path = path
+        .canonicalize()
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let source = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    let mut loader = Loader {
+        sources: SourceMap::new(),
+        modules: vec![],
+        errors: vec![],
+        loaded: HashMap::new(),
+        loading: vec![],
+    };
+    loader.load(module_name(&path), path, source);
+    loader
+        .errors
+        .sort_by(|(_, span1), (_, span2)| span1.cmp(span2));
+    Ok((loader.sources, loader.modules, loader.errors))
+}
+
+/// The name of the module in a file, which is the name of the file without
+/// its extension.
+fn module_name(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+struct Loader {
+    sources: SourceMap,
+    modules: Vec<Module>,
+    errors: Vec<ModuleError>,
+    /// Map from the path of each module loaded so far to its index
+    loaded: HashMap<PathBuf, usize>,
+    /// Paths of the modules currently being loaded, each one imported by the
+    /// one before it
+    loading: Vec<PathBuf>,
+}
+
+impl Loader {
+    /// Load a module and the modules it imports, returning its index.
+    fn load(&mut self, name: String, path: PathBuf, source: String) -> usize {
+        let file = self.sources.add(path.clone(), source);
+        let (program, mut errors) = parse_program(file, self.sources.text(file));
+
+        self.loading.push(path.clone());
+        let mut imports = HashMap::new();
+        for stmt in &program {
+            let Statement::Import {
+                path: import_path,
+                span,
+            } = stmt
+            else {
+                continue;
+            };
+            let span = span.clone();
+            let import_name = import_path.last().unwrap().0.clone();
+            if imports.contains_key(&import_name) {
+                errors.push((format!("Module {} is already imported", import_name), span));
+                continue;
+            }
+            match self.resolve(&path, import_path) {
+                Ok(index) => {
+                    imports.insert(import_name, index);
+                }
+                Err(msg) => errors.push((msg, span)),
+            }
+        }
+        self.loading.pop();
+
+        let index = self.modules.len();
+        self.modules.push(Module {
+            name,
+            file,
+            program,
+            imports,
+        });
+        self.loaded.insert(path, index);
+        self.errors.extend(errors);
+        index
+    }
+
+    /// Find the module imported by `import_path` from the module at
+    /// `importer`, loading it if it has not been loaded yet.
+    fn resolve(
+        &mut self,
+        importer: &Path,
+        import_path: &[(String, Span)],
+    ) -> Result<usize, String> {
+        let mut path = importer.parent().unwrap_or(Path::new("")).to_path_buf();
+        for (component, _) in import_path {
+            path.push(component);
+        }
+        path.set_extension("wye");
+
+        let dotted = import_path
+            .iter()
+            .map(|(component, _)| component.as_str())
+            .collect::<Vec<_>>()
+            .join(".");
+        let Ok(path) = path.canonicalize() else {
+            return Err(format!(
+                "Cannot find module {} at {}",
+                dotted,
+                path.display()
+            ));
+        };
+
+        if let Some(pos) = self.loading.iter().position(|p| *p == path) {
+            let cycle = self.loading[pos..]
+                .iter()
+                .chain([&path])
+                .map(|p| p.file_name().unwrap().to_string_lossy())
+                .collect::<Vec<_>>();
+            return Err(format!(
+                "Import of {} is cyclic: {}",
+                dotted,
+                cycle.join(" -> ")
+            ));
+        }
+        if let Some(index) = self.loaded.get(&path) {
+            return Ok(*index);
+        }
+
+        let source = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read module {}: {}", dotted, e))?;
+        let name = import_path.last().unwrap().0.clone();
+        Ok(self.load(name, path, source))
+    }
+}
@@ -0,0 +1,12 @@
+use super::*;
+
+#[cfg(test)]
+mod test_load;
+
+/// Path of a file in the directory of programs used by module tests.
+#[cfg(test)]
+pub(crate) fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("src/module/tests/fixtures")
+        .join(name)
+}
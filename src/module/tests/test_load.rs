@@ -0,0 +1,60 @@
+use super::*;
+
+fn load(name: &str) -> (SourceMap, Vec<Module>, Vec<ModuleError>) {
+    load_program(&fixture(name)).unwrap()
+}
+
+#[test]
+fn test_load_modules() {
+    let (sources, modules, errors) = load("main.wye");
+    assert!(errors.is_empty(), "{:?}", errors);
+
+    // Modules come after the modules they import, and a module imported
+    // twice is only loaded once
+    let names = modules.iter().map(|m| m.name.as_str()).collect::<Vec<_>>();
+    assert!(names == vec!["List", "Strings", "main"]);
+    assert!(sources.path(modules[0].file) == fixture("util/List.wye").canonicalize().unwrap());
+    // Files are numbered in the order they are loaded, starting from the root
+    assert!(sources.files().len() == 3);
+    assert!(modules[2].file == 0);
+    assert!(modules[1].imports == HashMap::from([("List".to_string(), 0)]));
+    assert!(
+        modules[2].imports == HashMap::from([("List".to_string(), 0), ("Strings".to_string(), 1)])
+    );
+
+    assert!(load_program(&fixture("missing.wye")).is_err());
+}
+
+#[test]
+fn test_load_errors() {
+    let (sources, modules, errors) = load("errors.wye");
+    assert!(modules.len() == 2);
+    let root = modules[1].file;
+    assert!(errors.len() == 2, "{:?}", errors);
+
+    assert!(errors[0].0.starts_with("Cannot find module nowhere at "));
+    assert!(errors[0].1 == Span::new(root, 0, 14));
+    assert!(sources.snippet(&errors[0].1) == "import nowhere");
+    assert!(
+        errors[1]
+            == (
+                "Module List is already imported".to_string(),
+                Span::new(root, 29, 40)
+            )
+    );
+}
+
+#[test]
+fn test_load_cycle() {
+    let (sources, modules, errors) = load("cycle/a.wye");
+    // c imports a, which is still being loaded
+    assert!(modules.iter().map(|m| m.name.as_str()).collect::<Vec<_>>() == vec!["c", "b", "a"]);
+    assert!(
+        errors
+            == vec![(
+                "Import of a is cyclic: a.wye -> b.wye -> c.wye -> a.wye".to_string(),
+                Span::new(modules[0].file, 0, 8)
+            )]
+    );
+    assert!(sources.path(modules[0].file).ends_with("cycle/c.wye"));
+}
@@ -1,4 +1,4 @@
-use super::span::{unspanned_seq, GetSpan, OptionSpan, Span, UnSpan};
+use super::span::{unspanned_seq, GetSpan, Span, UnSpan};
 use super::util::OptionBox;
 use crate::types::structure::Flex;
 use crate::types::Type;
@@ -6,23 +6,46 @@ use ordered_float::OrderedFloat;
 use std::collections::BTreeMap;
 
 /// This file describes the Abstract Syntax Tree for Wye. A Wye program is, at
-/// base, a sequence of Wye statements. At present, there are only 5 allowed Wye
-/// statements:
+/// base, a sequence of Wye statements. At present, the allowed Wye statements
+/// are:
 /// - Expressions
 /// - Enum declarations
+/// - Type aliases
 /// - Struct declarations
 /// - Interface declarations
 /// - Interface implementations
+/// - Module imports and opens
 ///
 /// Expressions evaluate to values of a particular type. Variables in methods or
 /// let statements may be annotated with types in order to aid the type checker.
 /// It is useful to describe these types in an abstract syntax within the AST.
 
 // TODO(WYE-5): documentation
-// TODO: and keyword for group bindings / mutual recursion
 
 pub type Program = Vec<Statement>;
 
+/// The name of the entry point of a program, which is called with the
+/// command-line arguments once every statement has run.
+pub const MAIN: &str = "main";
+
+/// The top-level binding of the entry point of a program, if it has one. If
+/// `main` is bound more than once, the last binding is the entry point.
+pub fn main_binding(prog: &Program) -> Option<&VarWithValue> {
+    prog.iter()
+        .rev()
+        .flat_map(|stmt| {
+            let group = match stmt {
+                Statement::Expression(Expression::Let(varwithval, None, _)) => {
+                    std::slice::from_ref(varwithval)
+                }
+                Statement::Expression(Expression::LetGroup(group, None, _)) => &group[..],
+                _ => &[],
+            };
+            group.iter().rev()
+        })
+        .find(|varwithval| varwithval.name.0 == MAIN)
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Statement {
     Expression(Expression),
@@ -30,99 +53,179 @@ pub enum Statement {
     // variants have optional fields.
     // These, unlike Expressions, are not recursive structures
     EnumDecl {
-        name: (String, OptionSpan),
+        name: (String, Span),
+        type_args: Vec<PolytypeVar>,
+        variants: Vec<(String, Option<Type>, Span)>,
+        span: Span,
+    },
+    // type <polytype var>* <Id> = <type>
+    // type <Id> <polytype var>* = <type>
+    // An abbreviation for a type, which the type checker expands wherever
+    // the alias is used.
+    TypeAlias {
+        name: (String, Span),
         type_args: Vec<PolytypeVar>,
-        variants: Vec<(String, Option<Type>, OptionSpan)>,
-        span: OptionSpan,
+        typ: Type,
+        span: Span,
     },
-    // struct <Id> <polytype var>* { <Id>: type,+ }
+    // struct <polytype var>* <Id> { <Id>: type,+ }
     StructDecl {
-        name: (String, OptionSpan),
+        name: (String, Span),
         type_args: Vec<PolytypeVar>,
-        members: Vec<(String, Type, OptionSpan)>,
+        members: Vec<(String, Type, Span)>,
+        span: Span,
     },
-    // sig <Id> <polytype var>* (requires (<Id> | (<Id> <polytype var>+))+)? { (method|val): type|methodimpl }
+    // sig <polytype var>* <Id> (requires <polytype var>* <Id> (+ <polytype var>* <Id>)*)?
+    //     { ((val|method) <Id>: type | methodimpl),* }
     InterfaceDecl {
-        name: (String, OptionSpan),
+        name: (String, Span),
         type_args: Vec<PolytypeVar>,
-        requires: Vec<(String, OptionSpan, Vec<PolytypeVar>)>,
+        requires: Vec<(String, Span, Vec<PolytypeVar>)>,
         // Implemented methods
         // name, args, output type, expression
-        impl_methods: Vec<VarWithValue>,
+        impl_methods: Vec<(VarWithValue, Span)>,
         // Unimplemented methods
-        spec_methods: Vec<(String, Type, OptionSpan)>,
-        values: Vec<(String, Type, OptionSpan)>,
+        spec_methods: Vec<(String, Type, Span)>,
+        values: Vec<(String, Type, Span)>,
+        span: Span,
     },
-    // impl <Id>: <Id> { (AttrSet|MethodImpl)+ }
+    // impl <polytype var>* <Id> (: <polytype var>* <Id>)? { (AttrSet|MethodImpl),* }
+    // Without an interface, this is an inherent implementation for the struct.
     InterfaceImpl {
         // name and type vars
-        for_struct: (String, OptionSpan, Vec<PolytypeVar>),
-        impl_interface: Option<(String, OptionSpan, Vec<PolytypeVar>)>,
-        attr_sets: Vec<AttrSet>,
+        for_struct: (String, Span, Vec<PolytypeVar>),
+        impl_interface: Option<(String, Span, Vec<PolytypeVar>)>,
+        attr_sets: Vec<(AttrSet, Span)>,
         // id, arguments, expression
-        method_impls: Vec<VarWithValue>,
+        method_impls: Vec<(VarWithValue, Span)>,
+        span: Span,
     },
+    // import <Id>(.<Id>)*
+    // Loads the module in the file at this path, relative to the importing
+    // file, and binds it to the last <Id>.
+    Import {
+        path: Vec<(String, Span)>,
+        span: Span,
+    },
+    // open <Id>
+    // Brings the members of an imported module into scope unqualified.
+    Open {
+        module: (String, Span),
+        span: Span,
+    },
+    // (infixl|infixr|infix) <precedence> <op>+
+    // Declares the precedence and associativity of operators defined by the
+    // program, for the whole file.
+    Fixity {
+        assoc: Assoc,
+        precedence: u8,
+        operators: Vec<(String, Span)>,
+        span: Span,
+    },
+    // A statement that failed to parse, along with the syntax error. The
+    // parser skips to the next `;` so that the rest of the program can
+    // still be checked.
+    Error(String, Span),
 }
 
 /// Expressions describe some kind of computation that evaluates to a value,
 /// which can be stored in a variable, or used in further expressions.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Expression {
-    Nothing(OptionSpan),
-    IntLiteral(i64, OptionSpan),
-    FloatLiteral(OrderedFloat<f64>, OptionSpan),
-    StringLiteral(String, OptionSpan),
-    List(Vec<Expression>, OptionSpan),
-    Tuple(Vec<Expression>, OptionSpan),
+    Nothing(Span),
+    IntLiteral(i64, Span),
+    FloatLiteral(OrderedFloat<f64>, Span),
+    StringLiteral(String, Span),
+    List(Vec<Expression>, Span),
+    Tuple(Vec<Expression>, Span),
+    // [ <Expr> | <clause>, ..., <clause> ]
+    // Generators bind their variable in the clauses after them and in the
+    // element expression.
+    ListComprehension {
+        expr: Box<Expression>,
+        clauses: Vec<ComprehensionClause>,
+        span: Span,
+    },
     // { <id>: value, ..., <id>: value }
     // Record expressions only have fields, no methods
-    Record(BTreeMap<String, (Expression, OptionSpan)>, Flex, OptionSpan),
+    Record(BTreeMap<String, (Expression, Span)>, Flex, Span),
     // Reference a variable or function from the environment.
-    Identifier(String, OptionSpan),
-    BinaryOp(BinaryOp, OptionSpan),
+    Identifier(String, Span),
+    BinaryOp(BinaryOp, Span),
     // Print a value to stdout
-    Print(OptionSpan),
+    Print(Span),
     // Print a value to stderr and exit.
-    Fail(OptionSpan),
+    Fail(Span),
+    // Negate a bool
+    Not(Span),
     // <Enum Name> . <Variant Name> with Field
     EnumVariant {
         enum_id: String,
         variant: String,
         field: Box<Expression>,
-        span: OptionSpan,
+        span: Span,
     },
     // <Id>.<Id>: could be enum or struct or attribute
-    Projection(Box<Expression>, String, OptionSpan),
+    Projection(Box<Expression>, String, Span),
     // <Expr>#<Id>
-    MethodAccess(Box<Expression>, String, OptionSpan),
+    MethodAccess(Box<Expression>, String, Span),
+    // <Expr> <op> <Expr> (<op> <Expr>)*
+    // A chain of infix operations that uses an operator defined by the
+    // program, with the span of each operand and operator. The precedence of
+    // such operators is only known once the whole file is parsed, so
+    // `parse_program` resolves the chain into nested applications.
+    InfixChain(Vec<(Expression, Span)>, Vec<(Expression, Span)>, Span),
     // <Expr> args
-    FuncApplication(Box<Expression>, Vec<Expression>, OptionSpan),
+    FuncApplication(Box<Expression>, Vec<Expression>, Span),
     // <Expr> arg1=e1 arg2=e2 ...
-    NamedArgsFuncApp(
-        Box<Expression>,
-        Vec<(String, Expression, OptionSpan)>,
-        OptionSpan,
-    ),
+    NamedArgsFuncApp(Box<Expression>, Vec<(String, Expression, Span)>, Span),
     // match <Expr> { <Pat> => <Expr>, ... , <Pat> => <Expr> }
+    // Each arm holds its pattern, its expression, and the span of the whole arm
     Match {
         matchand: Box<Expression>,
-        arms: Vec<(Pattern, Expression)>,
-        span: OptionSpan,
+        arms: Vec<(Pattern, Expression, Span)>,
+        span: Span,
+    },
+    // if <Expr> then <Expr> else <Expr>
+    // Only the branch chosen by the condition is evaluated.
+    If {
+        cond: Box<Expression>,
+        then_expr: Box<Expression>,
+        else_expr: Box<Expression>,
+        span: Span,
     },
     // \ <identifier> -> Expr
     Lambda {
         arg: String,
         expr: Box<Expression>,
-        span: OptionSpan,
+        span: Span,
     },
     // Evaluate an expression and store in a variable of a type
     // Poly let-in construct
     // let <id> (arguments & type-annotation) = <expression> (in thing)?
-    Let(VarWithValue, OptionBox<Expression>, OptionSpan),
+    Let(VarWithValue, OptionBox<Expression>, Span),
+    // Several bindings joined by `and`, which are all bound at once. With
+    // `rec` they can refer to each other.
+    // let (rec)? <binding> (and <binding>)+ (in thing)?
+    LetGroup(Vec<VarWithValue>, OptionBox<Expression>, Span),
+    // { <Expr>; ...; <Expr>; <Expr>? }
+    // Evaluate expressions in order, taking the value of the last one, which
+    // is nothing if the block ends with `;`. A let without `in` binds its
+    // names for the rest of the block.
+    Block(Vec<Expression>, OptionBox<Expression>, Span),
     // Change the value of a variable. This is only allowed in object methods.
     // The set expression evaluates to nothing.
-    // thing <- expr
-    Set(AttrSet, OptionSpan),
+    // set <Id>.<Id> = <Expr>
+    Set(AttrSet, Span),
+}
+
+/// A clause of a list comprehension.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ComprehensionClause {
+    // <Id> <- <Expr>: draw each element of a list in turn
+    Generator(String, Expression, Span),
+    // <Expr>: keep only the elements for which this is true
+    Guard(Expression),
 }
 
 /// Reserved tokens used to denote builtin binary operations, which are
@@ -153,15 +256,41 @@ pub enum BinaryOp {
 
     /// List construction.
     Cons,
+
+    /// Boolean connectives. Applied to both operands, these only evaluate
+    /// the right operand if the left does not already decide the result.
+    And,
+    Or,
+
+    /// Function application and composition.
+    /// x |> f applies f to x
+    Pipe,
+    /// f >> g applies f, then g
+    ComposeForward,
+    /// f << g applies g, then f
+    ComposeBackward,
+}
+
+/// How a chain of infix operators of the same precedence is grouped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Assoc {
+    Left,
+    Right,
+    // Operators that cannot be chained without parentheses
+    None,
 }
 
 /// TODO(WYE-5): Documentation
 /// f a b = c
-/// f a: int -> b: string -> int = c
+/// f (a: int) -> (b: string) -> int = c
+/// x: int = c
+/// Arguments and the output may be annotated with types, which the type
+/// checker holds the binding to.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct VarWithValue {
-    pub name: (String, OptionSpan),
-    pub args: Vec<(String, OptionSpan)>,
+    pub name: (String, Span),
+    pub args: Vec<(String, Option<Type>, Span)>,
+    pub output_type: Option<Type>,
     pub rec: bool,
     pub expr: Box<Expression>,
 }
@@ -170,8 +299,8 @@ pub struct VarWithValue {
 /// This is an expression that is only allowed in method implementations.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct AttrSet {
-    pub entity: (String, OptionSpan),
-    pub attr: (String, OptionSpan),
+    pub entity: (String, Span),
+    pub attr: (String, Span),
     pub new_expr: Box<Expression>,
 }
 
@@ -180,35 +309,38 @@ pub struct AttrSet {
 pub struct PolytypeVar {
     pub name: String,
     pub bound: Option<String>,
-    pub span: OptionSpan,
+    pub span: Span,
 }
 
 /// Pattern matching.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Pattern {
-    Wildcard(OptionSpan),
-    IntLiteral(i64, OptionSpan),
-    FloatLiteral(OrderedFloat<f64>, OptionSpan),
-    StringLiteral(String, OptionSpan),
-    Identifier(String, OptionSpan),
+    Wildcard(Span),
+    IntLiteral(i64, Span),
+    FloatLiteral(OrderedFloat<f64>, Span),
+    StringLiteral(String, Span),
+    Identifier(String, Span),
     // TypeId (with Identifier)?
     // TypeId (with Pattern)
-    TypeVariant(String, OptionBox<Pattern>, OptionSpan),
+    TypeVariant(String, OptionBox<Pattern>, Span),
     // x :: xs
-    ListCons(String, String, OptionSpan),
-    EmptyList(OptionSpan),
-    Union(Vec<Pattern>, OptionSpan),
-    Complement(Box<Pattern>, OptionSpan),
-    List(Vec<Pattern>, OptionSpan),
-    Tuple(Vec<Pattern>, OptionSpan),
+    ListCons(String, String, Span),
+    EmptyList(Span),
+    Union(Vec<Pattern>, Span),
+    Complement(Box<Pattern>, Span),
+    List(Vec<Pattern>, Span),
+    Tuple(Vec<Pattern>, Span),
+    // { <id>: <pat>, ..., <id>: <pat> } matches exactly these fields, while
+    // { <id>: <pat>, ..., _ } matches any record with at least these fields
+    Record(BTreeMap<String, (Pattern, Span)>, Flex, Span),
     // <pat> if <guard_expr> - take this only if pat matches and guard(pat) is true
     Guarded {
         pattern: Box<Pattern>,
         guard: Expression,
-        span: OptionSpan,
+        span: Span,
     },
     // case <e>; e must evaluate to boolean
-    Case(Expression, OptionSpan),
+    Case(Expression, Span),
 }
 
 impl UnSpan for Statement {
@@ -221,25 +353,27 @@ impl UnSpan for Statement {
                 variants,
                 span: _,
             } => Self::EnumDecl {
-                name: (name.0.clone(), None),
+                name: (name.0.clone(), Span::dummy()),
                 type_args: unspanned_seq(&type_args),
                 variants: variants
                     .iter()
-                    .map(|v| (v.0.clone(), v.1.clone(), None))
+                    .map(|v| (v.0.clone(), v.1.clone(), Span::dummy()))
                     .collect(),
-                span: None,
+                span: Span::dummy(),
             },
             Self::StructDecl {
                 name,
                 type_args,
                 members,
+                span: _,
             } => Self::StructDecl {
-                name: (name.0.clone(), None),
+                name: (name.0.clone(), Span::dummy()),
                 type_args: unspanned_seq(&type_args),
                 members: members
                     .iter()
-                    .map(|m| (m.0.clone(), m.1.clone(), None))
+                    .map(|m| (m.0.clone(), m.1.clone(), Span::dummy()))
                     .collect(),
+                span: Span::dummy(),
             },
             Self::InterfaceDecl {
                 name,
@@ -248,39 +382,90 @@ impl UnSpan for Statement {
                 impl_methods,
                 spec_methods,
                 values,
+                span: _,
             } => Self::InterfaceDecl {
-                name: (name.0.clone(), None),
+                name: (name.0.clone(), Span::dummy()),
                 type_args: unspanned_seq(&type_args),
                 requires: requires
                     .iter()
-                    .map(|r| (r.0.clone(), None, unspanned_seq(&r.2)))
+                    .map(|r| (r.0.clone(), Span::dummy(), unspanned_seq(&r.2)))
+                    .collect(),
+                impl_methods: impl_methods
+                    .iter()
+                    .map(|m| (m.0.unspanned(), Span::dummy()))
                     .collect(),
-                impl_methods: unspanned_seq(&impl_methods),
                 spec_methods: spec_methods
                     .iter()
-                    .map(|m| (m.0.clone(), m.1.clone(), None))
+                    .map(|m| (m.0.clone(), m.1.clone(), Span::dummy()))
                     .collect(),
                 values: values
                     .iter()
-                    .map(|v| (v.0.clone(), v.1.clone(), None))
+                    .map(|v| (v.0.clone(), v.1.clone(), Span::dummy()))
                     .collect(),
+                span: Span::dummy(),
             },
             Self::InterfaceImpl {
                 for_struct,
                 impl_interface,
                 attr_sets,
                 method_impls,
+                span: _,
             } => Self::InterfaceImpl {
-                for_struct: (for_struct.0.clone(), None, unspanned_seq(&for_struct.2)),
+                for_struct: (
+                    for_struct.0.clone(),
+                    Span::dummy(),
+                    unspanned_seq(&for_struct.2),
+                ),
                 impl_interface: match impl_interface {
                     Some((name, _, type_args)) => {
-                        Some((name.clone(), None, unspanned_seq(&type_args)))
+                        Some((name.clone(), Span::dummy(), unspanned_seq(&type_args)))
                     }
                     None => None,
                 },
-                attr_sets: unspanned_seq(&attr_sets),
-                method_impls: unspanned_seq(&method_impls),
+                attr_sets: attr_sets
+                    .iter()
+                    .map(|a| (a.0.unspanned(), Span::dummy()))
+                    .collect(),
+                method_impls: method_impls
+                    .iter()
+                    .map(|m| (m.0.unspanned(), Span::dummy()))
+                    .collect(),
+                span: Span::dummy(),
+            },
+            Self::TypeAlias {
+                name,
+                type_args,
+                typ,
+                span: _,
+            } => Self::TypeAlias {
+                name: (name.0.clone(), Span::dummy()),
+                type_args: unspanned_seq(&type_args),
+                typ: typ.clone(),
+                span: Span::dummy(),
+            },
+            Self::Import { path, span: _ } => Self::Import {
+                path: path.iter().map(|p| (p.0.clone(), Span::dummy())).collect(),
+                span: Span::dummy(),
             },
+            Self::Open { module, span: _ } => Self::Open {
+                module: (module.0.clone(), Span::dummy()),
+                span: Span::dummy(),
+            },
+            Self::Fixity {
+                assoc,
+                precedence,
+                operators,
+                span: _,
+            } => Self::Fixity {
+                assoc: *assoc,
+                precedence: *precedence,
+                operators: operators
+                    .iter()
+                    .map(|op| (op.0.clone(), Span::dummy()))
+                    .collect(),
+                span: Span::dummy(),
+            },
+            Self::Error(msg, _) => Self::Error(msg.clone(), Span::dummy()),
         }
     }
 }
@@ -288,23 +473,33 @@ impl UnSpan for Statement {
 impl UnSpan for Expression {
     fn unspanned(&self) -> Self {
         match &self {
-            Self::Nothing(_) => Self::Nothing(None),
-            Self::IntLiteral(i, _) => Self::IntLiteral(*i, None),
-            Self::FloatLiteral(f, _) => Self::FloatLiteral(*f, None),
-            Self::StringLiteral(s, _) => Self::StringLiteral(s.clone(), None),
-            Self::List(lst, _) => Self::List(unspanned_seq(&lst), None),
-            Self::Tuple(tup, _) => Self::Tuple(unspanned_seq(&tup), None),
+            Self::Nothing(_) => Self::Nothing(Span::dummy()),
+            Self::IntLiteral(i, _) => Self::IntLiteral(*i, Span::dummy()),
+            Self::FloatLiteral(f, _) => Self::FloatLiteral(*f, Span::dummy()),
+            Self::StringLiteral(s, _) => Self::StringLiteral(s.clone(), Span::dummy()),
+            Self::List(lst, _) => Self::List(unspanned_seq(&lst), Span::dummy()),
+            Self::Tuple(tup, _) => Self::Tuple(unspanned_seq(&tup), Span::dummy()),
+            Self::ListComprehension {
+                expr,
+                clauses,
+                span: _,
+            } => Self::ListComprehension {
+                expr: Box::new(expr.unspanned()),
+                clauses: unspanned_seq(&clauses),
+                span: Span::dummy(),
+            },
             Self::Record(rec, flex, _) => Self::Record(
                 rec.iter()
-                    .map(|r| (r.0.clone(), (r.1 .0.unspanned(), None)))
+                    .map(|r| (r.0.clone(), (r.1 .0.unspanned(), Span::dummy())))
                     .collect(),
                 flex.clone(),
-                None,
+                Span::dummy(),
             ),
-            Self::Identifier(id, _) => Self::Identifier(id.clone(), None),
-            Self::BinaryOp(binop, _) => Self::BinaryOp(binop.clone(), None),
-            Self::Print(_) => Self::Print(None),
-            Self::Fail(_) => Self::Fail(None),
+            Self::Identifier(id, _) => Self::Identifier(id.clone(), Span::dummy()),
+            Self::BinaryOp(binop, _) => Self::BinaryOp(binop.clone(), Span::dummy()),
+            Self::Print(_) => Self::Print(Span::dummy()),
+            Self::Fail(_) => Self::Fail(Span::dummy()),
+            Self::Not(_) => Self::Not(Span::dummy()),
             Self::EnumVariant {
                 enum_id,
                 variant,
@@ -314,23 +509,34 @@ impl UnSpan for Expression {
                 enum_id: enum_id.clone(),
                 variant: variant.clone(),
                 field: Box::new(field.unspanned()),
-                span: None,
+                span: Span::dummy(),
             },
             Self::Projection(e, id, _) => {
-                Self::Projection(Box::new(e.unspanned()), id.clone(), None)
+                Self::Projection(Box::new(e.unspanned()), id.clone(), Span::dummy())
             }
             Self::MethodAccess(e, id, _) => {
-                Self::MethodAccess(Box::new(e.unspanned()), id.clone(), None)
+                Self::MethodAccess(Box::new(e.unspanned()), id.clone(), Span::dummy())
             }
+            Self::InfixChain(operands, operators, _) => Self::InfixChain(
+                operands
+                    .iter()
+                    .map(|e| (e.0.unspanned(), Span::dummy()))
+                    .collect(),
+                operators
+                    .iter()
+                    .map(|e| (e.0.unspanned(), Span::dummy()))
+                    .collect(),
+                Span::dummy(),
+            ),
             Self::FuncApplication(e, args, _) => {
-                Self::FuncApplication(Box::new(e.unspanned()), unspanned_seq(&args), None)
+                Self::FuncApplication(Box::new(e.unspanned()), unspanned_seq(&args), Span::dummy())
             }
             Self::NamedArgsFuncApp(e, args, _) => Self::NamedArgsFuncApp(
                 Box::new(e.unspanned()),
                 args.iter()
-                    .map(|(name, arg, _)| (name.clone(), arg.unspanned(), None))
+                    .map(|(name, arg, _)| (name.clone(), arg.unspanned(), Span::dummy()))
                     .collect(),
-                None,
+                Span::dummy(),
             ),
             Self::Match {
                 matchand,
@@ -340,14 +546,25 @@ impl UnSpan for Expression {
                 matchand: Box::new(matchand.unspanned()),
                 arms: arms
                     .iter()
-                    .map(|(p, e)| (p.unspanned(), e.unspanned()))
+                    .map(|(p, e, _)| (p.unspanned(), e.unspanned(), Span::dummy()))
                     .collect(),
-                span: None,
+                span: Span::dummy(),
+            },
+            Self::If {
+                cond,
+                then_expr,
+                else_expr,
+                span: _,
+            } => Self::If {
+                cond: Box::new(cond.unspanned()),
+                then_expr: Box::new(then_expr.unspanned()),
+                else_expr: Box::new(else_expr.unspanned()),
+                span: Span::dummy(),
             },
             Self::Lambda { arg, expr, span: _ } => Self::Lambda {
                 arg: arg.clone(),
                 expr: Box::new(expr.unspanned()),
-                span: None,
+                span: Span::dummy(),
             },
             Self::Let(v, e, _) => {
                 let new_e = if let Some(box_expr) = e {
@@ -355,9 +572,21 @@ impl UnSpan for Expression {
                 } else {
                     None
                 };
-                Self::Let(v.unspanned(), new_e, None)
+                Self::Let(v.unspanned(), new_e, Span::dummy())
             }
-            Self::Set(a, _) => Self::Set(a.unspanned(), None),
+            Self::LetGroup(vs, e, _) => Self::LetGroup(
+                vs.iter().map(|v| v.unspanned()).collect(),
+                e.as_ref().map(|box_expr| Box::new(box_expr.unspanned())),
+                Span::dummy(),
+            ),
+            Self::Block(exprs, result, _) => Self::Block(
+                exprs.iter().map(|e| e.unspanned()).collect(),
+                result
+                    .as_ref()
+                    .map(|box_expr| Box::new(box_expr.unspanned())),
+                Span::dummy(),
+            ),
+            Self::Set(a, _) => Self::Set(a.unspanned(), Span::dummy()),
         }
     }
 }
@@ -365,19 +594,33 @@ impl UnSpan for Expression {
 impl UnSpan for VarWithValue {
     fn unspanned(&self) -> Self {
         Self {
-            name: (self.name.0.clone(), None),
-            args: self.args.iter().map(|v| (v.0.clone(), None)).collect(),
+            name: (self.name.0.clone(), Span::dummy()),
+            args: self
+                .args
+                .iter()
+                .map(|v| (v.0.clone(), v.1.clone(), Span::dummy()))
+                .collect(),
+            output_type: self.output_type.clone(),
             rec: self.rec,
             expr: Box::new(self.expr.unspanned()),
         }
     }
 }
 
+impl UnSpan for ComprehensionClause {
+    fn unspanned(&self) -> Self {
+        match self {
+            Self::Generator(id, e, _) => Self::Generator(id.clone(), e.unspanned(), Span::dummy()),
+            Self::Guard(e) => Self::Guard(e.unspanned()),
+        }
+    }
+}
+
 impl UnSpan for AttrSet {
     fn unspanned(&self) -> Self {
         Self {
-            entity: (self.entity.0.clone(), None),
-            attr: (self.attr.0.clone(), None),
+            entity: (self.entity.0.clone(), Span::dummy()),
+            attr: (self.attr.0.clone(), Span::dummy()),
             new_expr: Box::new(self.new_expr.unspanned()),
         }
     }
@@ -388,7 +631,7 @@ impl UnSpan for PolytypeVar {
         Self {
             name: self.name.clone(),
             bound: self.bound.clone(),
-            span: None,
+            span: Span::dummy(),
         }
     }
 }
@@ -396,24 +639,31 @@ impl UnSpan for PolytypeVar {
 impl UnSpan for Pattern {
     fn unspanned(&self) -> Self {
         match &self {
-            Self::Wildcard(_) => Self::Wildcard(None),
-            Self::IntLiteral(i, _) => Self::IntLiteral(*i, None),
-            Self::FloatLiteral(f, _) => Self::FloatLiteral(*f, None),
-            Self::StringLiteral(s, _) => Self::StringLiteral(s.clone(), None),
-            Self::Identifier(id, _) => Self::Identifier(id.clone(), None),
+            Self::Wildcard(_) => Self::Wildcard(Span::dummy()),
+            Self::IntLiteral(i, _) => Self::IntLiteral(*i, Span::dummy()),
+            Self::FloatLiteral(f, _) => Self::FloatLiteral(*f, Span::dummy()),
+            Self::StringLiteral(s, _) => Self::StringLiteral(s.clone(), Span::dummy()),
+            Self::Identifier(id, _) => Self::Identifier(id.clone(), Span::dummy()),
             Self::TypeVariant(tid, field, _) => {
                 let field = match &field {
                     Some(p) => Some(Box::new(p.unspanned())),
                     _ => None,
                 };
-                Self::TypeVariant(tid.clone(), field, None)
+                Self::TypeVariant(tid.clone(), field, Span::dummy())
             }
-            Self::ListCons(s1, s2, _) => Self::ListCons(s1.clone(), s2.clone(), None),
-            Self::EmptyList(_) => Self::EmptyList(None),
-            Self::Union(pv, _) => Self::Union(unspanned_seq(&pv), None),
-            Self::Complement(p, _) => Self::Complement(Box::new(p.unspanned()), None),
-            Self::List(pv, _) => Self::List(unspanned_seq(&pv), None),
-            Self::Tuple(pv, _) => Self::Tuple(unspanned_seq(&pv), None),
+            Self::ListCons(s1, s2, _) => Self::ListCons(s1.clone(), s2.clone(), Span::dummy()),
+            Self::EmptyList(_) => Self::EmptyList(Span::dummy()),
+            Self::Union(pv, _) => Self::Union(unspanned_seq(&pv), Span::dummy()),
+            Self::Complement(p, _) => Self::Complement(Box::new(p.unspanned()), Span::dummy()),
+            Self::List(pv, _) => Self::List(unspanned_seq(&pv), Span::dummy()),
+            Self::Tuple(pv, _) => Self::Tuple(unspanned_seq(&pv), Span::dummy()),
+            Self::Record(rec, flex, _) => Self::Record(
+                rec.iter()
+                    .map(|r| (r.0.clone(), (r.1 .0.unspanned(), Span::dummy())))
+                    .collect(),
+                *flex,
+                Span::dummy(),
+            ),
             Self::Guarded {
                 pattern,
                 guard,
@@ -421,37 +671,64 @@ impl UnSpan for Pattern {
             } => Self::Guarded {
                 pattern: Box::new(pattern.unspanned()),
                 guard: guard.unspanned(),
-                span: None,
+                span: Span::dummy(),
             },
-            Self::Case(e, _) => Self::Case(e.unspanned(), None),
+            Self::Case(e, _) => Self::Case(e.unspanned(), Span::dummy()),
         }
     }
 }
 
-#[cfg(not(test))]
 impl GetSpan for Expression {
     fn get_span(&self) -> Span {
         match &self {
-            Self::Nothing(s) => s.as_ref().unwrap().clone(),
-            Self::IntLiteral(_, s) => s.as_ref().unwrap().clone(),
-            Self::FloatLiteral(_, s) => s.as_ref().unwrap().clone(),
-            Self::StringLiteral(_, s) => s.as_ref().unwrap().clone(),
-            Self::List(_, s) => s.as_ref().unwrap().clone(),
-            Self::Tuple(_, s) => s.as_ref().unwrap().clone(),
-            Self::Record(_, _, s) => s.as_ref().unwrap().clone(),
-            Self::Identifier(_, s) => s.as_ref().unwrap().clone(),
-            Self::BinaryOp(_, s) => s.as_ref().unwrap().clone(),
-            Self::Print(s) => s.as_ref().unwrap().clone(),
-            Self::Fail(s) => s.as_ref().unwrap().clone(),
-            Self::EnumVariant { span, .. } => span.as_ref().unwrap().clone(),
-            Self::Projection(_, _, s) => s.as_ref().unwrap().clone(),
-            Self::MethodAccess(_, _, s) => s.as_ref().unwrap().clone(),
-            Self::FuncApplication(_, _, s) => s.as_ref().unwrap().clone(),
-            Self::NamedArgsFuncApp(_, _, s) => s.as_ref().unwrap().clone(),
-            Self::Match { span, .. } => span.as_ref().unwrap().clone(),
-            Self::Lambda { span, .. } => span.as_ref().unwrap().clone(),
-            Self::Let(_, _, s) => s.as_ref().unwrap().clone(),
-            Self::Set(_, s) => s.as_ref().unwrap().clone(),
+            Self::Nothing(s) => s.clone(),
+            Self::IntLiteral(_, s) => s.clone(),
+            Self::FloatLiteral(_, s) => s.clone(),
+            Self::StringLiteral(_, s) => s.clone(),
+            Self::List(_, s) => s.clone(),
+            Self::Tuple(_, s) => s.clone(),
+            Self::ListComprehension { span, .. } => span.clone(),
+            Self::Record(_, _, s) => s.clone(),
+            Self::Identifier(_, s) => s.clone(),
+            Self::BinaryOp(_, s) => s.clone(),
+            Self::Print(s) => s.clone(),
+            Self::Fail(s) => s.clone(),
+            Self::Not(s) => s.clone(),
+            Self::EnumVariant { span, .. } => span.clone(),
+            Self::Projection(_, _, s) => s.clone(),
+            Self::MethodAccess(_, _, s) => s.clone(),
+            Self::InfixChain(_, _, s) => s.clone(),
+            Self::FuncApplication(_, _, s) => s.clone(),
+            Self::NamedArgsFuncApp(_, _, s) => s.clone(),
+            Self::Match { span, .. } => span.clone(),
+            Self::If { span, .. } => span.clone(),
+            Self::Lambda { span, .. } => span.clone(),
+            Self::Let(_, _, s) => s.clone(),
+            Self::LetGroup(_, _, s) => s.clone(),
+            Self::Block(_, _, s) => s.clone(),
+            Self::Set(_, s) => s.clone(),
+        }
+    }
+}
+
+impl GetSpan for Pattern {
+    fn get_span(&self) -> Span {
+        match &self {
+            Self::Wildcard(s) => s.clone(),
+            Self::IntLiteral(_, s) => s.clone(),
+            Self::FloatLiteral(_, s) => s.clone(),
+            Self::StringLiteral(_, s) => s.clone(),
+            Self::Identifier(_, s) => s.clone(),
+            Self::TypeVariant(_, _, s) => s.clone(),
+            Self::ListCons(_, _, s) => s.clone(),
+            Self::EmptyList(s) => s.clone(),
+            Self::Union(_, s) => s.clone(),
+            Self::Complement(_, s) => s.clone(),
+            Self::List(_, s) => s.clone(),
+            Self::Tuple(_, s) => s.clone(),
+            Self::Record(_, _, s) => s.clone(),
+            Self::Guarded { span, .. } => span.clone(),
+            Self::Case(_, s) => s.clone(),
         }
     }
 }
@@ -0,0 +1,386 @@
+//! Precedence and associativity of infix operators.
+//!
+//! Infix expressions are parsed as flat chains of operands and operators.
+//! Builtin operators have fixed precedences, so chains of only builtin
+//! operators are resolved as they are parsed. Operators defined by the program
+//! get their fixity from declarations such as `infixl 6 <+>`, which apply to
+//! the whole file, so chains that use them are resolved by `resolve_program`
+//! once the file is parsed.
+use super::ast::{Assoc, BinaryOp, ComprehensionClause, Expression, Pattern, Program, Statement};
+use super::span::{Span, Spanned};
+use std::collections::HashMap;
+use std::fmt;
+
+/// The argument of the function that a section such as `(<+> 1)` stands for,
+/// which cannot clash with any variable of the program.
+pub const SECTION_ARG: &str = "(lhs)";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fixity {
+    pub assoc: Assoc,
+    /// Operators of higher precedence bind tighter
+    pub precedence: u8,
+}
+
+/// The fixity of operators that have no declaration, which bind tighter than
+/// every builtin operator.
+pub const DEFAULT_FIXITY: Fixity = Fixity {
+    assoc: Assoc::Left,
+    precedence: 9,
+};
+
+impl fmt::Display for Fixity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let keyword = match self.assoc {
+            Assoc::Left => "infixl",
+            Assoc::Right => "infixr",
+            Assoc::None => "infix",
+        };
+        write!(f, "{} {}", keyword, self.precedence)
+    }
+}
+
+pub fn builtin_fixity(bop: &BinaryOp) -> Fixity {
+    let (assoc, precedence) = match bop {
+        BinaryOp::ComposeForward => (Assoc::Left, 8),
+        BinaryOp::ComposeBackward => (Assoc::Right, 8),
+        BinaryOp::Mult | BinaryOp::FlMult | BinaryOp::FloorDiv | BinaryOp::Div => (Assoc::Left, 7),
+        BinaryOp::Add | BinaryOp::FlAdd | BinaryOp::Sub | BinaryOp::FlSub => (Assoc::Left, 6),
+        BinaryOp::Eq | BinaryOp::Neq => (Assoc::Left, 5),
+        BinaryOp::Lt | BinaryOp::Gt | BinaryOp::Leq | BinaryOp::Geq => (Assoc::Left, 4),
+        // Consing onto a list gives a list, so `1 :: 2 :: []` is `1 :: (2 :: [])`
+        BinaryOp::Cons => (Assoc::Right, 4),
+        BinaryOp::And => (Assoc::Right, 3),
+        BinaryOp::Or => (Assoc::Right, 2),
+        // Pipelines bind loosest, so `x |> f a >> g` needs no parentheses
+        BinaryOp::Pipe => (Assoc::Left, 1),
+    };
+    Fixity { assoc, precedence }
+}
+
+fn builtin_symbol(bop: &BinaryOp) -> &'static str {
+    match bop {
+        BinaryOp::Add => "+",
+        BinaryOp::FlAdd => "+.",
+        BinaryOp::Sub => "-",
+        BinaryOp::FlSub => "-.",
+        BinaryOp::Mult => "*",
+        BinaryOp::FlMult => "*.",
+        BinaryOp::FloorDiv => "/",
+        BinaryOp::Div => "/.",
+        BinaryOp::Lt => "<",
+        BinaryOp::Gt => ">",
+        BinaryOp::Leq => "<=",
+        BinaryOp::Geq => ">=",
+        BinaryOp::Eq => "==",
+        BinaryOp::Neq => "!=",
+        BinaryOp::Cons => "::",
+        BinaryOp::Pipe => "|>",
+        BinaryOp::ComposeForward => ">>",
+        BinaryOp::ComposeBackward => "<<",
+        BinaryOp::And => "&&",
+        BinaryOp::Or => "||",
+    }
+}
+
+/// The fixity of the operator of an infix operation, which is either a
+/// builtin operator or the identifier an operator of the program is bound to.
+fn fixity_of(op: &Expression, fixities: &HashMap<String, Fixity>) -> Fixity {
+    match op {
+        Expression::BinaryOp(bop, _) => builtin_fixity(bop),
+        Expression::Identifier(name, _) => *fixities.get(name).unwrap_or(&DEFAULT_FIXITY),
+        _ => DEFAULT_FIXITY,
+    }
+}
+
+fn operator_name(op: &Expression) -> &str {
+    match op {
+        Expression::BinaryOp(bop, _) => builtin_symbol(bop),
+        Expression::Identifier(name, _) => name,
+        _ => "<operator>",
+    }
+}
+
+/// The first operand of an infix expression, followed by each operator and
+/// the operand after it, as parsed before fixities are applied.
+pub type InfixSeq = (
+    Spanned<Expression>,
+    Vec<(Spanned<Expression>, Spanned<Expression>)>,
+);
+
+/// Build the expression for an infix expression parsed as `first`, followed
+/// by each operator and operand in `rest`.
+pub fn parse_chain(
+    first: Spanned<Expression>,
+    rest: Vec<(Spanned<Expression>, Spanned<Expression>)>,
+) -> Result<Expression, (String, Span)> {
+    let mut operands = vec![(first.value, first.span)];
+    let mut operators = vec![];
+    for (op, operand) in rest {
+        operators.push((op.value, op.span));
+        operands.push((operand.value, operand.span));
+    }
+
+    let all_builtin = operators
+        .iter()
+        .all(|(op, _)| matches!(op, Expression::BinaryOp(..)));
+    if all_builtin {
+        return Ok(resolve_chain(operands, operators, &HashMap::new())?.0);
+    }
+    let span = operands[0].1.to(&operands.last().unwrap().1);
+    Ok(Expression::InfixChain(operands, operators, span))
+}
+
+/// Resolve a chain of infix operations into nested applications of its
+/// operators, according to their fixities. Operators of the same precedence
+/// must associate the same way, and may not be chained at all if they are
+/// non-associative.
+pub fn resolve_chain(
+    operands: Vec<(Expression, Span)>,
+    operators: Vec<(Expression, Span)>,
+    fixities: &HashMap<String, Fixity>,
+) -> Result<(Expression, Span), (String, Span)> {
+    let mut operands = operands.into_iter();
+    let mut output = vec![operands.next().unwrap()];
+    let mut pending: Vec<((Expression, Span), Fixity)> = vec![];
+
+    for (op, operand) in operators.into_iter().zip(operands) {
+        let fixity = fixity_of(&op.0, fixities);
+        while let Some((top, top_fixity)) = pending.last() {
+            if top_fixity.precedence < fixity.precedence {
+                break;
+            }
+            if top_fixity.precedence == fixity.precedence {
+                match (top_fixity.assoc, fixity.assoc) {
+                    (Assoc::Left, Assoc::Left) => {}
+                    (Assoc::Right, Assoc::Right) => break,
+                    _ => {
+                        return Err((
+                            format!(
+                                "Cannot mix {} ({}) and {} ({}) in the same infix expression without parentheses",
+                                operator_name(&top.0),
+                                top_fixity,
+                                operator_name(&op.0),
+                                fixity,
+                            ),
+                            op.1.clone(),
+                        ))
+                    }
+                }
+            }
+            apply_pending(&mut output, &mut pending);
+        }
+        pending.push((op, fixity));
+        output.push(operand);
+    }
+    while !pending.is_empty() {
+        apply_pending(&mut output, &mut pending);
+    }
+    Ok(output.pop().unwrap())
+}
+
+/// Apply the most recent pending operator to the last two operands.
+fn apply_pending(
+    output: &mut Vec<(Expression, Span)>,
+    pending: &mut Vec<((Expression, Span), Fixity)>,
+) {
+    let ((op, _), _) = pending.pop().unwrap();
+    let (rhs, rhs_span) = output.pop().unwrap();
+    let (lhs, lhs_span) = output.pop().unwrap();
+    let span = lhs_span.to(&rhs_span);
+    output.push((
+        Expression::FuncApplication(Box::new(op), vec![lhs, rhs], span.clone()),
+        span,
+    ));
+}
+
+/// Resolve every infix chain in the program using the fixity declarations of
+/// the program. Statements with an error are replaced by `Statement::Error`.
+pub fn resolve_program(prog: &mut Program) {
+    let mut fixities = HashMap::new();
+    for stmt in prog.iter_mut() {
+        let Statement::Fixity {
+            assoc,
+            precedence,
+            operators,
+            ..
+        } = stmt
+        else {
+            continue;
+        };
+        let fixity = Fixity {
+            assoc: *assoc,
+            precedence: *precedence,
+        };
+        let mut error = None;
+        for (op, op_span) in operators.iter() {
+            if fixities.contains_key(op) {
+                error = Some((
+                    format!("Fixity of {} is already declared", op),
+                    op_span.clone(),
+                ));
+                break;
+            }
+            fixities.insert(op.clone(), fixity);
+        }
+        if let Some((msg, span)) = error {
+            *stmt = Statement::Error(msg, span);
+        }
+    }
+
+    for stmt in prog.iter_mut() {
+        if let Err((msg, span)) = resolve_statement(stmt, &fixities) {
+            *stmt = Statement::Error(msg, span);
+        }
+    }
+}
+
+type Resolved = Result<(), (String, Span)>;
+
+fn resolve_statement(stmt: &mut Statement, fixities: &HashMap<String, Fixity>) -> Resolved {
+    match stmt {
+        Statement::Expression(expr) => resolve_expr(expr, fixities),
+        Statement::InterfaceDecl { impl_methods, .. } => {
+            for (method, _) in impl_methods {
+                resolve_expr(&mut method.expr, fixities)?;
+            }
+            Ok(())
+        }
+        Statement::InterfaceImpl {
+            attr_sets,
+            method_impls,
+            ..
+        } => {
+            for (attr_set, _) in attr_sets {
+                resolve_expr(&mut attr_set.new_expr, fixities)?;
+            }
+            for (method, _) in method_impls {
+                resolve_expr(&mut method.expr, fixities)?;
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+fn resolve_exprs<'a, I>(exprs: I, fixities: &HashMap<String, Fixity>) -> Resolved
+where
+    I: IntoIterator<Item = &'a mut Expression>,
+{
+    for expr in exprs {
+        resolve_expr(expr, fixities)?;
+    }
+    Ok(())
+}
+
+fn resolve_expr(expr: &mut Expression, fixities: &HashMap<String, Fixity>) -> Resolved {
+    match expr {
+        Expression::InfixChain(operands, operators, _) => {
+            resolve_exprs(operands.iter_mut().map(|(e, _)| e), fixities)?;
+            let resolved = resolve_chain(
+                std::mem::take(operands),
+                std::mem::take(operators),
+                fixities,
+            )?;
+            *expr = resolved.0;
+            Ok(())
+        }
+        Expression::Nothing(_)
+        | Expression::IntLiteral(..)
+        | Expression::FloatLiteral(..)
+        | Expression::StringLiteral(..)
+        | Expression::Identifier(..)
+        | Expression::BinaryOp(..)
+        | Expression::Print(_)
+        | Expression::Fail(_)
+        | Expression::Not(_) => Ok(()),
+        Expression::List(exprs, _) | Expression::Tuple(exprs, _) => resolve_exprs(exprs, fixities),
+        Expression::ListComprehension { expr, clauses, .. } => {
+            resolve_expr(expr, fixities)?;
+            for clause in clauses {
+                match clause {
+                    ComprehensionClause::Generator(_, e, _) | ComprehensionClause::Guard(e) => {
+                        resolve_expr(e, fixities)?
+                    }
+                }
+            }
+            Ok(())
+        }
+        Expression::Record(fields, _, _) => {
+            resolve_exprs(fields.values_mut().map(|(e, _)| e), fixities)
+        }
+        Expression::EnumVariant { field, .. } => resolve_expr(field, fixities),
+        Expression::Projection(e, _, _) | Expression::MethodAccess(e, _, _) => {
+            resolve_expr(e, fixities)
+        }
+        Expression::FuncApplication(func, args, _) => {
+            resolve_expr(func, fixities)?;
+            resolve_exprs(args, fixities)
+        }
+        Expression::NamedArgsFuncApp(func, args, _) => {
+            resolve_expr(func, fixities)?;
+            resolve_exprs(args.iter_mut().map(|(_, e, _)| e), fixities)
+        }
+        Expression::Match { matchand, arms, .. } => {
+            resolve_expr(matchand, fixities)?;
+            for (pat, e, _) in arms {
+                resolve_pattern(pat, fixities)?;
+                resolve_expr(e, fixities)?;
+            }
+            Ok(())
+        }
+        Expression::If {
+            cond,
+            then_expr,
+            else_expr,
+            ..
+        } => resolve_exprs([&mut **cond, &mut **then_expr, &mut **else_expr], fixities),
+        Expression::Lambda { expr, .. } => resolve_expr(expr, fixities),
+        Expression::Let(varwithval, in_expr, _) => {
+            resolve_expr(&mut varwithval.expr, fixities)?;
+            resolve_exprs(in_expr.iter_mut().map(|e| &mut **e), fixities)
+        }
+        Expression::LetGroup(group, in_expr, _) => {
+            resolve_exprs(group.iter_mut().map(|v| &mut *v.expr), fixities)?;
+            resolve_exprs(in_expr.iter_mut().map(|e| &mut **e), fixities)
+        }
+        Expression::Block(exprs, result, _) => {
+            resolve_exprs(exprs, fixities)?;
+            resolve_exprs(result.iter_mut().map(|e| &mut **e), fixities)
+        }
+        Expression::Set(attr_set, _) => resolve_expr(&mut attr_set.new_expr, fixities),
+    }
+}
+
+fn resolve_pattern(pat: &mut Pattern, fixities: &HashMap<String, Fixity>) -> Resolved {
+    match pat {
+        Pattern::Guarded { pattern, guard, .. } => {
+            resolve_pattern(pattern, fixities)?;
+            resolve_expr(guard, fixities)
+        }
+        Pattern::Case(e, _) => resolve_expr(e, fixities),
+        Pattern::TypeVariant(_, Some(p), _) | Pattern::Complement(p, _) => {
+            resolve_pattern(p, fixities)
+        }
+        Pattern::Union(pats, _) | Pattern::List(pats, _) | Pattern::Tuple(pats, _) => {
+            for p in pats {
+                resolve_pattern(p, fixities)?;
+            }
+            Ok(())
+        }
+        Pattern::Record(fields, _, _) => {
+            for (p, _) in fields.values_mut() {
+                resolve_pattern(p, fixities)?;
+            }
+            Ok(())
+        }
+        Pattern::Wildcard(_)
+        | Pattern::IntLiteral(..)
+        | Pattern::FloatLiteral(..)
+        | Pattern::StringLiteral(..)
+        | Pattern::Identifier(..)
+        | Pattern::TypeVariant(_, None, _)
+        | Pattern::ListCons(..)
+        | Pattern::EmptyList(_) => Ok(()),
+    }
+}
@@ -0,0 +1,782 @@
+use super::span::{FileId, Span};
+use ordered_float::OrderedFloat;
+use std::collections::HashSet;
+use std::fmt;
+
+/// Tokens of a Wye program, as consumed by the grammar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+    // Names and literals
+    Identifier(String),
+    IntLiteral(i64),
+    FloatLiteral(OrderedFloat<f64>),
+    /// String literal with escape sequences already resolved.
+    StringLiteral(String),
+    /// 'name
+    Polytype(String),
+    /// bound'name
+    BoundedPolytype(String, String),
+    /// An infix operator defined by the program, such as `<+>`
+    Operator(String),
+    /// Input that could not be lexed, with a description of the problem.
+    /// No grammar rule accepts it, so the parser recovers from it like any
+    /// other syntax error.
+    Error(String),
+
+    // Keywords
+    And,
+    Case,
+    Else,
+    Enum,
+    Fail,
+    If,
+    Impl,
+    Import,
+    In,
+    Infix,
+    Infixl,
+    Infixr,
+    Let,
+    Match,
+    Method,
+    Not,
+    Nothing,
+    Open,
+    Print,
+    Rec,
+    Requires,
+    Set,
+    Sig,
+    Struct,
+    Then,
+    Type,
+    With,
+    // Builtin types
+    IntType,
+    FloatType,
+    StringType,
+    NoneType,
+
+    // Punctuation
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    /// `[]`, which may contain whitespace
+    EmptyList,
+    LBrace,
+    RBrace,
+    /// `{|`, opening an exact record
+    LExactBrace,
+    /// `|}`, closing an exact record
+    RExactBrace,
+    Comma,
+    Semicolon,
+    Colon,
+    Dot,
+    Hash,
+    Underscore,
+    Backslash,
+    Tilde,
+    Bar,
+    Assign,
+    Arrow,
+    /// `<-`, only within the clauses of a list comprehension
+    LeftArrow,
+    FatArrow,
+
+    // Builtin binary operators
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    FlPlus,
+    FlMinus,
+    FlStar,
+    FlSlash,
+    Lt,
+    Gt,
+    Leq,
+    Geq,
+    Eq,
+    Neq,
+    Cons,
+    /// `|>`
+    Pipe,
+    /// `>>`
+    ComposeForward,
+    /// `<<`
+    ComposeBackward,
+    /// `&&`
+    AndAlso,
+    /// `||`
+    OrElse,
+}
+
+/// Reserved words, which can never be used as identifiers.
+fn keyword(word: &str) -> Option<Token> {
+    let tok = match word {
+        "and" => Token::And,
+        "case" => Token::Case,
+        "else" => Token::Else,
+        "enum" => Token::Enum,
+        "fail" => Token::Fail,
+        "if" => Token::If,
+        "impl" => Token::Impl,
+        "import" => Token::Import,
+        "in" => Token::In,
+        "infix" => Token::Infix,
+        "infixl" => Token::Infixl,
+        "infixr" => Token::Infixr,
+        "let" => Token::Let,
+        "match" => Token::Match,
+        "method" => Token::Method,
+        "not" => Token::Not,
+        "nothing" => Token::Nothing,
+        "open" => Token::Open,
+        "print" => Token::Print,
+        "rec" => Token::Rec,
+        "requires" => Token::Requires,
+        "set" => Token::Set,
+        "sig" => Token::Sig,
+        "struct" => Token::Struct,
+        "then" => Token::Then,
+        "type" => Token::Type,
+        "with" => Token::With,
+        "int" => Token::IntType,
+        "float" => Token::FloatType,
+        "string" => Token::StringType,
+        "none" => Token::NoneType,
+        "_" => Token::Underscore,
+        _ => return None,
+    };
+    Some(tok)
+}
+
+impl fmt::Display for Token {
+    /// Display a token as it would be written in source.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            Token::Identifier(id) => return write!(f, "{}", id),
+            Token::IntLiteral(i) => return write!(f, "{}", i),
+            Token::FloatLiteral(x) => return write!(f, "{:?}", x.0),
+            Token::StringLiteral(s) => return write!(f, "{:?}", s),
+            Token::Polytype(name) => return write!(f, "'{}", name),
+            Token::BoundedPolytype(bound, name) => return write!(f, "{}'{}", bound, name),
+            Token::Operator(op) => return write!(f, "{}", op),
+            Token::Error(msg) => return write!(f, "{}", msg),
+            Token::And => "and",
+            Token::Case => "case",
+            Token::Else => "else",
+            Token::Enum => "enum",
+            Token::Fail => "fail",
+            Token::If => "if",
+            Token::Impl => "impl",
+            Token::Import => "import",
+            Token::In => "in",
+            Token::Infix => "infix",
+            Token::Infixl => "infixl",
+            Token::Infixr => "infixr",
+            Token::Let => "let",
+            Token::Match => "match",
+            Token::Method => "method",
+            Token::Not => "not",
+            Token::Nothing => "nothing",
+            Token::Open => "open",
+            Token::Print => "print",
+            Token::Rec => "rec",
+            Token::Requires => "requires",
+            Token::Set => "set",
+            Token::Sig => "sig",
+            Token::Struct => "struct",
+            Token::Then => "then",
+            Token::Type => "type",
+            Token::With => "with",
+            Token::IntType => "int",
+            Token::FloatType => "float",
+            Token::StringType => "string",
+            Token::NoneType => "none",
+            Token::LParen => "(",
+            Token::RParen => ")",
+            Token::LBracket => "[",
+            Token::RBracket => "]",
+            Token::EmptyList => "[]",
+            Token::LBrace => "{",
+            Token::RBrace => "}",
+            Token::LExactBrace => "{|",
+            Token::RExactBrace => "|}",
+            Token::Comma => ",",
+            Token::Semicolon => ";",
+            Token::Colon => ":",
+            Token::Dot => ".",
+            Token::Hash => "#",
+            Token::Underscore => "_",
+            Token::Backslash => "\\",
+            Token::Tilde => "~",
+            Token::Bar => "|",
+            Token::Assign => "=",
+            Token::Arrow => "->",
+            Token::LeftArrow => "<-",
+            Token::FatArrow => "=>",
+            Token::Plus => "+",
+            Token::Minus => "-",
+            Token::Star => "*",
+            Token::Slash => "/",
+            Token::FlPlus => "+.",
+            Token::FlMinus => "-.",
+            Token::FlStar => "*.",
+            Token::FlSlash => "/.",
+            Token::Lt => "<",
+            Token::Gt => ">",
+            Token::Leq => "<=",
+            Token::Geq => ">=",
+            Token::Eq => "==",
+            Token::Neq => "!=",
+            Token::Cons => "::",
+            Token::Pipe => "|>",
+            Token::ComposeForward => ">>",
+            Token::ComposeBackward => "<<",
+            Token::AndAlso => "&&",
+            Token::OrElse => "||",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+pub type LexError = (String, Span);
+
+/// A token along with its start and end byte positions, as LALRPOP expects.
+pub type SpannedToken = Result<(usize, Token, usize), LexError>;
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Characters that operators defined by the program are made of.
+fn is_operator_char(c: char) -> bool {
+    "!$&*+-./:<=>?@^|~".contains(c)
+}
+
+/// Tokens made up of punctuation, longest first for each prefix.
+const SYMBOLS: [(&str, Token); 40] = [
+    ("{|", Token::LExactBrace),
+    ("|}", Token::RExactBrace),
+    ("->", Token::Arrow),
+    ("<-", Token::LeftArrow),
+    ("=>", Token::FatArrow),
+    ("==", Token::Eq),
+    ("!=", Token::Neq),
+    ("<=", Token::Leq),
+    (">=", Token::Geq),
+    ("::", Token::Cons),
+    ("|>", Token::Pipe),
+    (">>", Token::ComposeForward),
+    ("<<", Token::ComposeBackward),
+    ("&&", Token::AndAlso),
+    ("||", Token::OrElse),
+    ("+.", Token::FlPlus),
+    ("-.", Token::FlMinus),
+    ("*.", Token::FlStar),
+    ("/.", Token::FlSlash),
+    ("(", Token::LParen),
+    (")", Token::RParen),
+    ("[", Token::LBracket),
+    ("]", Token::RBracket),
+    ("{", Token::LBrace),
+    ("}", Token::RBrace),
+    (",", Token::Comma),
+    (";", Token::Semicolon),
+    (":", Token::Colon),
+    (".", Token::Dot),
+    ("#", Token::Hash),
+    ("\\", Token::Backslash),
+    ("~", Token::Tilde),
+    ("|", Token::Bar),
+    ("=", Token::Assign),
+    ("+", Token::Plus),
+    ("-", Token::Minus),
+    ("*", Token::Star),
+    ("/", Token::Slash),
+    ("<", Token::Lt),
+    (">", Token::Gt),
+];
+
+fn is_symbol(text: &str) -> bool {
+    SYMBOLS.iter().any(|(symbol, _)| *symbol == text)
+}
+
+/// Whether a digit separator `_` in a number literal is not directly between
+/// two digits.
+fn misplaced_separator<P: Fn(char) -> bool>(digits: &str, is_digit: P) -> bool {
+    let chars = digits.chars().collect::<Vec<_>>();
+    chars.iter().enumerate().any(|(i, c)| {
+        *c == '_'
+            && !(i > 0
+                && is_digit(chars[i - 1])
+                && chars.get(i + 1).is_some_and(|next| is_digit(*next)))
+    })
+}
+
+fn separator_error(text: &str) -> String {
+    format!(
+        "Digit separator `_` must be between digits in number literal {}",
+        text
+    )
+}
+
+/// Splits the text of a Wye program into tokens, skipping whitespace,
+/// `%` line comments and (possibly nested) `[% ... %]` block comments.
+/// Input that cannot be lexed becomes an error token, after which lexing
+/// carries on, so that every error in the input is reported.
+pub struct Lexer<'input> {
+    file: FileId,
+    input: &'input str,
+    pos: usize,
+    /// The brackets currently open, innermost last, each along with whether
+    /// it is a `[` followed by a `|`, whose comprehension clauses are being
+    /// lexed.
+    brackets: Vec<(Token, bool)>,
+    /// Operators the program declares, which are never split up
+    declared_operators: HashSet<String>,
+}
+
+impl<'input> Lexer<'input> {
+    pub fn new(file: FileId, input: &'input str) -> Self {
+        // Operators are declared for the whole file, so they are found in a
+        // first pass over it
+        let declared_operators = declared_operators(Self::without_operators(file, input));
+        Self {
+            declared_operators,
+            ..Self::without_operators(file, input)
+        }
+    }
+
+    /// A lexer that does not know which operators the program declares.
+    fn without_operators(file: FileId, input: &'input str) -> Self {
+        Self {
+            file,
+            input,
+            pos: 0,
+            brackets: vec![],
+            declared_operators: HashSet::new(),
+        }
+    }
+
+    /// Keep track of the brackets opened and closed by a token.
+    fn track_brackets(&mut self, tok: &Token) {
+        match tok {
+            Token::LParen | Token::LBracket | Token::LBrace | Token::LExactBrace => {
+                self.brackets.push((tok.clone(), false))
+            }
+            Token::RParen | Token::RBracket | Token::RBrace | Token::RExactBrace => {
+                self.brackets.pop();
+            }
+            Token::Bar => {
+                if let Some((Token::LBracket, clauses)) = self.brackets.last_mut() {
+                    *clauses = true;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn in_comprehension_clauses(&self) -> bool {
+        matches!(self.brackets.last(), Some((Token::LBracket, true)))
+    }
+
+    fn rest(&self) -> &'input str {
+        &self.input[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn peek_nth(&self, n: usize) -> Option<char> {
+        self.rest().chars().nth(n)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    /// Consume the next character if it is `c`.
+    fn bump_if(&mut self, c: char) -> bool {
+        let matches = self.peek() == Some(c);
+        if matches {
+            self.bump();
+        }
+        matches
+    }
+
+    /// Consume the characters satisfying `pred`, returning them.
+    fn take_while<P: Fn(char) -> bool>(&mut self, pred: P) -> &'input str {
+        let start = self.pos;
+        while self.peek().is_some_and(&pred) {
+            self.bump();
+        }
+        &self.input[start..self.pos]
+    }
+
+    fn skip_trivia(&mut self) -> Result<(), LexError> {
+        loop {
+            let rest = self.rest();
+            if rest.starts_with("[%") {
+                self.skip_block_comment()?;
+            } else if rest.starts_with('%') {
+                self.take_while(|c| c != '\n' && c != '\r');
+            } else if self.peek().is_some_and(char::is_whitespace) {
+                self.take_while(char::is_whitespace);
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn skip_block_comment(&mut self) -> Result<(), LexError> {
+        let start = self.pos;
+        let mut depth = 0;
+        loop {
+            let rest = self.rest();
+            if rest.starts_with("[%") {
+                depth += 1;
+                self.pos += 2;
+            } else if rest.starts_with("%]") {
+                depth -= 1;
+                self.pos += 2;
+                if depth == 0 {
+                    return Ok(());
+                }
+            } else if self.bump().is_none() {
+                return Err((
+                    "Unterminated block comment".to_string(),
+                    Span::new(self.file, start, start + 2),
+                ));
+            }
+        }
+    }
+
+    /// Lex a string literal. An invalid escape sequence is reported once
+    /// the whole literal is consumed.
+    fn lex_string(&mut self, start: usize) -> SpannedToken {
+        let mut value = String::new();
+        let mut escape_error = None;
+        loop {
+            let escape_start = self.pos;
+            match self.bump() {
+                None => {
+                    return Err((
+                        "Unterminated string literal".to_string(),
+                        Span::new(self.file, start, self.pos),
+                    ))
+                }
+                Some('"') => break,
+                Some('\\') => match self.lex_escape(escape_start) {
+                    Ok(c) => value.push(c),
+                    Err(err) => {
+                        escape_error.get_or_insert(err);
+                    }
+                },
+                Some(c) => value.push(c),
+            }
+        }
+        match escape_error {
+            Some(err) => Err(err),
+            None => Ok((start, Token::StringLiteral(value), self.pos)),
+        }
+    }
+
+    /// Resolve an escape sequence, whose backslash is at `start`.
+    fn lex_escape(&mut self, start: usize) -> Result<char, LexError> {
+        let c = match self.bump() {
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('0') => '\0',
+            Some('\\') => '\\',
+            Some('"') => '"',
+            Some('u') => {
+                // The closing quote of the string is not consumed on error
+                if !self.bump_if('{') {
+                    return Err((
+                        "Expected `{` in unicode escape".to_string(),
+                        Span::new(self.file, start, self.pos),
+                    ));
+                }
+                let digits = self.take_while(|c| c.is_ascii_hexdigit());
+                if !self.bump_if('}') || digits.is_empty() || digits.len() > 6 {
+                    return Err((
+                        "Unicode escape must be of the form \\u{XXXX}, with 1 to 6 hex digits"
+                            .to_string(),
+                        Span::new(self.file, start, self.pos),
+                    ));
+                }
+                let code = u32::from_str_radix(digits, 16).unwrap();
+                match char::from_u32(code) {
+                    Some(c) => c,
+                    None => {
+                        return Err((
+                            format!("Invalid unicode character {:X} in escape", code),
+                            Span::new(self.file, start, self.pos),
+                        ))
+                    }
+                }
+            }
+            _ => {
+                return Err((
+                    format!("Invalid escape sequence {}", &self.input[start..self.pos]),
+                    Span::new(self.file, start, self.pos),
+                ))
+            }
+        };
+        Ok(c)
+    }
+
+    /// Lex an integer or float literal, which may be negative. Integers may
+    /// be written in hexadecimal, octal or binary with a `0x`, `0o` or `0b`
+    /// prefix, and digits of any literal may be separated by `_`.
+    fn lex_number(&mut self, start: usize) -> SpannedToken {
+        let negative = self.peek() == Some('-');
+        if negative {
+            self.bump();
+        }
+        let radix = match (self.peek(), self.peek_nth(1)) {
+            (Some('0'), Some('x')) => Some((16, "hexadecimal")),
+            (Some('0'), Some('o')) => Some((8, "octal")),
+            (Some('0'), Some('b')) => Some((2, "binary")),
+            _ => None,
+        };
+        if let Some((radix, radix_name)) = radix {
+            self.pos += 2;
+            let digits = self.take_while(|c| c.is_ascii_alphanumeric() || c == '_');
+            let text = &self.input[start..self.pos];
+            let span = Span::new(self.file, start, self.pos);
+            if digits.is_empty() {
+                return Err((format!("Number literal {} has no digits", text), span));
+            }
+            if let Some(c) = digits.chars().find(|c| *c != '_' && !c.is_digit(radix)) {
+                return Err((
+                    format!("Invalid digit {} in {} literal {}", c, radix_name, text),
+                    span,
+                ));
+            }
+            if misplaced_separator(digits, |c| c.is_ascii_alphanumeric()) {
+                return Err((separator_error(text), span));
+            }
+            let sign = if negative { "-" } else { "" };
+            let digits = format!("{}{}", sign, digits.replace('_', ""));
+            return match i64::from_str_radix(&digits, radix) {
+                Ok(i) => Ok((start, Token::IntLiteral(i), self.pos)),
+                Err(_) => Err((
+                    format!("Integer literal {} does not fit in 64 bits", text),
+                    span,
+                )),
+            };
+        }
+
+        let is_digit = |c: char| c.is_ascii_digit() || c == '_';
+        let int_part = self.take_while(is_digit);
+        let mut is_float = false;
+        if self.peek() == Some('.') && self.peek_nth(1).is_some_and(|c| c.is_ascii_digit()) {
+            is_float = true;
+            self.bump();
+            self.take_while(is_digit);
+        }
+        // An `e` directly after the digits always starts an exponent, which
+        // must be made up of digits, with an optional sign
+        let mut malformed_exponent = false;
+        if matches!(self.peek(), Some('e' | 'E')) {
+            is_float = true;
+            self.bump();
+            if matches!(self.peek(), Some('-' | '+')) {
+                self.bump();
+            }
+            let exponent = self.take_while(is_ident_char);
+            malformed_exponent = !exponent.starts_with(|c: char| c.is_ascii_digit())
+                || !exponent.chars().all(is_digit);
+        }
+        let text = &self.input[start..self.pos];
+        let span = Span::new(self.file, start, self.pos);
+        if malformed_exponent {
+            return Err((
+                format!("Malformed exponent in number literal {}", text),
+                span,
+            ));
+        }
+
+        let digits_start = if negative { 1 } else { 0 };
+        if misplaced_separator(&text[digits_start..], |c| c.is_ascii_digit()) {
+            return Err((separator_error(text), span));
+        }
+        // As in C, a leading zero could be taken to mean octal, which is
+        // written with `0o` instead
+        let int_part = int_part.replace('_', "");
+        if int_part.len() > 1 && int_part.starts_with('0') {
+            return Err((
+                format!("Number literal {} cannot have leading zeros", text),
+                span,
+            ));
+        }
+
+        let cleaned = text.replace('_', "");
+        let tok = if is_float {
+            let x = cleaned.parse::<f64>().unwrap();
+            if x.is_infinite() {
+                return Err((
+                    format!("Float literal {} is too large to be represented", text),
+                    span,
+                ));
+            }
+            Token::FloatLiteral(OrderedFloat(x))
+        } else {
+            match cleaned.parse::<i64>() {
+                Ok(i) => Token::IntLiteral(i),
+                Err(_) => {
+                    return Err((
+                        format!("Integer literal {} does not fit in 64 bits", text),
+                        span,
+                    ))
+                }
+            }
+        };
+        Ok((start, tok, self.pos))
+    }
+
+    /// Lex an identifier, keyword, or bounded polytype variable.
+    fn lex_word(&mut self, start: usize) -> SpannedToken {
+        let word = self.take_while(is_ident_char);
+        if self.peek() == Some('\'') && self.peek_nth(1).is_some_and(is_ident_start) {
+            self.bump();
+            let name = self.take_while(is_ident_char);
+            return Ok((
+                start,
+                Token::BoundedPolytype(word.to_string(), name.to_string()),
+                self.pos,
+            ));
+        }
+        let tok = keyword(word).unwrap_or_else(|| Token::Identifier(word.to_string()));
+        Ok((start, tok, self.pos))
+    }
+
+    /// Lex a token made up of punctuation, preferring the longest match.
+    fn lex_symbol(&mut self, start: usize) -> SpannedToken {
+        // An empty list is a single token, even with whitespace inside
+        if let Some(after) = self.rest().strip_prefix('[') {
+            let inner = after.len() - after.trim_start().len();
+            if after[inner..].starts_with(']') {
+                self.pos += inner + 2;
+                return Ok((start, Token::EmptyList, self.pos));
+            }
+        }
+
+        // A run of operator characters is a single operator, unless it is a
+        // builtin symbol, so that `<+>` is not read as `<`, `+`, `>`
+        let mut op_len = self
+            .rest()
+            .find(|c| !is_operator_char(c))
+            .unwrap_or(self.rest().len());
+        // A run such as the `=-` of `x=-1`, that ends in a `-` before a
+        // digit, is a builtin symbol followed by a negative number, unless the
+        // program declares it as an operator
+        let run = &self.rest()[..op_len];
+        if let Some(symbol) = run.strip_suffix('-') {
+            if is_symbol(symbol)
+                && !is_symbol(run)
+                && !self.declared_operators.contains(run)
+                && self.rest()[op_len..].starts_with(|c: char| c.is_ascii_digit())
+            {
+                op_len = symbol.len();
+            }
+        }
+        // `<-` only introduces the list of a generator, so elsewhere `3<-1`
+        // compares with a negative number
+        if op_len == 2 && self.rest().starts_with("<-") && !self.in_comprehension_clauses() {
+            self.pos += 1;
+            return Ok((start, Token::Lt, self.pos));
+        }
+        for (text, tok) in SYMBOLS.iter() {
+            if self.rest().starts_with(text) && text.len() >= op_len {
+                self.pos += text.len();
+                return Ok((start, tok.clone(), self.pos));
+            }
+        }
+        if op_len > 0 {
+            let op = self.take_while(is_operator_char);
+            return Ok((start, Token::Operator(op.to_string()), self.pos));
+        }
+
+        let c = self.bump().unwrap();
+        let msg = if c == '\'' {
+            "Expected a type variable name after `'`".to_string()
+        } else {
+            format!("Unexpected character `{}`", c)
+        };
+        Err((msg, Span::new(self.file, start, self.pos)))
+    }
+}
+
+impl<'input> Iterator for Lexer<'input> {
+    type Item = SpannedToken;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Err(err) = self.skip_trivia() {
+            return Some(Ok(error_token(err)));
+        }
+
+        let start = self.pos;
+        let c = self.peek()?;
+        let next = self.peek_nth(1);
+        let res = if c == '"' {
+            self.bump();
+            self.lex_string(start)
+        } else if c.is_ascii_digit() || (c == '-' && next.is_some_and(|n| n.is_ascii_digit())) {
+            self.lex_number(start)
+        } else if c == '\'' && next.is_some_and(is_ident_start) {
+            self.bump();
+            let name = self.take_while(is_ident_char);
+            Ok((start, Token::Polytype(name.to_string()), self.pos))
+        } else if is_ident_start(c) {
+            self.lex_word(start)
+        } else {
+            self.lex_symbol(start)
+        };
+
+        let tok = res.unwrap_or_else(error_token);
+        self.track_brackets(&tok.1);
+        Some(Ok(tok))
+    }
+}
+
+/// The operators a program declares, either in a fixity declaration or by
+/// naming them in parentheses, as in `let (<+>) a b = a + b`.
+fn declared_operators(lexer: Lexer) -> HashSet<String> {
+    let tokens = lexer.flatten().map(|(_, tok, _)| tok).collect::<Vec<_>>();
+    let mut declared = HashSet::new();
+    let mut in_fixity = false;
+    for (i, tok) in tokens.iter().enumerate() {
+        match tok {
+            Token::Infix | Token::Infixl | Token::Infixr => in_fixity = true,
+            Token::Semicolon => in_fixity = false,
+            Token::Operator(op)
+                if in_fixity
+                    || (i > 0
+                        && tokens[i - 1] == Token::LParen
+                        && tokens.get(i + 1) == Some(&Token::RParen)) =>
+            {
+                declared.insert(op.clone());
+            }
+            _ => {}
+        }
+    }
+    declared
+}
+
+fn error_token((msg, span): LexError) -> (usize, Token, usize) {
+    (span.start, Token::Error(msg), span.end)
+}
@@ -1,9 +1,106 @@
 use lalrpop_util::lalrpop_mod;
 
 pub mod ast;
+pub mod fixity;
+pub mod lexer;
 pub mod span;
 mod util;
 lalrpop_mod!(pub grammar, "/parse/grammar.rs");
 
 #[cfg(test)]
 mod tests;
+
+/// Parse a whole program, recovering from errors at statement boundaries so
+/// that every error in the input is reported at once. Statements that failed
+/// to parse are kept in the program as `Statement::Error` nodes, and the
+/// errors are returned in source order. Infix expressions are resolved
+/// according to the fixity declarations of the whole program.
+pub fn parse_program(file: span::FileId, input: &str) -> (ast::Program, Vec<(String, span::Span)>) {
+    // Lex once, so that recovering from an error only re-parses tokens
+    let tokens = lexer::Lexer::new(file, input).flatten().collect::<Vec<_>>();
+    let ends = statement_ends(&tokens);
+    let mut prog = vec![];
+    let mut start = 0;
+    loop {
+        match parse_statements(file, tokens_between(&tokens, start, input.len())) {
+            Ok(stmts) => {
+                prog.extend(stmts);
+                break;
+            }
+            // An error raised by a grammar action stops the parser, so the
+            // statement it is in is replaced by the error, and parsing
+            // resumes after that statement
+            Err(err) => {
+                let (msg, span) = util::describe_parse_error(file, err);
+                let stmt_start = ends
+                    .iter()
+                    .rev()
+                    .find(|end| **end <= span.start)
+                    .map_or(start, |end| usize::max(*end, start));
+                // The statements before it were parsed without being stopped
+                let before = tokens_between(&tokens, start, stmt_start);
+                if !before.is_empty() {
+                    prog.extend(parse_statements(file, before).unwrap_or_default());
+                }
+                prog.push(ast::Statement::Error(msg, span.clone()));
+                match ends.iter().find(|end| **end > span.start) {
+                    Some(end) if !tokens_between(&tokens, *end, input.len()).is_empty() => {
+                        start = *end
+                    }
+                    _ => break,
+                }
+            }
+        }
+    }
+
+    fixity::resolve_program(&mut prog);
+    let errors = prog
+        .iter()
+        .filter_map(|stmt| match stmt {
+            ast::Statement::Error(msg, span) => Some((msg.clone(), span.clone())),
+            _ => None,
+        })
+        .collect();
+    (prog, errors)
+}
+
+type ParseResult =
+    Result<ast::Program, lalrpop_util::ParseError<usize, lexer::Token, (String, span::Span)>>;
+
+type Tokens = [(usize, lexer::Token, usize)];
+
+/// Parse a sequence of statements from their tokens.
+fn parse_statements(file: span::FileId, tokens: &Tokens) -> ParseResult {
+    grammar::ProgramParser::new().parse(file, tokens.iter().cloned().map(Ok))
+}
+
+/// The tokens that start between byte positions `start` and `end`.
+fn tokens_between(tokens: &Tokens, start: usize, end: usize) -> &Tokens {
+    let first = tokens.partition_point(|(tok_start, _, _)| *tok_start < start);
+    let last = tokens.partition_point(|(tok_start, _, _)| *tok_start < end);
+    &tokens[first..last]
+}
+
+/// The positions just after each `;` that ends a top-level statement, that
+/// is, that is not inside a block. A `;` cannot occur inside any other
+/// brackets, so one that does ends the statement whose brackets were never
+/// closed, as it does for the parser when it recovers.
+fn statement_ends(tokens: &Tokens) -> Vec<usize> {
+    use lexer::Token;
+    let mut open = vec![];
+    let mut ends = vec![];
+    for (_, tok, end) in tokens {
+        match tok {
+            Token::LParen | Token::LBracket | Token::LBrace | Token::LExactBrace => open.push(tok),
+            Token::RParen | Token::RBracket | Token::RBrace | Token::RExactBrace => {
+                open.pop();
+            }
+            Token::Semicolon if open.last() != Some(&&Token::LBrace) => {
+                open.clear();
+                ends.push(*end);
+            }
+            _ => {}
+        }
+    }
+    ends
+}
@@ -1,21 +1,40 @@
+use std::path::{Path, PathBuf};
+
+/// Identifies a file of a program in its `SourceMap`.
+pub type FileId = usize;
+
 /// A node in the parse tree of a program is spanned by bytes at certain
-/// positions in the text of a program. This struct captures those byte
-/// positions along with the node value so that line numbers and other
-/// information can be reported with errors.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// positions in the text of a file. This struct captures the file and those
+/// byte positions so that line numbers and other information can be reported
+/// with errors.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Span {
+    pub file: FileId,
     pub start: usize,
     pub end: usize,
 }
 
 impl Span {
-    pub fn new(start: usize, end: usize) -> Self {
-        Self { start, end }
+    pub fn new(file: FileId, start: usize, end: usize) -> Self {
+        Self { file, start, end }
+    }
+
+    /// A span that points at no file, for nodes that were not parsed, such as
+    /// the expected trees of tests.
+    pub fn dummy() -> Self {
+        Self::new(FileId::MAX, 0, 0)
+    }
+
+    /// The span from the start of this span to the end of `other`, which must
+    /// be in the same file.
+    pub fn to(&self, other: &Span) -> Self {
+        Self::new(self.file, self.start, other.end)
     }
 }
 
-/// Create a span of largest length from a collection of spans.
-pub fn widest_span<I>(seq: &I) -> OptionSpan
+/// Create a span of largest length from a collection of spans, which must all
+/// be in the same file.
+pub fn widest_span<I>(seq: &I) -> Option<Span>
 where
     I: AsRef<[Span]>,
 {
@@ -30,13 +49,55 @@ where
         if span.end > max_end {
             max_end = span.end;
         }
-        output = Some(Span::new(min_start, max_end));
+        output = Some(Span::new(span.file, min_start, max_end));
     }
 
     output
 }
 
-pub type OptionSpan = Option<Span>;
+/// A file of a program, along with its text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceFile {
+    pub path: PathBuf,
+    pub text: String,
+}
+
+/// The files of a program, each identified by the `FileId` carried by the
+/// spans of the nodes parsed from it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a file, returning the id its spans should carry.
+    pub fn add(&mut self, path: PathBuf, text: String) -> FileId {
+        self.files.push(SourceFile { path, text });
+        self.files.len() - 1
+    }
+
+    pub fn path(&self, file: FileId) -> &Path {
+        &self.files[file].path
+    }
+
+    pub fn text(&self, file: FileId) -> &str {
+        &self.files[file].text
+    }
+
+    /// The text a span covers.
+    pub fn snippet(&self, span: &Span) -> &str {
+        &self.text(span.file)[span.start..span.end]
+    }
+
+    /// Every file, in order of their ids.
+    pub fn files(&self) -> &[SourceFile] {
+        &self.files
+    }
+}
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Spanned<T: Clone + PartialEq + Eq> {
@@ -54,10 +115,24 @@ impl<T: Clone + PartialEq + Eq> Spanned<T> {
     }
 }
 
+/// Replace every span of a node and the nodes it contains with
+/// `Span::dummy()`.
 pub trait UnSpan {
     fn unspanned(&self) -> Self;
 }
 
+/// Structural equality that ignores spans, so that a parsed tree can be
+/// compared with one built by hand.
+pub trait SpanlessEq {
+    fn spanless_eq(&self, other: &Self) -> bool;
+}
+
+impl<T: UnSpan + PartialEq> SpanlessEq for T {
+    fn spanless_eq(&self, other: &Self) -> bool {
+        self.unspanned() == other.unspanned()
+    }
+}
+
 pub fn unspanned_seq<I, T: UnSpan>(seq: &I) -> Vec<T>
 where
     I: AsRef<[T]> + ?Sized,
@@ -68,19 +143,11 @@ where
         .collect::<Vec<T>>()
 }
 
-// TODO: once again the spans story could improve
-// do I really have to return to this stupid spans problem
-// Maybe a global const?
-
-// #[cfg(test)]
-// pub const IGNORE_SPANS: bool = true;
-// #[cfg(not(test))]
-// pub const IGNORE_SPANS: bool = false;
-// then use Span::new() everywhere, which defaults
-// to usize::MAX, usize::MAX when IGNORE spans is
-// true. Then we can have a dummy span constant that
-// is basically checked for equality everywhere
-// and there is none of this unspanned or unwrapping bs
+impl<T: UnSpan> UnSpan for Vec<T> {
+    fn unspanned(&self) -> Self {
+        unspanned_seq(self)
+    }
+}
 
 pub trait GetSpan {
     fn get_span(&self) -> Span;
@@ -3,8 +3,12 @@ use super::*;
 #[cfg(test)]
 mod test_expr;
 #[cfg(test)]
+mod test_lexer;
+#[cfg(test)]
 mod test_pattern;
 #[cfg(test)]
+mod test_program;
+#[cfg(test)]
 mod test_stmt;
 #[cfg(test)]
 mod test_type;
@@ -1,16 +1,18 @@
 use super::ast;
 use super::ast::Expression::*;
 use super::ast::Statement::Expression;
-use super::ast::VarWithValue;
+use super::ast::{AttrSet, ComprehensionClause, VarWithValue};
+use super::lexer::Lexer;
 use super::span::{Span, UnSpan};
 use super::*;
 use crate::test_util::to_of64;
 use crate::types::structure::Flex;
+use crate::types::Type;
 use lalrpop_util::ParseError;
 use std::collections::BTreeMap;
 
 fn parse(parser: &grammar::StatementParser, inp: &'static str) -> ast::Expression {
-    let out = parser.parse(inp).unwrap().unspanned();
+    let out = parser.parse(0, Lexer::new(0, inp)).unwrap().unspanned();
     if let Expression(e) = out {
         e
     } else {
@@ -24,59 +26,79 @@ fn test_parse_literal() {
     let parser = grammar::StatementParser::new();
 
     // Ok IntLiteral
-    assert!(parse(&parser, "nothing") == Nothing(None));
-    assert!(parse(&parser, "fail") == Fail(None));
-    assert!(parse(&parser, "print") == Print(None));
-    assert!(parse(&parser, "4") == IntLiteral(4, None));
-    assert!(parse(&parser, "52") == IntLiteral(52, None));
-    assert!(parse(&parser, "-1787234") == IntLiteral(-1787234, None));
-    assert!(parse(&parser, "675") == IntLiteral(675, None));
+    assert!(parse(&parser, "nothing") == Nothing(Span::dummy()));
+    assert!(parse(&parser, "fail") == Fail(Span::dummy()));
+    assert!(parse(&parser, "print") == Print(Span::dummy()));
+    assert!(parse(&parser, "4") == IntLiteral(4, Span::dummy()));
+    assert!(parse(&parser, "52") == IntLiteral(52, Span::dummy()));
+    assert!(parse(&parser, "-1787234") == IntLiteral(-1787234, Span::dummy()));
+    assert!(parse(&parser, "675") == IntLiteral(675, Span::dummy()));
     // Err IntLiteral
-    assert!(parser.parse("0527").is_err());
-    assert!(parser.parse("-000343").is_err());
+    assert!(parser.parse(0, Lexer::new(0, "0527")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "-000343")).is_err());
     // Ok FloatLiteral
-    assert!(parse(&parser, "5.0") == FloatLiteral(to_of64(5.0), None));
-    assert!(parse(&parser, "1.0e-9") == FloatLiteral(to_of64(1e-9), None));
-    assert!(parse(&parser, "0.23124") == FloatLiteral(to_of64(0.23124), None));
-    assert!(parse(&parser, "1.2222E100") == FloatLiteral(to_of64(1.2222E100), None));
+    assert!(parse(&parser, "5.0") == FloatLiteral(to_of64(5.0), Span::dummy()));
+    assert!(parse(&parser, "1.0e-9") == FloatLiteral(to_of64(1e-9), Span::dummy()));
+    assert!(parse(&parser, "0.23124") == FloatLiteral(to_of64(0.23124), Span::dummy()));
+    assert!(parse(&parser, "1.2222E100") == FloatLiteral(to_of64(1.2222E100), Span::dummy()));
     // Err FloatLiteral
-    assert!(parser.parse("00.9").is_err());
-    assert!(parser.parse("4.").is_err());
-    assert!(parser.parse("0.5689eE2").is_err());
-    assert!(parser.parse("12.888e").is_err());
-    assert!(parser.parse("3.145r10").is_err());
-    assert!(parser.parse("1.2.3.4").is_err());
-    assert!(parser.parse("5 .0").is_err());
-
-    assert!(parse(&parser, "\"hello there\"") == StringLiteral("hello there".to_string(), None));
-    assert!(parse(&parser, "\"µß£££ç∑ 😎\"") == StringLiteral("µß£££ç∑ 😎".to_string(), None));
-    assert!(parse(&parser, "\"\"") == StringLiteral("".to_string(), None));
-    assert!(parser.parse("\"hi there\"\"").is_err());
-    assert!(parser.parse("\"bruh").is_err());
-    assert!(parser.parse("no begin! \"").is_err());
+    assert!(parser.parse(0, Lexer::new(0, "00.9")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "4.")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "0.5689eE2")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "12.888e")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "3.145r10")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "1.2.3.4")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "5 .0")).is_err());
+
+    assert!(
+        parse(&parser, "\"hello there\"")
+            == StringLiteral("hello there".to_string(), Span::dummy())
+    );
+    assert!(
+        parse(&parser, "\"µß£££ç∑ 😎\"") == StringLiteral("µß£££ç∑ 😎".to_string(), Span::dummy())
+    );
+    assert!(parse(&parser, "\"\"") == StringLiteral("".to_string(), Span::dummy()));
+    assert!(
+        parse(&parser, r#""tab\tquote\" \u{2764}""#)
+            == StringLiteral("tab\tquote\" \u{2764}".to_string(), Span::dummy())
+    );
+    assert!(parser.parse(0, Lexer::new(0, "\"hi there\"\"")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, r#""bad \escape""#)).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "\"bruh")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "no begin! \"")).is_err());
 }
 
 #[test]
 fn test_parse_list() {
     let parser = grammar::StatementParser::new();
 
-    assert!(parse(&parser, "[]") == List(vec![], None));
-    assert!(parse(&parser, "[-1.0e6]") == List(vec![FloatLiteral(to_of64(-1.0e6), None)], None));
+    assert!(parse(&parser, "[]") == List(vec![], Span::dummy()));
+    assert!(
+        parse(&parser, "[-1.0e6]")
+            == List(
+                vec![FloatLiteral(to_of64(-1.0e6), Span::dummy())],
+                Span::dummy()
+            )
+    );
     assert!(
-        parse(&parser, "[4, 5]") == List(vec![IntLiteral(4, None), IntLiteral(5, None),], None)
+        parse(&parser, "[4, 5]")
+            == List(
+                vec![IntLiteral(4, Span::dummy()), IntLiteral(5, Span::dummy()),],
+                Span::dummy()
+            )
     );
     assert!(
         parse(&parser, "[\"buh\",4,5,7.0     , \t 8, \"⏰\"]")
             == List(
                 vec![
-                    StringLiteral("buh".to_string(), None),
-                    IntLiteral(4, None),
-                    IntLiteral(5, None),
-                    FloatLiteral(to_of64(7.0), None),
-                    IntLiteral(8, None),
-                    StringLiteral("⏰".to_string(), None)
+                    StringLiteral("buh".to_string(), Span::dummy()),
+                    IntLiteral(4, Span::dummy()),
+                    IntLiteral(5, Span::dummy()),
+                    FloatLiteral(to_of64(7.0), Span::dummy()),
+                    IntLiteral(8, Span::dummy()),
+                    StringLiteral("⏰".to_string(), Span::dummy())
                 ],
-                None
+                Span::dummy()
             )
     );
     // parser doesn't do type checking
@@ -86,62 +108,144 @@ fn test_parse_list() {
             r#"[1, "wow ಣ", 1.0, (2), [46, 47, -9.85], (-52, )]"#
         ) == List(
             vec![
-                IntLiteral(1, None),
-                StringLiteral("wow ಣ".to_string(), None),
-                FloatLiteral(to_of64(1.0), None),
-                IntLiteral(2, None),
+                IntLiteral(1, Span::dummy()),
+                StringLiteral("wow ಣ".to_string(), Span::dummy()),
+                FloatLiteral(to_of64(1.0), Span::dummy()),
+                IntLiteral(2, Span::dummy()),
                 List(
                     vec![
-                        IntLiteral(46, None),
-                        IntLiteral(47, None),
-                        FloatLiteral(to_of64(-9.85), None),
+                        IntLiteral(46, Span::dummy()),
+                        IntLiteral(47, Span::dummy()),
+                        FloatLiteral(to_of64(-9.85), Span::dummy()),
                     ],
-                    None
+                    Span::dummy()
                 ),
-                Tuple(vec![IntLiteral(-52, None)], None)
+                Tuple(vec![IntLiteral(-52, Span::dummy())], Span::dummy())
             ],
-            None
+            Span::dummy()
         )
     );
     assert!(
         parse(&parser, "[x, 4]")
             == List(
-                vec![Identifier("x".to_string(), None), IntLiteral(4, None)],
-                None
+                vec![
+                    Identifier("x".to_string(), Span::dummy()),
+                    IntLiteral(4, Span::dummy())
+                ],
+                Span::dummy()
             )
     );
 
-    assert!(parser.parse("[,]").is_err());
-    assert!(parser.parse("[,7]").is_err());
-    assert!(parser.parse("[7,]").is_err());
-    assert!(parser.parse("[4, 5,]").is_err());
-    assert!(parser.parse("[4, -6").is_err());
-    assert!(parser.parse("x, 7.0, ]").is_err());
-    assert!(parser.parse("[").is_err());
-    assert!(parser.parse("]").is_err());
+    assert!(parser.parse(0, Lexer::new(0, "[,]")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "[,7]")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "[7,]")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "[4, 5,]")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "[4, -6")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "x, 7.0, ]")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "[")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "]")).is_err());
+}
+
+#[test]
+fn test_parse_list_comprehension() {
+    let parser = grammar::StatementParser::new();
+
+    assert!(
+        parse(&parser, "[f x | x <- xs, x > 0]")
+            == ListComprehension {
+                expr: Box::new(FuncApplication(
+                    Box::new(Identifier("f".to_string(), Span::dummy())),
+                    vec![Identifier("x".to_string(), Span::dummy())],
+                    Span::dummy()
+                )),
+                clauses: vec![
+                    ComprehensionClause::Generator(
+                        "x".to_string(),
+                        Identifier("xs".to_string(), Span::dummy()),
+                        Span::dummy()
+                    ),
+                    ComprehensionClause::Guard(FuncApplication(
+                        Box::new(BinaryOp(ast::BinaryOp::Gt, Span::dummy())),
+                        vec![
+                            Identifier("x".to_string(), Span::dummy()),
+                            IntLiteral(0, Span::dummy())
+                        ],
+                        Span::dummy()
+                    )),
+                ],
+                span: Span::dummy(),
+            }
+    );
+    assert!(
+        parser
+            .parse(0, Lexer::new(0, "[(x, y) | x <- [1], y <- ys x]"))
+            .unwrap()
+            == Expression(ListComprehension {
+                expr: Box::new(Tuple(
+                    vec![
+                        Identifier("x".to_string(), Span::new(0, 2, 3)),
+                        Identifier("y".to_string(), Span::new(0, 5, 6))
+                    ],
+                    Span::new(0, 1, 7)
+                )),
+                clauses: vec![
+                    ComprehensionClause::Generator(
+                        "x".to_string(),
+                        List(
+                            vec![IntLiteral(1, Span::new(0, 16, 17))],
+                            Span::new(0, 15, 18)
+                        ),
+                        Span::new(0, 10, 18)
+                    ),
+                    ComprehensionClause::Generator(
+                        "y".to_string(),
+                        FuncApplication(
+                            Box::new(Identifier("ys".to_string(), Span::new(0, 25, 27))),
+                            vec![Identifier("x".to_string(), Span::new(0, 28, 29))],
+                            Span::new(0, 25, 29)
+                        ),
+                        Span::new(0, 20, 29)
+                    ),
+                ],
+                span: Span::new(0, 0, 30),
+            })
+    );
+
+    assert!(parser.parse(0, Lexer::new(0, "[x | ]")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "[x | x <- xs,]")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "[| x <- xs]")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "[x, y | x <- xs]")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "[x | 4 <- xs]")).is_err());
 }
 
 #[test]
 fn test_parse_tuple() {
     let parser = grammar::StatementParser::new();
 
-    assert!(parse(&parser, "(-4)") == IntLiteral(-4, None));
-    assert!(parse(&parser, "(-4,)") == Tuple(vec![IntLiteral(-4, None)], None));
+    assert!(parse(&parser, "(-4)") == IntLiteral(-4, Span::dummy()));
+    assert!(parse(&parser, "(-4,)") == Tuple(vec![IntLiteral(-4, Span::dummy())], Span::dummy()));
     assert!(
-        parse(&parser, "(5, 6, )") == Tuple(vec![IntLiteral(5, None), IntLiteral(6, None)], None)
+        parse(&parser, "(5, 6, )")
+            == Tuple(
+                vec![IntLiteral(5, Span::dummy()), IntLiteral(6, Span::dummy())],
+                Span::dummy()
+            )
     );
     assert!(
         parse(&parser, "(3, -7.25)")
             == Tuple(
-                vec![IntLiteral(3, None), FloatLiteral(to_of64(-7.25), None)],
-                None
+                vec![
+                    IntLiteral(3, Span::dummy()),
+                    FloatLiteral(to_of64(-7.25), Span::dummy())
+                ],
+                Span::dummy()
             )
     );
 
-    assert!(parser.parse("(").is_err());
-    assert!(parser.parse(")").is_err());
-    assert!(parser.parse("(4, 6, \"yah!\"").is_err());
-    assert!(parser.parse("5, 6, 3)").is_err());
+    assert!(parser.parse(0, Lexer::new(0, "(")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, ")")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "(4, 6, \"yah!\"")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "5, 6, 3)")).is_err());
 }
 
 #[test]
@@ -155,15 +259,15 @@ fn test_parse_record_expr() {
                     "field".to_string(),
                     (
                         Projection(
-                            Box::new(Identifier("Field".to_string(), None)),
+                            Box::new(Identifier("Field".to_string(), Span::dummy())),
                             "feeld".to_string(),
-                            None
+                            Span::dummy()
                         ),
-                        None
+                        Span::dummy()
                     )
                 )]),
                 Flex::Permissive,
-                None
+                Span::dummy()
             )
     );
     assert!(
@@ -177,33 +281,51 @@ fn test_parse_record_expr() {
     }"
         ) == Record(
             BTreeMap::from([
-                ("bint".to_string(), (IntLiteral(3, None), None)),
+                (
+                    "bint".to_string(),
+                    (IntLiteral(3, Span::dummy()), Span::dummy())
+                ),
                 (
                     "jint".to_string(),
-                    (List(vec![IntLiteral(2, None)], None), None)
+                    (
+                        List(vec![IntLiteral(2, Span::dummy())], Span::dummy()),
+                        Span::dummy()
+                    )
+                ),
+                (
+                    "cidnt".to_string(),
+                    (IntLiteral(1, Span::dummy()), Span::dummy())
                 ),
-                ("cidnt".to_string(), (IntLiteral(1, None), None)),
                 (
                     "lint".to_string(),
                     (
-                        Tuple(vec![StringLiteral("hi".to_string(), None)], None),
-                        None
+                        Tuple(
+                            vec![StringLiteral("hi".to_string(), Span::dummy())],
+                            Span::dummy()
+                        ),
+                        Span::dummy()
                     )
                 )
             ]),
             Flex::Permissive,
-            None
+            Span::dummy()
         )
     );
     assert!(
         parse(&parser, "({one: 2, three: 4})")
             == Record(
                 BTreeMap::from([
-                    ("one".to_string(), (IntLiteral(2, None), None)),
-                    ("three".to_string(), (IntLiteral(4, None), None))
+                    (
+                        "one".to_string(),
+                        (IntLiteral(2, Span::dummy()), Span::dummy())
+                    ),
+                    (
+                        "three".to_string(),
+                        (IntLiteral(4, Span::dummy()), Span::dummy())
+                    )
                 ]),
                 Flex::Permissive,
-                None
+                Span::dummy()
             )
     );
 
@@ -212,81 +334,98 @@ fn test_parse_record_expr() {
             == Tuple(
                 vec![Record(
                     BTreeMap::from([
-                        ("one".to_string(), (IntLiteral(2, None), None)),
-                        ("three".to_string(), (IntLiteral(4, None), None))
+                        (
+                            "one".to_string(),
+                            (IntLiteral(2, Span::dummy()), Span::dummy())
+                        ),
+                        (
+                            "three".to_string(),
+                            (IntLiteral(4, Span::dummy()), Span::dummy())
+                        )
                     ]),
                     Flex::Permissive,
-                    None
+                    Span::dummy()
                 )],
-                None
+                Span::dummy()
             )
     );
     assert!(
         parse(&parser, "{super: 4,}")
             == Record(
-                BTreeMap::from([("super".to_string(), (IntLiteral(4, None), None))]),
+                BTreeMap::from([(
+                    "super".to_string(),
+                    (IntLiteral(4, Span::dummy()), Span::dummy())
+                )]),
                 Flex::Permissive,
-                None
+                Span::dummy()
             )
     );
     assert!(
         parse(&parser, "{|x: 4|}")
             == Record(
-                BTreeMap::from([("x".to_string(), (IntLiteral(4, None), None))]),
+                BTreeMap::from([(
+                    "x".to_string(),
+                    (IntLiteral(4, Span::dummy()), Span::dummy())
+                )]),
                 Flex::Exact,
-                None
+                Span::dummy()
             )
     );
 
-    assert!(parser.parse("{}").is_err());
-    assert!(parser.parse("{||}").is_err());
-    assert!(parser.parse("{super(pub): 4}").is_err());
-    assert!(parser.parse("{int: 4}").is_err());
-    assert!(parser.parse("{|int: 4|}").is_err());
-    assert!(parser.parse("{4: thing}").is_err());
-    assert!(parser.parse("unclosed: curly}").is_err());
-    assert!(parser.parse("{one: two three: four}").is_err());
-    assert!(parser.parse("{a: 2, a: 3}").is_err());
+    assert!(parser.parse(0, Lexer::new(0, "{}")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "{||}")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "{super(pub): 4}")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "{int: 4}")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "{|int: 4|}")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "{4: thing}")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "unclosed: curly}")).is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "{one: two three: four}"))
+        .is_err());
+    assert!(parser.parse(0, Lexer::new(0, "{a: 2, a: 3}")).is_err());
 }
 
 #[test]
 fn test_parse_identifier() {
     let parser = grammar::StatementParser::new();
 
-    assert!(parse(&parser, "x") == Identifier("x".to_string(), None));
-    assert!(parse(&parser, "identif") == Identifier("identif".to_string(), None));
-    assert!(parse(&parser, "hElO_") == Identifier("hElO_".to_string(), None));
-    assert!(parse(&parser, "_a0001") == Identifier("_a0001".to_string(), None));
-    assert!(parse(&parser, "Hello") == Identifier("Hello".to_string(), None));
-    assert!(parse(&parser, "__Option") == Identifier("__Option".to_string(), None));
-    assert!(parse(&parser, "Ty6_Var68__iant_") == Identifier("Ty6_Var68__iant_".to_string(), None));
-    assert!(parse(&parser, "___01") == Identifier("___01".to_string(), None));
-    assert!(parse(&parser, "___") == Identifier("___".to_string(), None));
-    assert!(parse(&parser, "(<)") == BinaryOp(ast::BinaryOp::Lt, None));
-
-    assert!(parse(&parser, "(+)") == BinaryOp(ast::BinaryOp::Add, None));
-    assert!(parse(&parser, "(+.)") == BinaryOp(ast::BinaryOp::FlAdd, None));
-    assert!(parse(&parser, "(-)") == BinaryOp(ast::BinaryOp::Sub, None));
-    assert!(parse(&parser, "(-.)") == BinaryOp(ast::BinaryOp::FlSub, None));
-    assert!(parse(&parser, "(*)") == BinaryOp(ast::BinaryOp::Mult, None));
-    assert!(parse(&parser, "(*.)") == BinaryOp(ast::BinaryOp::FlMult, None));
-    assert!(parse(&parser, "(/)") == BinaryOp(ast::BinaryOp::FloorDiv, None));
-    assert!(parse(&parser, "(/.)") == BinaryOp(ast::BinaryOp::Div, None));
-
-    assert!(parser.parse("string").is_err());
-    assert!(parser.parse("with").is_err());
-    assert!(parser.parse("int").is_err());
-    assert!(parser.parse("<").is_err());
-    assert!(parser.parse("(-").is_err());
-    assert!(parser.parse("a*").is_err());
-    assert!(parser.parse("//)").is_err());
-    assert!(parser.parse("/.)").is_err());
-    assert!(parser.parse("yel⏰o").is_err());
-    assert!(parser.parse("31232abcd").is_err());
-    assert!(parser.parse("Hel)lo").is_err());
-    assert!(parser.parse("31232_AA").is_err());
-    assert!(parser.parse("_Yel⏰o").is_err());
-    assert!(parser.parse("aபாதை").is_err());
+    assert!(parse(&parser, "x") == Identifier("x".to_string(), Span::dummy()));
+    assert!(parse(&parser, "identif") == Identifier("identif".to_string(), Span::dummy()));
+    assert!(parse(&parser, "hElO_") == Identifier("hElO_".to_string(), Span::dummy()));
+    assert!(parse(&parser, "_a0001") == Identifier("_a0001".to_string(), Span::dummy()));
+    assert!(parse(&parser, "Hello") == Identifier("Hello".to_string(), Span::dummy()));
+    assert!(parse(&parser, "__Option") == Identifier("__Option".to_string(), Span::dummy()));
+    assert!(
+        parse(&parser, "Ty6_Var68__iant_")
+            == Identifier("Ty6_Var68__iant_".to_string(), Span::dummy())
+    );
+    assert!(parse(&parser, "___01") == Identifier("___01".to_string(), Span::dummy()));
+    assert!(parse(&parser, "___") == Identifier("___".to_string(), Span::dummy()));
+    assert!(parse(&parser, "(<)") == BinaryOp(ast::BinaryOp::Lt, Span::dummy()));
+
+    assert!(parse(&parser, "(+)") == BinaryOp(ast::BinaryOp::Add, Span::dummy()));
+    assert!(parse(&parser, "(+.)") == BinaryOp(ast::BinaryOp::FlAdd, Span::dummy()));
+    assert!(parse(&parser, "(-)") == BinaryOp(ast::BinaryOp::Sub, Span::dummy()));
+    assert!(parse(&parser, "(-.)") == BinaryOp(ast::BinaryOp::FlSub, Span::dummy()));
+    assert!(parse(&parser, "(*)") == BinaryOp(ast::BinaryOp::Mult, Span::dummy()));
+    assert!(parse(&parser, "(*.)") == BinaryOp(ast::BinaryOp::FlMult, Span::dummy()));
+    assert!(parse(&parser, "(/)") == BinaryOp(ast::BinaryOp::FloorDiv, Span::dummy()));
+    assert!(parse(&parser, "(/.)") == BinaryOp(ast::BinaryOp::Div, Span::dummy()));
+
+    assert!(parser.parse(0, Lexer::new(0, "string")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "with")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "int")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "<")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "(-")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "a*")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "//)")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "/.)")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "yel⏰o")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "31232abcd")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "Hel)lo")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "31232_AA")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "_Yel⏰o")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "aபாதை")).is_err());
 }
 
 #[test]
@@ -296,18 +435,18 @@ fn test_parse_enum_variant() {
     assert!(
         parse(&parser, "Card.King")
             == Projection(
-                Box::new(Identifier("Card".to_string(), None)),
+                Box::new(Identifier("Card".to_string(), Span::dummy())),
                 "King".to_string(),
-                None
+                Span::dummy()
             )
     );
 
     assert!(
         parse(&parser, "Card . King")
             == Projection(
-                Box::new(Identifier("Card".to_string(), None)),
+                Box::new(Identifier("Card".to_string(), Span::dummy())),
                 "King".to_string(),
-                None
+                Span::dummy()
             )
     );
 
@@ -315,12 +454,12 @@ fn test_parse_enum_variant() {
         parse(&parser, "a.b.c")
             == Projection(
                 Box::new(Projection(
-                    Box::new(Identifier("a".to_string(), None)),
+                    Box::new(Identifier("a".to_string(), Span::dummy())),
                     "b".to_string(),
-                    None
+                    Span::dummy()
                 )),
                 "c".to_string(),
-                None,
+                Span::dummy(),
             )
     );
 
@@ -329,32 +468,36 @@ fn test_parse_enum_variant() {
             == EnumVariant {
                 enum_id: "Option".to_string(),
                 variant: "Some".to_string(),
-                field: Box::new(IntLiteral(4, None)),
-                span: None
+                field: Box::new(IntLiteral(4, Span::dummy())),
+                span: Span::dummy()
             }
     );
 
     assert!(
         parse(&parser, "hello. there")
             == Projection(
-                Box::new(Identifier("hello".to_string(), None)),
+                Box::new(Identifier("hello".to_string(), Span::dummy())),
                 "there".to_string(),
-                None,
+                Span::dummy(),
             )
     );
 
-    assert!(parser.parse("(hello).there").is_err());
-    assert!(parser.parse("(hello foo). there").is_err());
-    assert!(parser.parse("hi with 4").is_err());
-    assert!(parser.parse("Option.Some.Other with 3").is_err());
+    assert!(parser.parse(0, Lexer::new(0, "(hello).there")).is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "(hello foo). there"))
+        .is_err());
+    assert!(parser.parse(0, Lexer::new(0, "hi with 4")).is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "Option.Some.Other with 3"))
+        .is_err());
 
     assert!(
         parse(&parser, "Option .Some with 4")
             == EnumVariant {
                 enum_id: "Option".to_string(),
                 variant: "Some".to_string(),
-                field: Box::new(IntLiteral(4, None)),
-                span: None
+                field: Box::new(IntLiteral(4, Span::dummy())),
+                span: Span::dummy()
             }
     );
 
@@ -363,8 +506,8 @@ fn test_parse_enum_variant() {
             == EnumVariant {
                 enum_id: "Thing".to_string(),
                 variant: "thing".to_string(),
-                field: Box::new(IntLiteral(3, None)),
-                span: None,
+                field: Box::new(IntLiteral(3, Span::dummy())),
+                span: Span::dummy(),
             }
     );
 
@@ -379,38 +522,38 @@ fn test_parse_enum_variant() {
         ) == EnumVariant {
             enum_id: "Tree".to_string(),
             variant: "Node".to_string(),
-            span: None,
+            span: Span::dummy(),
             field: Box::new(Tuple(
                 vec![
                     EnumVariant {
                         enum_id: "Tree".to_string(),
                         variant: "Node".to_string(),
-                        span: None,
+                        span: Span::dummy(),
                         field: Box::new(Tuple(
                             vec![
                                 Projection(
-                                    Box::new(Identifier("Tree".to_string(), None)),
+                                    Box::new(Identifier("Tree".to_string(), Span::dummy())),
                                     "Leaf".to_string(),
-                                    None
+                                    Span::dummy()
                                 ),
                                 Projection(
-                                    Box::new(Identifier("Tree".to_string(), None)),
+                                    Box::new(Identifier("Tree".to_string(), Span::dummy())),
                                     "Leaf".to_string(),
-                                    None
+                                    Span::dummy()
                                 ),
-                                FloatLiteral(to_of64(-2.5), None)
+                                FloatLiteral(to_of64(-2.5), Span::dummy())
                             ],
-                            None
+                            Span::dummy()
                         ))
                     },
                     Projection(
-                        Box::new(Identifier("Tree".to_string(), None)),
+                        Box::new(Identifier("Tree".to_string(), Span::dummy())),
                         "Leaf".to_string(),
-                        None
+                        Span::dummy()
                     ),
-                    IntLiteral(7, None),
+                    IntLiteral(7, Span::dummy()),
                 ],
-                None
+                Span::dummy()
             ))
         }
     );
@@ -419,13 +562,13 @@ fn test_parse_enum_variant() {
             == EnumVariant {
                 enum_id: "Listy".to_string(),
                 variant: "Listy".to_string(),
-                span: None,
+                span: Span::dummy(),
                 field: Box::new(List(
                     vec![
-                        IntLiteral(1, None),
-                        StringLiteral("hell⏰".to_string(), None),
+                        IntLiteral(1, Span::dummy()),
+                        StringLiteral("hell⏰".to_string(), Span::dummy()),
                     ],
-                    None
+                    Span::dummy()
                 ))
             }
     );
@@ -434,64 +577,74 @@ fn test_parse_enum_variant() {
             == EnumVariant {
                 enum_id: "Tupy".to_string(),
                 variant: "MaybeTuple".to_string(),
-                span: None,
-                field: Box::new(FloatLiteral(to_of64(-5.2), None))
+                span: Span::dummy(),
+                field: Box::new(FloatLiteral(to_of64(-5.2), Span::dummy()))
             }
     );
 
     // Missing parenthesis
     assert!(parser
         .parse(
-            "Tree.Node with (
+            0,
+            Lexer::new(
+                0,
+                "Tree.Node with (
             (Tree.Node with (Tree.Leaf, Tree.Leaf, -2.5),
             Tree.Leaf,
             7
         )"
+            )
         )
         .is_err());
     // No projection
-    assert!(parser.parse("Listy with [1, \"hell⏰\"])").is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "Listy with [1, \"hell⏰\"])"))
+        .is_err());
     // Trailing unmatched parenthesis
-    assert!(parser.parse("Listy.Listy with [1, \"hell⏰\"])").is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "Listy.Listy with [1, \"hell⏰\"])"))
+        .is_err());
 
     assert!(
         parse(&parser, "x.y")
             == Projection(
-                Box::new(Identifier("x".to_string(), None)),
+                Box::new(Identifier("x".to_string(), Span::dummy())),
                 "y".to_string(),
-                None
+                Span::dummy()
             )
     );
-    assert!(parser.parse("0xy.var").is_err());
+    assert!(parser.parse(0, Lexer::new(0, "0xy.var")).is_err());
     assert!(
         parse(&parser, "xy0.__xy")
             == Projection(
-                Box::new(Identifier("xy0".to_string(), None)),
+                Box::new(Identifier("xy0".to_string(), Span::dummy())),
                 "__xy".to_string(),
-                None
+                Span::dummy()
             )
     );
     assert!(
         parse(&parser, "__9._a5")
             == Projection(
-                Box::new(Identifier("__9".to_string(), None)),
+                Box::new(Identifier("__9".to_string(), Span::dummy())),
                 "_a5".to_string(),
-                None
+                Span::dummy()
             )
     );
 
-    assert!(parser.parse("xs*+bruh").is_err());
-    assert!(parser.parse("x.8").is_err());
-    assert!(parser.parse("Yu.p with [8, 78").is_err());
-    assert!(parser.parse("Option.Some int").is_err());
-    assert!(parser.parse("He)i.k with 4").is_err());
+    assert!(parser.parse(0, Lexer::new(0, "xs * + bruh")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "x.8")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "Yu.p with [8, 78")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "Option.Some int")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "He)i.k with 4")).is_err());
     // Tokens should have at least a space between them
     // Collect spans to check collision
-    assert!(parser.parse("(a_9.u8)with \"hi\"").is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "(a_9.u8)with \"hi\""))
+        .is_err());
     assert!(matches!(
-        parser.parse("  thingy.thing with\"hi\"").err().unwrap(),
+        parser.parse(0, Lexer::new(0, "  thingy.thing with\"hi\"")).err().unwrap(),
         ParseError::User { error: (e, s) }
-        if e.contains("Space required") && s == Span::new(15, 23)
+        if e.contains("Space required") && s == Span::new(0, 15, 23)
     ));
 }
 
@@ -502,12 +655,20 @@ fn test_parse_func_application() {
     assert!(
         parse(&parser, "hello a=4.5 br=8")
             == NamedArgsFuncApp(
-                Box::new(Identifier("hello".to_string(), None)),
+                Box::new(Identifier("hello".to_string(), Span::dummy())),
                 vec![
-                    ("a".to_string(), FloatLiteral(to_of64(4.5), None), None),
-                    ("br".to_string(), IntLiteral(8, None), None)
+                    (
+                        "a".to_string(),
+                        FloatLiteral(to_of64(4.5), Span::dummy()),
+                        Span::dummy()
+                    ),
+                    (
+                        "br".to_string(),
+                        IntLiteral(8, Span::dummy()),
+                        Span::dummy()
+                    )
                 ],
-                None
+                Span::dummy()
             )
     );
 
@@ -515,80 +676,88 @@ fn test_parse_func_application() {
         parse(&parser, "X.Y 3")
             == FuncApplication(
                 Box::new(Projection(
-                    Box::new(Identifier("X".to_string(), None)),
+                    Box::new(Identifier("X".to_string(), Span::dummy())),
                     "Y".to_string(),
-                    None,
+                    Span::dummy(),
                 )),
-                vec![IntLiteral(3, None)],
-                None
+                vec![IntLiteral(3, Span::dummy())],
+                Span::dummy()
             )
     );
 
     assert!(
         parse(&parser, "f g")
             == FuncApplication(
-                Box::new(Identifier("f".to_string(), None)),
-                vec![Identifier("g".to_string(), None)],
-                None
+                Box::new(Identifier("f".to_string(), Span::dummy())),
+                vec![Identifier("g".to_string(), Span::dummy())],
+                Span::dummy()
             )
     );
 
     assert!(
         parse(&parser, "f -7.9")
             == FuncApplication(
-                Box::new(Identifier("f".to_string(), None)),
-                vec![FloatLiteral(to_of64(-7.9), None)],
-                None
+                Box::new(Identifier("f".to_string(), Span::dummy())),
+                vec![FloatLiteral(to_of64(-7.9), Span::dummy())],
+                Span::dummy()
             )
     );
 
     assert!(
         parse(&parser, "f -4 2")
             == FuncApplication(
-                Box::new(Identifier("f".to_string(), None)),
-                vec![IntLiteral(-4, None), IntLiteral(2, None)],
-                None
+                Box::new(Identifier("f".to_string(), Span::dummy())),
+                vec![IntLiteral(-4, Span::dummy()), IntLiteral(2, Span::dummy())],
+                Span::dummy()
             )
     );
 
     assert!(
         parse(&parser, "(g 5)")
             == FuncApplication(
-                Box::new(Identifier("g".to_string(), None)),
-                vec![IntLiteral(5, None)],
-                None
+                Box::new(Identifier("g".to_string(), Span::dummy())),
+                vec![IntLiteral(5, Span::dummy())],
+                Span::dummy()
             )
     );
 
     assert!(
         parser
-            .parse("(g 4 \"hi\" (f bruh = 2))")
+            .parse(0, Lexer::new(0, "(g 4 \"hi\" (f bruh = 2))"))
             .unwrap()
             .unspanned()
             == Expression(FuncApplication(
-                Box::new(Identifier("g".to_string(), None)),
+                Box::new(Identifier("g".to_string(), Span::dummy())),
                 vec![
-                    IntLiteral(4, None),
-                    StringLiteral("hi".to_string(), None),
+                    IntLiteral(4, Span::dummy()),
+                    StringLiteral("hi".to_string(), Span::dummy()),
                     NamedArgsFuncApp(
-                        Box::new(Identifier("f".to_string(), None)),
-                        vec![("bruh".to_string(), IntLiteral(2, None), None),],
-                        None,
+                        Box::new(Identifier("f".to_string(), Span::dummy())),
+                        vec![(
+                            "bruh".to_string(),
+                            IntLiteral(2, Span::dummy()),
+                            Span::dummy()
+                        ),],
+                        Span::dummy(),
                     )
                 ],
-                None
+                Span::dummy()
             ))
     );
     assert!(
         parse(&parser, "print 4")
-            == FuncApplication(Box::new(Print(None)), vec![IntLiteral(4, None)], None,)
+            == FuncApplication(
+                Box::new(Print(Span::dummy())),
+                vec![IntLiteral(4, Span::dummy())],
+                Span::dummy(),
+            )
     );
     assert!(
         parse(&parser, "(+) 4")
             == FuncApplication(
-                Box::new(BinaryOp(ast::BinaryOp::Add, None)),
-                vec![IntLiteral(4, None)],
-                None,
+                Box::new(BinaryOp(ast::BinaryOp::Add, Span::dummy())),
+                vec![IntLiteral(4, Span::dummy())],
+                Span::dummy(),
             )
     );
 
@@ -596,84 +765,88 @@ fn test_parse_func_application() {
         parse(&parser, "(f 2) (g 3)")
             == FuncApplication(
                 Box::new(FuncApplication(
-                    Box::new(Identifier("f".to_string(), None)),
-                    vec![IntLiteral(2, None)],
-                    None,
+                    Box::new(Identifier("f".to_string(), Span::dummy())),
+                    vec![IntLiteral(2, Span::dummy())],
+                    Span::dummy(),
                 )),
                 vec![FuncApplication(
-                    Box::new(Identifier("g".to_string(), None)),
-                    vec![IntLiteral(3, None)],
-                    None,
+                    Box::new(Identifier("g".to_string(), Span::dummy())),
+                    vec![IntLiteral(3, Span::dummy())],
+                    Span::dummy(),
                 )],
-                None
+                Span::dummy()
             )
     );
 
     assert!(
         parser
-            .parse("[f g.e.t, x.y -5.6, Option.Some with 4]")
+            .parse(0, Lexer::new(0, "[f g.e.t, x.y -5.6, Option.Some with 4]"))
             .unwrap()
             .unspanned()
             == Expression(List(
                 vec![
                     FuncApplication(
-                        Box::new(Identifier("f".to_string(), None)),
+                        Box::new(Identifier("f".to_string(), Span::dummy())),
                         vec![Projection(
                             Box::new(Projection(
-                                Box::new(Identifier("g".to_string(), None)),
+                                Box::new(Identifier("g".to_string(), Span::dummy())),
                                 "e".to_string(),
-                                None,
+                                Span::dummy(),
                             )),
                             "t".to_string(),
-                            None
+                            Span::dummy()
                         )],
-                        None,
+                        Span::dummy(),
                     ),
                     FuncApplication(
                         Box::new(Projection(
-                            Box::new(Identifier("x".to_string(), None)),
+                            Box::new(Identifier("x".to_string(), Span::dummy())),
                             "y".to_string(),
-                            None,
+                            Span::dummy(),
                         )),
-                        vec![FloatLiteral(to_of64(-5.6), None)],
-                        None
+                        vec![FloatLiteral(to_of64(-5.6), Span::dummy())],
+                        Span::dummy()
                     ),
                     EnumVariant {
                         enum_id: "Option".to_string(),
                         variant: "Some".to_string(),
-                        field: Box::new(IntLiteral(4, None)),
-                        span: None
+                        field: Box::new(IntLiteral(4, Span::dummy())),
+                        span: Span::dummy()
                     }
                 ],
-                None
+                Span::dummy()
             ))
     );
 
-    assert!(parser.parse("f Option.Some with 4").is_err());
-    assert!(parser.parse("f (Option.Some with 4)").is_ok());
+    assert!(parser
+        .parse(0, Lexer::new(0, "f Option.Some with 4"))
+        .is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "f (Option.Some with 4)"))
+        .is_ok());
     // As far as parsing is concerned, this is not an error.
     assert!(
         parse(&parser, "4 g")
             == FuncApplication(
-                Box::new(IntLiteral(4, None)),
-                vec![Identifier("g".to_string(), None)],
-                None,
+                Box::new(IntLiteral(4, Span::dummy())),
+                vec![Identifier("g".to_string(), Span::dummy())],
+                Span::dummy(),
             )
     );
-    assert!(parser.parse("f(8)").is_err());
-    assert!(parser.parse("f(g=8)").is_err());
-    assert!(parser.parse("f (g=8)").is_err());
+    assert!(parser.parse(0, Lexer::new(0, "f(8)")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "f(g=8)")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "f (g=8)")).is_err());
     assert!(
         parse(&parser, "f g=(8)")
             == NamedArgsFuncApp(
-                Box::new(Identifier("f".to_string(), None)),
-                vec![("g".to_string(), IntLiteral(8, None), None)],
-                None,
+                Box::new(Identifier("f".to_string(), Span::dummy())),
+                vec![("g".to_string(), IntLiteral(8, Span::dummy()), Span::dummy())],
+                Span::dummy(),
             )
     );
-    assert!(parser.parse("f.g=3").is_err());
-    assert!(parser.parse("f g=").is_err());
-    assert!(parser.parse("f\"hi\" 3").is_err());
+    assert!(parser.parse(0, Lexer::new(0, "f.g=3")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "f g=")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "f\"hi\" 3")).is_err());
 }
 
 #[test]
@@ -683,168 +856,341 @@ fn test_parse_infix_binary_op() {
     assert!(
         parse(&parser, "4 + 5 * 6")
             == FuncApplication(
-                Box::new(BinaryOp(ast::BinaryOp::Add, None)),
+                Box::new(BinaryOp(ast::BinaryOp::Add, Span::dummy())),
                 vec![
-                    IntLiteral(4, None),
+                    IntLiteral(4, Span::dummy()),
                     FuncApplication(
-                        Box::new(BinaryOp(ast::BinaryOp::Mult, None)),
-                        vec![IntLiteral(5, None), IntLiteral(6, None)],
-                        None
+                        Box::new(BinaryOp(ast::BinaryOp::Mult, Span::dummy())),
+                        vec![IntLiteral(5, Span::dummy()), IntLiteral(6, Span::dummy())],
+                        Span::dummy()
                     )
                 ],
-                None
+                Span::dummy()
             )
     );
-    assert!(parser.parse("* 3 4").is_err());
+    assert!(parser.parse(0, Lexer::new(0, "* 3 4")).is_err());
     assert!(
         parse(&parser, "(*) 3 4")
             == FuncApplication(
-                Box::new(BinaryOp(ast::BinaryOp::Mult, None)),
-                vec![IntLiteral(3, None), IntLiteral(4, None)],
-                None,
+                Box::new(BinaryOp(ast::BinaryOp::Mult, Span::dummy())),
+                vec![IntLiteral(3, Span::dummy()), IntLiteral(4, Span::dummy())],
+                Span::dummy(),
             )
     );
     assert!(
         parse(&parser, "1 * 5 + 2 / 4")
             == FuncApplication(
-                Box::new(BinaryOp(ast::BinaryOp::Add, None)),
+                Box::new(BinaryOp(ast::BinaryOp::Add, Span::dummy())),
                 vec![
                     FuncApplication(
-                        Box::new(BinaryOp(ast::BinaryOp::Mult, None)),
-                        vec![IntLiteral(1, None), IntLiteral(5, None),],
-                        None,
+                        Box::new(BinaryOp(ast::BinaryOp::Mult, Span::dummy())),
+                        vec![IntLiteral(1, Span::dummy()), IntLiteral(5, Span::dummy()),],
+                        Span::dummy(),
                     ),
                     FuncApplication(
-                        Box::new(BinaryOp(ast::BinaryOp::FloorDiv, None)),
-                        vec![IntLiteral(2, None), IntLiteral(4, None),],
-                        None
+                        Box::new(BinaryOp(ast::BinaryOp::FloorDiv, Span::dummy())),
+                        vec![IntLiteral(2, Span::dummy()), IntLiteral(4, Span::dummy()),],
+                        Span::dummy()
                     )
                 ],
-                None
+                Span::dummy()
             )
     );
 
     assert!(
         parse(&parser, "1.5 == 2.5 == 3.5")
             == FuncApplication(
-                Box::new(BinaryOp(ast::BinaryOp::Eq, None)),
+                Box::new(BinaryOp(ast::BinaryOp::Eq, Span::dummy())),
                 vec![
                     FuncApplication(
-                        Box::new(BinaryOp(ast::BinaryOp::Eq, None)),
+                        Box::new(BinaryOp(ast::BinaryOp::Eq, Span::dummy())),
                         vec![
-                            FloatLiteral(to_of64(1.5), None),
-                            FloatLiteral(to_of64(2.5), None),
+                            FloatLiteral(to_of64(1.5), Span::dummy()),
+                            FloatLiteral(to_of64(2.5), Span::dummy()),
                         ],
-                        None
+                        Span::dummy()
                     ),
-                    FloatLiteral(to_of64(3.5), None)
+                    FloatLiteral(to_of64(3.5), Span::dummy())
                 ],
-                None
+                Span::dummy()
             )
     );
 
     assert!(
         parse(&parser, "(l1 :: l2) * 5")
             == FuncApplication(
-                Box::new(BinaryOp(ast::BinaryOp::Mult, None)),
+                Box::new(BinaryOp(ast::BinaryOp::Mult, Span::dummy())),
                 vec![
                     FuncApplication(
-                        Box::new(BinaryOp(ast::BinaryOp::Cons, None)),
+                        Box::new(BinaryOp(ast::BinaryOp::Cons, Span::dummy())),
                         vec![
-                            Identifier("l1".to_string(), None),
-                            Identifier("l2".to_string(), None),
+                            Identifier("l1".to_string(), Span::dummy()),
+                            Identifier("l2".to_string(), Span::dummy()),
                         ],
-                        None,
+                        Span::dummy(),
                     ),
-                    IntLiteral(5, None),
+                    IntLiteral(5, Span::dummy()),
                 ],
-                None,
+                Span::dummy(),
             )
     );
 
     assert!(
         parse(&parser, "a < b + 5")
             == FuncApplication(
-                Box::new(BinaryOp(ast::BinaryOp::Lt, None)),
+                Box::new(BinaryOp(ast::BinaryOp::Lt, Span::dummy())),
                 vec![
-                    Identifier("a".to_string(), None),
+                    Identifier("a".to_string(), Span::dummy()),
                     FuncApplication(
-                        Box::new(BinaryOp(ast::BinaryOp::Add, None)),
-                        vec![Identifier("b".to_string(), None), IntLiteral(5, None),],
-                        None,
+                        Box::new(BinaryOp(ast::BinaryOp::Add, Span::dummy())),
+                        vec![
+                            Identifier("b".to_string(), Span::dummy()),
+                            IntLiteral(5, Span::dummy()),
+                        ],
+                        Span::dummy(),
                     ),
                 ],
-                None,
+                Span::dummy(),
             )
     );
     assert!(
         parse(&parser, "(a >= b) + 5")
             == FuncApplication(
-                Box::new(BinaryOp(ast::BinaryOp::Add, None)),
+                Box::new(BinaryOp(ast::BinaryOp::Add, Span::dummy())),
                 vec![
                     FuncApplication(
-                        Box::new(BinaryOp(ast::BinaryOp::Geq, None)),
+                        Box::new(BinaryOp(ast::BinaryOp::Geq, Span::dummy())),
                         vec![
-                            Identifier("a".to_string(), None),
-                            Identifier("b".to_string(), None),
+                            Identifier("a".to_string(), Span::dummy()),
+                            Identifier("b".to_string(), Span::dummy()),
                         ],
-                        None,
+                        Span::dummy(),
                     ),
-                    IntLiteral(5, None),
+                    IntLiteral(5, Span::dummy()),
                 ],
-                None,
+                Span::dummy(),
             )
     );
 
-    assert!(parse(&parser, "(<=)") == BinaryOp(ast::BinaryOp::Leq, None));
+    assert!(parse(&parser, "(<=)") == BinaryOp(ast::BinaryOp::Leq, Span::dummy()));
     assert!(
         parse(&parser, "(/) 4")
             == FuncApplication(
-                Box::new(BinaryOp(ast::BinaryOp::FloorDiv, None)),
-                vec![IntLiteral(4, None)],
-                None,
+                Box::new(BinaryOp(ast::BinaryOp::FloorDiv, Span::dummy())),
+                vec![IntLiteral(4, Span::dummy())],
+                Span::dummy(),
             )
     );
     assert!(
         parse(&parser, "((::) 4) - ((-) -6 \"hi\")")
             == FuncApplication(
-                Box::new(BinaryOp(ast::BinaryOp::Sub, None)),
+                Box::new(BinaryOp(ast::BinaryOp::Sub, Span::dummy())),
                 vec![
                     FuncApplication(
-                        Box::new(BinaryOp(ast::BinaryOp::Cons, None)),
-                        vec![IntLiteral(4, None)],
-                        None,
+                        Box::new(BinaryOp(ast::BinaryOp::Cons, Span::dummy())),
+                        vec![IntLiteral(4, Span::dummy())],
+                        Span::dummy(),
                     ),
                     FuncApplication(
-                        Box::new(BinaryOp(ast::BinaryOp::Sub, None)),
-                        vec![IntLiteral(-6, None), StringLiteral("hi".to_string(), None),],
-                        None,
+                        Box::new(BinaryOp(ast::BinaryOp::Sub, Span::dummy())),
+                        vec![
+                            IntLiteral(-6, Span::dummy()),
+                            StringLiteral("hi".to_string(), Span::dummy()),
+                        ],
+                        Span::dummy(),
                     ),
                 ],
-                None,
+                Span::dummy(),
             )
     );
 
     assert!(
         parse(&parser, "(::) 4  ((-) -6 \"hi\")")
             == FuncApplication(
-                Box::new(BinaryOp(ast::BinaryOp::Cons, None)),
+                Box::new(BinaryOp(ast::BinaryOp::Cons, Span::dummy())),
                 vec![
-                    IntLiteral(4, None),
+                    IntLiteral(4, Span::dummy()),
                     FuncApplication(
-                        Box::new(BinaryOp(ast::BinaryOp::Sub, None)),
-                        vec![IntLiteral(-6, None), StringLiteral("hi".to_string(), None),],
-                        None,
+                        Box::new(BinaryOp(ast::BinaryOp::Sub, Span::dummy())),
+                        vec![
+                            IntLiteral(-6, Span::dummy()),
+                            StringLiteral("hi".to_string(), Span::dummy()),
+                        ],
+                        Span::dummy(),
                     ),
                 ],
+                Span::dummy(),
+            )
+    );
+
+    // Function application binds tighter than any operator
+    assert!(
+        parse(&parser, "(::) 4 - ((-) -6 \"hi\")") == parse(&parser, "((::) 4) - ((-) -6 \"hi\")")
+    );
+    assert!(parse(&parser, "a + f b") == parse(&parser, "a + (f b)"));
+    assert!(parse(&parser, "pam f a :: lst") == parse(&parser, "(pam f a) :: lst"));
+    assert!(parser.parse(0, Lexer::new(0, "1 >")).is_err());
+
+    // Cons associates to the right
+    assert!(parse(&parser, "1 :: 2 :: []") == parse(&parser, "1 :: (2 :: [])"));
+    assert!(parse(&parser, "x :: f y :: xs") == parse(&parser, "x :: ((f y) :: xs)"));
+    assert!(parse(&parser, "a + 1 :: b") == parse(&parser, "(a + 1) :: b"));
+    assert!(
+        parser.parse(0, Lexer::new(0, "a < b :: c"))
+            == Err(ParseError::User {
+                error: (
+                    "Cannot mix < (infixl 4) and :: (infixr 4) in the same infix expression without parentheses"
+                        .to_string(),
+                    Span::new(0, 6, 8)
+                )
+            })
+    );
+}
+
+#[test]
+fn test_parse_user_operators() {
+    let parser = grammar::StatementParser::new();
+    let id = |name: &str| Identifier(name.to_string(), Span::dummy());
+
+    // Chains with operators defined by the program are left unresolved
+    assert!(
+        parse(&parser, "a <+> b * c")
+            == InfixChain(
+                vec![
+                    (id("a"), Span::dummy()),
+                    (id("b"), Span::dummy()),
+                    (id("c"), Span::dummy())
+                ],
+                vec![
+                    (id("<+>"), Span::dummy()),
+                    (BinaryOp(ast::BinaryOp::Mult, Span::dummy()), Span::dummy())
+                ],
+                Span::dummy(),
+            )
+    );
+    assert!(parse(&parser, "(<+>)") == id("<+>"));
+    assert!(
+        parse(&parser, "(<+> 1)")
+            == Lambda {
+                arg: fixity::SECTION_ARG.to_string(),
+                expr: Box::new(FuncApplication(
+                    Box::new(id("<+>")),
+                    vec![id(fixity::SECTION_ARG), IntLiteral(1, Span::dummy())],
+                    Span::dummy()
+                )),
+                span: Span::dummy(),
+            }
+    );
+    assert!(
+        parse(&parser, "(1 + 2 <+>)")
+            == FuncApplication(
+                Box::new(id("<+>")),
+                vec![FuncApplication(
+                    Box::new(BinaryOp(ast::BinaryOp::Add, Span::dummy())),
+                    vec![IntLiteral(1, Span::dummy()), IntLiteral(2, Span::dummy())],
+                    Span::dummy()
+                )],
+                Span::dummy()
+            )
+    );
+    assert!(
+        parse(&parser, "let (<+>) a b = a")
+            == Let(
+                VarWithValue {
+                    name: ("<+>".to_string(), Span::dummy()),
+                    args: vec![
+                        ("a".to_string(), None, Span::dummy()),
+                        ("b".to_string(), None, Span::dummy())
+                    ],
+                    output_type: None,
+                    rec: false,
+                    expr: Box::new(id("a")),
+                },
                 None,
+                Span::dummy()
+            )
+    );
+
+    assert!(parser.parse(0, Lexer::new(0, "a <+>")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "(<+> <+>)")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "let <+> a b = a")).is_err());
+}
+
+#[test]
+fn test_parse_pipeline() {
+    let parser = grammar::StatementParser::new();
+    let id = |name: &str| Identifier(name.to_string(), Span::dummy());
+    let app = |op: ast::BinaryOp, lhs, rhs| {
+        FuncApplication(
+            Box::new(BinaryOp(op, Span::dummy())),
+            vec![lhs, rhs],
+            Span::dummy(),
+        )
+    };
+
+    // Composition binds tighter than the pipeline
+    assert!(
+        parse(&parser, "x |> f >> g")
+            == app(
+                ast::BinaryOp::Pipe,
+                id("x"),
+                app(ast::BinaryOp::ComposeForward, id("f"), id("g"))
+            )
+    );
+    assert!(
+        parse(&parser, "a + 1 |> f |> g")
+            == app(
+                ast::BinaryOp::Pipe,
+                app(
+                    ast::BinaryOp::Pipe,
+                    app(ast::BinaryOp::Add, id("a"), IntLiteral(1, Span::dummy())),
+                    id("f")
+                ),
+                id("g")
+            )
+    );
+    // Applied functions need no parentheses
+    let add_2 = FuncApplication(
+        Box::new(id("add")),
+        vec![IntLiteral(2, Span::dummy())],
+        Span::dummy(),
+    );
+    assert!(
+        parse(&parser, "1 |> add 2 >> g")
+            == app(
+                ast::BinaryOp::Pipe,
+                IntLiteral(1, Span::dummy()),
+                app(ast::BinaryOp::ComposeForward, add_2, id("g"))
+            )
+    );
+    assert!(parse(&parser, "xs |> map f |> g x y") == parse(&parser, "(xs |> (map f)) |> (g x y)"));
+    assert!(
+        parse(&parser, "f << g << h")
+            == app(
+                ast::BinaryOp::ComposeBackward,
+                id("f"),
+                app(ast::BinaryOp::ComposeBackward, id("g"), id("h"))
+            )
+    );
+    assert!(
+        parse(&parser, "(|>) x f")
+            == FuncApplication(
+                Box::new(BinaryOp(ast::BinaryOp::Pipe, Span::dummy())),
+                vec![id("x"), id("f")],
+                Span::dummy()
             )
     );
 
-    assert!(parser.parse("(::) 4 - ((-) -6 \"hi\")").is_err());
-    assert!(parser.parse("a + f b").is_err());
-    assert!(parser.parse("1 >").is_err());
-    assert!(parser.parse("pam f a :: lst").is_err());
+    assert!(
+        parser.parse(0, Lexer::new(0, "f >> g << h"))
+            == Err(ParseError::User {
+                error: (
+                    "Cannot mix >> (infixl 8) and << (infixr 8) in the same infix expression without parentheses"
+                        .to_string(),
+                    Span::new(0, 7, 9)
+                )
+            })
+    );
 }
 
 #[test]
@@ -855,77 +1201,88 @@ fn test_parse_let() {
         parse(&parser, "let x = 4")
             == Let(
                 VarWithValue {
-                    name: ("x".to_string(), None),
+                    name: ("x".to_string(), Span::dummy()),
                     args: vec![],
+                    output_type: None,
                     rec: false,
-                    expr: Box::new(IntLiteral(4, None))
+                    expr: Box::new(IntLiteral(4, Span::dummy()))
                 },
                 None,
-                None,
+                Span::dummy(),
             )
     );
     assert!(
         parse(&parser, "let y z = (x 4)")
             == Let(
                 VarWithValue {
-                    name: ("y".to_string(), None),
-                    args: vec![("z".to_string(), None)],
+                    name: ("y".to_string(), Span::dummy()),
+                    args: vec![("z".to_string(), None, Span::dummy())],
+                    output_type: None,
                     rec: false,
                     expr: Box::new(FuncApplication(
-                        Box::new(Identifier("x".to_string(), None)),
-                        vec![IntLiteral(4, None)],
-                        None
+                        Box::new(Identifier("x".to_string(), Span::dummy())),
+                        vec![IntLiteral(4, Span::dummy())],
+                        Span::dummy()
                     )),
                 },
                 None,
-                None,
+                Span::dummy(),
             )
     );
     assert!(
         parse(&parser, "let rec mu = 3")
             == Let(
                 VarWithValue {
-                    name: ("mu".to_string(), None),
+                    name: ("mu".to_string(), Span::dummy()),
                     args: vec![],
+                    output_type: None,
                     rec: true,
-                    expr: Box::new(IntLiteral(3, None))
+                    expr: Box::new(IntLiteral(3, Span::dummy()))
                 },
                 None,
-                None
+                Span::dummy()
             )
     );
     assert!(
         parse(&parser, "let x y = (+) y 4")
             == Let(
                 VarWithValue {
-                    name: ("x".to_string(), None),
-                    args: vec![("y".to_string(), None)],
+                    name: ("x".to_string(), Span::dummy()),
+                    args: vec![("y".to_string(), None, Span::dummy())],
+                    output_type: None,
                     rec: false,
                     expr: Box::new(FuncApplication(
-                        Box::new(BinaryOp(ast::BinaryOp::Add, None)),
-                        vec![Identifier("y".to_string(), None), IntLiteral(4, None),],
-                        None,
+                        Box::new(BinaryOp(ast::BinaryOp::Add, Span::dummy())),
+                        vec![
+                            Identifier("y".to_string(), Span::dummy()),
+                            IntLiteral(4, Span::dummy()),
+                        ],
+                        Span::dummy(),
                     ))
                 },
                 None,
-                None,
+                Span::dummy(),
             )
     );
     assert!(
         parse(&parser, "let plus_4 x = x + 4")
             == Let(
                 VarWithValue {
-                    name: ("plus_4".to_string(), None),
-                    args: vec![("x".to_string(), None)],
+                    name: ("plus_4".to_string(), Span::dummy()),
+                    args: vec![("x".to_string(), None, Span::dummy())],
+                    output_type: None,
                     rec: false,
                     expr: Box::new(FuncApplication(
-                        Box::new(BinaryOp(ast::BinaryOp::Add, None)),
-                        vec![Identifier("x".to_string(), None), IntLiteral(4, None),],
-                        None,
+                        Box::new(BinaryOp(ast::BinaryOp::Add, Span::dummy())),
+                        vec![
+                            Identifier("x".to_string(), Span::dummy()),
+                            IntLiteral(4, Span::dummy()),
+                        ],
+                        Span::dummy(),
                     ))
                 },
                 None,
-                None,
+                Span::dummy(),
             )
     );
 
@@ -933,17 +1290,21 @@ fn test_parse_let() {
         parse(&parser, "let x = 4 in x + 9")
             == Let(
                 VarWithValue {
-                    name: ("x".to_string(), None),
+                    name: ("x".to_string(), Span::dummy()),
                     args: vec![],
+                    output_type: None,
                     rec: false,
-                    expr: Box::new(IntLiteral(4, None)),
+                    expr: Box::new(IntLiteral(4, Span::dummy())),
                 },
                 Some(Box::new(FuncApplication(
-                    Box::new(BinaryOp(ast::BinaryOp::Add, None)),
-                    vec![Identifier("x".to_string(), None), IntLiteral(9, None),],
-                    None,
+                    Box::new(BinaryOp(ast::BinaryOp::Add, Span::dummy())),
+                    vec![
+                        Identifier("x".to_string(), Span::dummy()),
+                        IntLiteral(9, Span::dummy()),
+                    ],
+                    Span::dummy(),
                 ))),
-                None,
+                Span::dummy(),
             )
     );
 
@@ -951,20 +1312,27 @@ fn test_parse_let() {
         parse(&parser, "let x = 5 in { a: x, b: 8 }")
             == Let(
                 VarWithValue {
-                    name: ("x".to_string(), None),
+                    name: ("x".to_string(), Span::dummy()),
                     args: vec![],
+                    output_type: None,
                     rec: false,
-                    expr: Box::new(IntLiteral(5, None)),
+                    expr: Box::new(IntLiteral(5, Span::dummy())),
                 },
                 Some(Box::new(Record(
                     BTreeMap::from([
-                        ("a".to_string(), (Identifier("x".to_string(), None), None)),
-                        ("b".to_string(), (IntLiteral(8, None), None)),
+                        (
+                            "a".to_string(),
+                            (Identifier("x".to_string(), Span::dummy()), Span::dummy())
+                        ),
+                        (
+                            "b".to_string(),
+                            (IntLiteral(8, Span::dummy()), Span::dummy())
+                        ),
                     ]),
                     Flex::Permissive,
-                    None,
+                    Span::dummy(),
                 ))),
-                None,
+                Span::dummy(),
             )
     );
 
@@ -972,27 +1340,31 @@ fn test_parse_let() {
         parse(&parser, "let rec pam f lst = pam f (a :: lst)")
             == Let(
                 VarWithValue {
-                    name: ("pam".to_string(), None),
-                    args: vec![("f".to_string(), None), ("lst".to_string(), None),],
+                    name: ("pam".to_string(), Span::dummy()),
+                    args: vec![
+                        ("f".to_string(), None, Span::dummy()),
+                        ("lst".to_string(), None, Span::dummy()),
+                    ],
+                    output_type: None,
                     rec: true,
                     expr: Box::new(FuncApplication(
-                        Box::new(Identifier("pam".to_string(), None)),
+                        Box::new(Identifier("pam".to_string(), Span::dummy())),
                         vec![
-                            Identifier("f".to_string(), None),
+                            Identifier("f".to_string(), Span::dummy()),
                             FuncApplication(
-                                Box::new(BinaryOp(ast::BinaryOp::Cons, None)),
+                                Box::new(BinaryOp(ast::BinaryOp::Cons, Span::dummy())),
                                 vec![
-                                    Identifier("a".to_string(), None),
-                                    Identifier("lst".to_string(), None),
+                                    Identifier("a".to_string(), Span::dummy()),
+                                    Identifier("lst".to_string(), Span::dummy()),
                                 ],
-                                None,
+                                Span::dummy(),
                             )
                         ],
-                        None,
+                        Span::dummy(),
                     ))
                 },
                 None,
-                None,
+                Span::dummy(),
             )
     );
     // multi-let
@@ -1002,67 +1374,856 @@ fn test_parse_let() {
             "let x = 4 in let y z = x + 4 in let z = y in let x = a + 8 + b"
         ) == Let(
             VarWithValue {
-                name: ("x".to_string(), None),
+                name: ("x".to_string(), Span::dummy()),
                 args: vec![],
+                output_type: None,
                 rec: false,
-                expr: Box::new(IntLiteral(4, None)),
+                expr: Box::new(IntLiteral(4, Span::dummy())),
             },
             Some(Box::new(Let(
                 VarWithValue {
-                    name: ("y".to_string(), None),
-                    args: vec![("z".to_string(), None)],
+                    name: ("y".to_string(), Span::dummy()),
+                    args: vec![("z".to_string(), None, Span::dummy())],
+                    output_type: None,
                     rec: false,
                     expr: Box::new(FuncApplication(
-                        Box::new(BinaryOp(ast::BinaryOp::Add, None)),
-                        vec![Identifier("x".to_string(), None), IntLiteral(4, None),],
-                        None,
+                        Box::new(BinaryOp(ast::BinaryOp::Add, Span::dummy())),
+                        vec![
+                            Identifier("x".to_string(), Span::dummy()),
+                            IntLiteral(4, Span::dummy()),
+                        ],
+                        Span::dummy(),
                     ))
                 },
                 Some(Box::new(Let(
                     VarWithValue {
-                        name: ("z".to_string(), None),
+                        name: ("z".to_string(), Span::dummy()),
                         args: vec![],
+                        output_type: None,
                         rec: false,
-                        expr: Box::new(Identifier("y".to_string(), None))
+                        expr: Box::new(Identifier("y".to_string(), Span::dummy()))
                     },
                     Some(Box::new(Let(
                         VarWithValue {
-                            name: ("x".to_string(), None),
+                            name: ("x".to_string(), Span::dummy()),
                             args: vec![],
+                            output_type: None,
                             rec: false,
                             expr: Box::new(FuncApplication(
-                                Box::new(BinaryOp(ast::BinaryOp::Add, None)),
+                                Box::new(BinaryOp(ast::BinaryOp::Add, Span::dummy())),
                                 vec![
                                     FuncApplication(
-                                        Box::new(BinaryOp(ast::BinaryOp::Add, None)),
+                                        Box::new(BinaryOp(ast::BinaryOp::Add, Span::dummy())),
                                         vec![
-                                            Identifier("a".to_string(), None),
-                                            IntLiteral(8, None),
+                                            Identifier("a".to_string(), Span::dummy()),
+                                            IntLiteral(8, Span::dummy()),
                                         ],
-                                        None,
+                                        Span::dummy(),
                                     ),
-                                    Identifier("b".to_string(), None),
+                                    Identifier("b".to_string(), Span::dummy()),
                                 ],
-                                None,
+                                Span::dummy(),
                             ))
                         },
                         None,
-                        None,
+                        Span::dummy(),
                     ))),
-                    None,
+                    Span::dummy(),
                 ))),
-                None,
+                Span::dummy(),
             ))),
-            None,
+            Span::dummy(),
         )
     );
 
-    assert!(parser.parse("let x = 4 in x + 9 in 3").is_err());
-    assert!(parser.parse("let x y = ").is_err());
-    assert!(parser.parse("let x let = 5 + x").is_err());
-    assert!(parser.parse("let a = let b = 5").is_err());
-    assert!(parser.parse("let a = b = 5").is_err());
-    assert!(parser.parse("let func (x) y = 4").is_err());
-    assert!(parser.parse("let x = int").is_err());
-    assert!(parser.parse("let = 4").is_err());
+    // typed let
+    assert!(
+        parse(&parser, "let x: int = 4")
+            == Let(
+                VarWithValue {
+                    name: ("x".to_string(), Span::dummy()),
+                    args: vec![],
+                    output_type: Some(Type::Int),
+                    rec: false,
+                    expr: Box::new(IntLiteral(4, Span::dummy())),
+                },
+                None,
+                Span::dummy(),
+            )
+    );
+    assert!(
+        parse(&parser, "let f (a: int) -> (b: float) -> [int] = c")
+            == Let(
+                VarWithValue {
+                    name: ("f".to_string(), Span::dummy()),
+                    args: vec![
+                        ("a".to_string(), Some(Type::Int), Span::dummy()),
+                        ("b".to_string(), Some(Type::Float), Span::dummy()),
+                    ],
+                    output_type: Some(Type::List(Box::new(Type::Int))),
+                    rec: false,
+                    expr: Box::new(Identifier("c".to_string(), Span::dummy())),
+                },
+                None,
+                Span::dummy(),
+            )
+    );
+    assert!(
+        parse(&parser, "let rec id (x: 'a) -> 'a = x in id 4")
+            == Let(
+                VarWithValue {
+                    name: ("id".to_string(), Span::dummy()),
+                    args: vec![(
+                        "x".to_string(),
+                        Some(Type::Poly("a".to_string(), None)),
+                        Span::dummy()
+                    )],
+                    output_type: Some(Type::Poly("a".to_string(), None)),
+                    rec: true,
+                    expr: Box::new(Identifier("x".to_string(), Span::dummy())),
+                },
+                Some(Box::new(FuncApplication(
+                    Box::new(Identifier("id".to_string(), Span::dummy())),
+                    vec![IntLiteral(4, Span::dummy())],
+                    Span::dummy(),
+                ))),
+                Span::dummy(),
+            )
+    );
+    assert!(parser
+        .parse(0, Lexer::new(0, "let f (a: int) = a"))
+        .is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "let f a -> int = a"))
+        .is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "let f (a) -> int = a"))
+        .is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "let f (a: int) -> 4 = a"))
+        .is_err());
+    assert!(parser.parse(0, Lexer::new(0, "let x: = 4")).is_err());
+
+    assert!(parser
+        .parse(0, Lexer::new(0, "let x = 4 in x + 9 in 3"))
+        .is_err());
+    assert!(parser.parse(0, Lexer::new(0, "let x y = ")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "let x let = 5 + x")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "let a = let b = 5")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "let a = b = 5")).is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "let func (x) y = 4"))
+        .is_err());
+    assert!(parser.parse(0, Lexer::new(0, "let x = int")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "let = 4")).is_err());
+}
+
+#[test]
+fn test_parse_let_group() {
+    let parser = grammar::StatementParser::new();
+
+    let binding = |name: &str, arg: &str, rec: bool| VarWithValue {
+        name: (name.to_string(), Span::dummy()),
+        args: vec![(arg.to_string(), None, Span::dummy())],
+        output_type: None,
+        rec,
+        expr: Box::new(Identifier(arg.to_string(), Span::dummy())),
+    };
+    assert!(
+        parse(&parser, "let rec f x = x and g y = y")
+            == LetGroup(
+                vec![binding("f", "x", true), binding("g", "y", true)],
+                None,
+                Span::dummy()
+            )
+    );
+    assert!(
+        parse(&parser, "let f x = x and g y = y and h z = z in f")
+            == LetGroup(
+                vec![
+                    binding("f", "x", false),
+                    binding("g", "y", false),
+                    binding("h", "z", false)
+                ],
+                Some(Box::new(Identifier("f".to_string(), Span::dummy()))),
+                Span::dummy()
+            )
+    );
+
+    assert!(parser.parse(0, Lexer::new(0, "let f x = x and")).is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "let rec and f x = x"))
+        .is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "let f = 1 and rec g = 2"))
+        .is_err());
+    assert!(matches!(
+        parser.parse(0, Lexer::new(0, "let rec f x = x and f y = y")).err().unwrap(),
+        ParseError::User { error: (e, s) }
+        if e == "Duplicate binding f in let" && s == Span::new(0, 20, 21)
+    ));
+}
+
+#[test]
+fn test_parse_match() {
+    let parser = grammar::StatementParser::new();
+
+    assert!(
+        parse(&parser, "match x { _ => 4 }")
+            == Match {
+                matchand: Box::new(Identifier("x".to_string(), Span::dummy())),
+                arms: vec![(
+                    ast::Pattern::Wildcard(Span::dummy()),
+                    IntLiteral(4, Span::dummy()),
+                    Span::dummy()
+                )],
+                span: Span::dummy(),
+            }
+    );
+    assert!(
+        parse(
+            &parser,
+            "match x {
+        case x < 1 => [],
+        1 => [1],
+        Some with y if y == 0 => f y,
+        _ => x :: (f ((3 * x) + 1)),
+    }"
+        ) == Match {
+            matchand: Box::new(Identifier("x".to_string(), Span::dummy())),
+            arms: vec![
+                (
+                    ast::Pattern::Case(
+                        FuncApplication(
+                            Box::new(BinaryOp(ast::BinaryOp::Lt, Span::dummy())),
+                            vec![
+                                Identifier("x".to_string(), Span::dummy()),
+                                IntLiteral(1, Span::dummy())
+                            ],
+                            Span::dummy()
+                        ),
+                        Span::dummy()
+                    ),
+                    List(vec![], Span::dummy()),
+                    Span::dummy()
+                ),
+                (
+                    ast::Pattern::IntLiteral(1, Span::dummy()),
+                    List(vec![IntLiteral(1, Span::dummy())], Span::dummy()),
+                    Span::dummy()
+                ),
+                (
+                    ast::Pattern::Guarded {
+                        pattern: Box::new(ast::Pattern::TypeVariant(
+                            "Some".to_string(),
+                            Some(Box::new(ast::Pattern::Identifier(
+                                "y".to_string(),
+                                Span::dummy()
+                            ))),
+                            Span::dummy()
+                        )),
+                        guard: FuncApplication(
+                            Box::new(BinaryOp(ast::BinaryOp::Eq, Span::dummy())),
+                            vec![
+                                Identifier("y".to_string(), Span::dummy()),
+                                IntLiteral(0, Span::dummy())
+                            ],
+                            Span::dummy()
+                        ),
+                        span: Span::dummy(),
+                    },
+                    FuncApplication(
+                        Box::new(Identifier("f".to_string(), Span::dummy())),
+                        vec![Identifier("y".to_string(), Span::dummy())],
+                        Span::dummy()
+                    ),
+                    Span::dummy()
+                ),
+                (
+                    ast::Pattern::Wildcard(Span::dummy()),
+                    FuncApplication(
+                        Box::new(BinaryOp(ast::BinaryOp::Cons, Span::dummy())),
+                        vec![
+                            Identifier("x".to_string(), Span::dummy()),
+                            FuncApplication(
+                                Box::new(Identifier("f".to_string(), Span::dummy())),
+                                vec![FuncApplication(
+                                    Box::new(BinaryOp(ast::BinaryOp::Add, Span::dummy())),
+                                    vec![
+                                        FuncApplication(
+                                            Box::new(BinaryOp(ast::BinaryOp::Mult, Span::dummy())),
+                                            vec![
+                                                IntLiteral(3, Span::dummy()),
+                                                Identifier("x".to_string(), Span::dummy())
+                                            ],
+                                            Span::dummy()
+                                        ),
+                                        IntLiteral(1, Span::dummy())
+                                    ],
+                                    Span::dummy()
+                                )],
+                                Span::dummy()
+                            )
+                        ],
+                        Span::dummy()
+                    ),
+                    Span::dummy()
+                ),
+            ],
+            span: Span::dummy(),
+        }
+    );
+    // Matches are delimited, so they can be used as arguments
+    assert!(
+        parse(&parser, "f (match (g x) { (a, b) => a })")
+            == FuncApplication(
+                Box::new(Identifier("f".to_string(), Span::dummy())),
+                vec![Match {
+                    matchand: Box::new(FuncApplication(
+                        Box::new(Identifier("g".to_string(), Span::dummy())),
+                        vec![Identifier("x".to_string(), Span::dummy())],
+                        Span::dummy()
+                    )),
+                    arms: vec![(
+                        ast::Pattern::Tuple(
+                            vec![
+                                ast::Pattern::Identifier("a".to_string(), Span::dummy()),
+                                ast::Pattern::Identifier("b".to_string(), Span::dummy())
+                            ],
+                            Span::dummy()
+                        ),
+                        Identifier("a".to_string(), Span::dummy()),
+                        Span::dummy()
+                    )],
+                    span: Span::dummy(),
+                }],
+                Span::dummy()
+            )
+    );
+
+    // Arm spans cover the pattern and the expression
+    if let Expression(Match { arms, .. }) = parser
+        .parse(0, Lexer::new(0, "match y { 1 => 2, x | z => 3 }"))
+        .unwrap()
+    {
+        assert!(arms[0].2 == Span::new(0, 10, 16));
+        assert!(arms[1].2 == Span::new(0, 18, 28));
+    } else {
+        panic!("Input is not a match expression")
+    }
+
+    assert!(parser.parse(0, Lexer::new(0, "match x {}")).is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "match x { 1 => 2 3 => 4 }"))
+        .is_err());
+    assert!(parser.parse(0, Lexer::new(0, "match x { 1 }")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "match x { => 1 }")).is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "match f x { _ => 1 }"))
+        .is_err());
+    assert!(parser.parse(0, Lexer::new(0, "match { _ => 1 }")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "match x { _ => 1")).is_err());
+}
+
+#[test]
+fn test_parse_lambda() {
+    let parser = grammar::StatementParser::new();
+
+    assert!(
+        parse(&parser, "\\x -> x")
+            == Lambda {
+                arg: "x".to_string(),
+                expr: Box::new(Identifier("x".to_string(), Span::dummy())),
+                span: Span::dummy(),
+            }
+    );
+    // Multiple arguments desugar into nested lambdas
+    assert!(
+        parse(&parser, "\\x y z -> x + z")
+            == Lambda {
+                arg: "x".to_string(),
+                expr: Box::new(Lambda {
+                    arg: "y".to_string(),
+                    expr: Box::new(Lambda {
+                        arg: "z".to_string(),
+                        expr: Box::new(FuncApplication(
+                            Box::new(BinaryOp(ast::BinaryOp::Add, Span::dummy())),
+                            vec![
+                                Identifier("x".to_string(), Span::dummy()),
+                                Identifier("z".to_string(), Span::dummy())
+                            ],
+                            Span::dummy()
+                        )),
+                        span: Span::dummy(),
+                    }),
+                    span: Span::dummy(),
+                }),
+                span: Span::dummy(),
+            }
+    );
+    assert!(
+        parse(&parser, "map (\\x -> x + 1) xs")
+            == FuncApplication(
+                Box::new(Identifier("map".to_string(), Span::dummy())),
+                vec![
+                    Lambda {
+                        arg: "x".to_string(),
+                        expr: Box::new(FuncApplication(
+                            Box::new(BinaryOp(ast::BinaryOp::Add, Span::dummy())),
+                            vec![
+                                Identifier("x".to_string(), Span::dummy()),
+                                IntLiteral(1, Span::dummy())
+                            ],
+                            Span::dummy()
+                        )),
+                        span: Span::dummy(),
+                    },
+                    Identifier("xs".to_string(), Span::dummy())
+                ],
+                Span::dummy()
+            )
+    );
+    assert!(
+        parse(&parser, "\\f -> \\x -> f x")
+            == Lambda {
+                arg: "f".to_string(),
+                expr: Box::new(Lambda {
+                    arg: "x".to_string(),
+                    expr: Box::new(FuncApplication(
+                        Box::new(Identifier("f".to_string(), Span::dummy())),
+                        vec![Identifier("x".to_string(), Span::dummy())],
+                        Span::dummy()
+                    )),
+                    span: Span::dummy(),
+                }),
+                span: Span::dummy(),
+            }
+    );
+    assert!(
+        parse(&parser, "\\x -> let y = x in y")
+            == Lambda {
+                arg: "x".to_string(),
+                expr: Box::new(Let(
+                    VarWithValue {
+                        name: ("y".to_string(), Span::dummy()),
+                        args: vec![],
+                        output_type: None,
+                        rec: false,
+                        expr: Box::new(Identifier("x".to_string(), Span::dummy())),
+                    },
+                    Some(Box::new(Identifier("y".to_string(), Span::dummy()))),
+                    Span::dummy()
+                )),
+                span: Span::dummy(),
+            }
+    );
+    assert!(
+        parse(&parser, "let inc = \\x -> x + 1 in inc 2")
+            == Let(
+                VarWithValue {
+                    name: ("inc".to_string(), Span::dummy()),
+                    args: vec![],
+                    output_type: None,
+                    rec: false,
+                    expr: Box::new(Lambda {
+                        arg: "x".to_string(),
+                        expr: Box::new(FuncApplication(
+                            Box::new(BinaryOp(ast::BinaryOp::Add, Span::dummy())),
+                            vec![
+                                Identifier("x".to_string(), Span::dummy()),
+                                IntLiteral(1, Span::dummy())
+                            ],
+                            Span::dummy()
+                        )),
+                        span: Span::dummy(),
+                    }),
+                },
+                Some(Box::new(FuncApplication(
+                    Box::new(Identifier("inc".to_string(), Span::dummy())),
+                    vec![IntLiteral(2, Span::dummy())],
+                    Span::dummy()
+                ))),
+                Span::dummy()
+            )
+    );
+
+    // The outermost lambda spans from the backslash, inner ones from their argument
+    if let Expression(Lambda { expr, span, .. }) =
+        parser.parse(0, Lexer::new(0, "\\a b -> a")).unwrap()
+    {
+        assert!(span == Span::new(0, 0, 9));
+        assert!(matches!(*expr, Lambda { span, .. } if span == Span::new(0, 3, 9)));
+    } else {
+        panic!("Input is not a lambda expression")
+    }
+
+    assert!(parser.parse(0, Lexer::new(0, "\\ -> x")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "\\x ->")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "\\x x")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "\\(x) -> x")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "\\4 -> x")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "x -> x")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "map \\x -> x xs")).is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "let f = \\x -> let y = x in y"))
+        .is_err());
+}
+
+#[test]
+fn test_parse_method_access() {
+    let parser = grammar::StatementParser::new();
+
+    assert!(
+        parse(&parser, "counter#incr")
+            == MethodAccess(
+                Box::new(Identifier("counter".to_string(), Span::dummy())),
+                "incr".to_string(),
+                Span::dummy()
+            )
+    );
+    // Method access chains with projection
+    assert!(
+        parse(&parser, "a.b#m")
+            == MethodAccess(
+                Box::new(Projection(
+                    Box::new(Identifier("a".to_string(), Span::dummy())),
+                    "b".to_string(),
+                    Span::dummy()
+                )),
+                "m".to_string(),
+                Span::dummy()
+            )
+    );
+    assert!(
+        parse(&parser, "a#m.b")
+            == Projection(
+                Box::new(MethodAccess(
+                    Box::new(Identifier("a".to_string(), Span::dummy())),
+                    "m".to_string(),
+                    Span::dummy()
+                )),
+                "b".to_string(),
+                Span::dummy()
+            )
+    );
+    // and binds tighter than function application
+    assert!(
+        parse(&parser, "v#dot w#norm")
+            == FuncApplication(
+                Box::new(MethodAccess(
+                    Box::new(Identifier("v".to_string(), Span::dummy())),
+                    "dot".to_string(),
+                    Span::dummy()
+                )),
+                vec![MethodAccess(
+                    Box::new(Identifier("w".to_string(), Span::dummy())),
+                    "norm".to_string(),
+                    Span::dummy()
+                )],
+                Span::dummy()
+            )
+    );
+    assert!(
+        parse(&parser, "xs#len + 1")
+            == FuncApplication(
+                Box::new(BinaryOp(ast::BinaryOp::Add, Span::dummy())),
+                vec![
+                    MethodAccess(
+                        Box::new(Identifier("xs".to_string(), Span::dummy())),
+                        "len".to_string(),
+                        Span::dummy()
+                    ),
+                    IntLiteral(1, Span::dummy())
+                ],
+                Span::dummy()
+            )
+    );
+    assert!(
+        parser.parse(0, Lexer::new(0, "a.b#m")).unwrap()
+            == Expression(MethodAccess(
+                Box::new(Projection(
+                    Box::new(Identifier("a".to_string(), Span::new(0, 0, 1))),
+                    "b".to_string(),
+                    Span::new(0, 0, 3)
+                )),
+                "m".to_string(),
+                Span::new(0, 0, 5)
+            ))
+    );
+
+    assert!(parser.parse(0, Lexer::new(0, "#m")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "a#")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "a##m")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "a#4")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "(f x)#m")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "a#m with 4")).is_err());
+}
+
+#[test]
+fn test_parse_set() {
+    let parser = grammar::StatementParser::new();
+
+    assert!(
+        parse(&parser, "set self.count = self.count + 1")
+            == Set(
+                AttrSet {
+                    entity: ("self".to_string(), Span::dummy()),
+                    attr: ("count".to_string(), Span::dummy()),
+                    new_expr: Box::new(FuncApplication(
+                        Box::new(BinaryOp(ast::BinaryOp::Add, Span::dummy())),
+                        vec![
+                            Projection(
+                                Box::new(Identifier("self".to_string(), Span::dummy())),
+                                "count".to_string(),
+                                Span::dummy()
+                            ),
+                            IntLiteral(1, Span::dummy())
+                        ],
+                        Span::dummy()
+                    )),
+                },
+                Span::dummy()
+            )
+    );
+    assert!(
+        parse(&parser, "\\x -> set p.x = x")
+            == Lambda {
+                arg: "x".to_string(),
+                expr: Box::new(Set(
+                    AttrSet {
+                        entity: ("p".to_string(), Span::dummy()),
+                        attr: ("x".to_string(), Span::dummy()),
+                        new_expr: Box::new(Identifier("x".to_string(), Span::dummy())),
+                    },
+                    Span::dummy()
+                )),
+                span: Span::dummy(),
+            }
+    );
+    assert!(
+        parse(&parser, "let v = 4 in set p.x = v")
+            == Let(
+                VarWithValue {
+                    name: ("v".to_string(), Span::dummy()),
+                    args: vec![],
+                    output_type: None,
+                    rec: false,
+                    expr: Box::new(IntLiteral(4, Span::dummy())),
+                },
+                Some(Box::new(Set(
+                    AttrSet {
+                        entity: ("p".to_string(), Span::dummy()),
+                        attr: ("x".to_string(), Span::dummy()),
+                        new_expr: Box::new(Identifier("v".to_string(), Span::dummy())),
+                    },
+                    Span::dummy()
+                ))),
+                Span::dummy(),
+            )
+    );
+    assert!(
+        parser.parse(0, Lexer::new(0, "set a.b = 4")).unwrap()
+            == Expression(Set(
+                AttrSet {
+                    entity: ("a".to_string(), Span::new(0, 4, 5)),
+                    attr: ("b".to_string(), Span::new(0, 6, 7)),
+                    new_expr: Box::new(IntLiteral(4, Span::new(0, 10, 11))),
+                },
+                Span::new(0, 0, 11)
+            ))
+    );
+
+    assert!(parser.parse(0, Lexer::new(0, "set a = 4")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "set a.b.c = 4")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "set a#b = 4")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "set a.b")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "f set a.b = 4")).is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "let x = set a.b = 4"))
+        .is_err());
+}
+
+#[test]
+fn test_parse_if_and_connectives() {
+    let parser = grammar::StatementParser::new();
+    let id = |name: &str| Identifier(name.to_string(), Span::dummy());
+    let app = |op: ast::BinaryOp, lhs, rhs| {
+        FuncApplication(
+            Box::new(BinaryOp(op, Span::dummy())),
+            vec![lhs, rhs],
+            Span::dummy(),
+        )
+    };
+
+    // && binds tighter than ||, and both are looser than comparisons
+    assert!(
+        parse(&parser, "a || b && x < 1")
+            == app(
+                ast::BinaryOp::Or,
+                id("a"),
+                app(
+                    ast::BinaryOp::And,
+                    id("b"),
+                    app(ast::BinaryOp::Lt, id("x"), IntLiteral(1, Span::dummy()))
+                )
+            )
+    );
+    assert!(
+        parse(&parser, "a && b && c")
+            == app(
+                ast::BinaryOp::And,
+                id("a"),
+                app(ast::BinaryOp::And, id("b"), id("c"))
+            )
+    );
+    assert!(
+        parse(&parser, "(not a) && b")
+            == app(
+                ast::BinaryOp::And,
+                FuncApplication(Box::new(Not(Span::dummy())), vec![id("a")], Span::dummy()),
+                id("b")
+            )
+    );
+
+    assert!(
+        parse(&parser, "if a then 1 else if b then 2 else 3")
+            == If {
+                cond: Box::new(id("a")),
+                then_expr: Box::new(IntLiteral(1, Span::dummy())),
+                else_expr: Box::new(If {
+                    cond: Box::new(id("b")),
+                    then_expr: Box::new(IntLiteral(2, Span::dummy())),
+                    else_expr: Box::new(IntLiteral(3, Span::dummy())),
+                    span: Span::dummy(),
+                }),
+                span: Span::dummy(),
+            }
+    );
+    // The else branch extends as far to the right as possible
+    assert!(
+        parse(&parser, "if a then b else c + 1")
+            == If {
+                cond: Box::new(id("a")),
+                then_expr: Box::new(id("b")),
+                else_expr: Box::new(app(
+                    ast::BinaryOp::Add,
+                    id("c"),
+                    IntLiteral(1, Span::dummy())
+                )),
+                span: Span::dummy(),
+            }
+    );
+    assert!(
+        parse(&parser, "let x = if a then 1 else 2 in x")
+            == Let(
+                VarWithValue {
+                    name: ("x".to_string(), Span::dummy()),
+                    args: vec![],
+                    output_type: None,
+                    rec: false,
+                    expr: Box::new(If {
+                        cond: Box::new(id("a")),
+                        then_expr: Box::new(IntLiteral(1, Span::dummy())),
+                        else_expr: Box::new(IntLiteral(2, Span::dummy())),
+                        span: Span::dummy(),
+                    }),
+                },
+                Some(Box::new(id("x"))),
+                Span::dummy()
+            )
+    );
+    assert!(
+        parser
+            .parse(0, Lexer::new(0, "if a then 1 else 2"))
+            .unwrap()
+            == Expression(If {
+                cond: Box::new(Identifier("a".to_string(), Span::new(0, 3, 4))),
+                then_expr: Box::new(IntLiteral(1, Span::new(0, 10, 11))),
+                else_expr: Box::new(IntLiteral(2, Span::new(0, 17, 18))),
+                span: Span::new(0, 0, 18),
+            })
+    );
+
+    assert!(parser.parse(0, Lexer::new(0, "if a then 1")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "if a else 1")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "a &&")).is_err());
+}
+
+#[test]
+fn test_parse_number_overflow() {
+    let parser = grammar::StatementParser::new();
+    assert!(
+        parse(&parser, "0x10 + 1_000")
+            == FuncApplication(
+                Box::new(BinaryOp(ast::BinaryOp::Add, Span::dummy())),
+                vec![
+                    IntLiteral(16, Span::dummy()),
+                    IntLiteral(1000, Span::dummy())
+                ],
+                Span::dummy()
+            )
+    );
+    // Literals that do not fit are reported instead of panicking
+    assert!(
+        parse_program(0, "let x = 99999999999999999999;").1
+            == vec![(
+                "Integer literal 99999999999999999999 does not fit in 64 bits".to_string(),
+                Span::new(0, 8, 28)
+            )]
+    );
+}
+
+#[test]
+fn test_parse_block() {
+    let parser = grammar::StatementParser::new();
+    let id = |name: &str| Identifier(name.to_string(), Span::dummy());
+    let print = |e| FuncApplication(Box::new(Print(Span::dummy())), vec![e], Span::dummy());
+
+    assert!(
+        parse(&parser, "{ print x; let y = 1; y }")
+            == Block(
+                vec![
+                    print(id("x")),
+                    Let(
+                        VarWithValue {
+                            name: ("y".to_string(), Span::dummy()),
+                            args: vec![],
+                            output_type: None,
+                            rec: false,
+                            expr: Box::new(IntLiteral(1, Span::dummy())),
+                        },
+                        None,
+                        Span::dummy()
+                    )
+                ],
+                Some(Box::new(id("y"))),
+                Span::dummy()
+            )
+    );
+    // A block that ends with `;` has no result
+    assert!(parse(&parser, "{ print x; }") == Block(vec![print(id("x"))], None, Span::dummy()));
+    assert!(parse(&parser, "{ x }") == Block(vec![], Some(Box::new(id("x"))), Span::dummy()));
+    assert!(
+        parse(&parser, "f { x; y }")
+            == FuncApplication(
+                Box::new(id("f")),
+                vec![Block(vec![id("x")], Some(Box::new(id("y"))), Span::dummy())],
+                Span::dummy()
+            )
+    );
+    assert!(
+        parser.parse(0, Lexer::new(0, "{ x; 1 }")).unwrap()
+            == Expression(Block(
+                vec![Identifier("x".to_string(), Span::new(0, 2, 3))],
+                Some(Box::new(IntLiteral(1, Span::new(0, 5, 6)))),
+                Span::new(0, 0, 8)
+            ))
+    );
+    // Records are still records
+    assert!(matches!(parse(&parser, "{ x: 1 }"), Record(..)));
+
+    assert!(parser.parse(0, Lexer::new(0, "{}")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "{ ; }")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "{ x y; }")).is_ok());
+    assert!(parser.parse(0, Lexer::new(0, "{ x; y: 1 }")).is_err());
 }
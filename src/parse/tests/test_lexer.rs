@@ -0,0 +1,356 @@
+use super::lexer::{Lexer, Token, Token::*};
+use super::span::Span;
+use crate::test_util::to_of64;
+
+fn lex(inp: &'static str) -> Vec<Token> {
+    Lexer::new(0, inp)
+        .map(|res| res.unwrap().1)
+        .collect::<Vec<_>>()
+}
+
+/// The first error token in the input, with its message and span
+fn lex_err(inp: &'static str) -> (String, Span) {
+    Lexer::new(0, inp)
+        .find_map(|res| match res.unwrap() {
+            (start, Error(msg), end) => Some((msg, Span::new(0, start, end))),
+            _ => None,
+        })
+        .unwrap()
+}
+
+#[test]
+fn test_lex_tokens() {
+    assert!(lex("") == vec![]);
+    assert!(
+        lex("let f x = x + 1")
+            == vec![
+                Let,
+                Identifier("f".to_string()),
+                Identifier("x".to_string()),
+                Assign,
+                Identifier("x".to_string()),
+                Plus,
+                IntLiteral(1),
+            ]
+    );
+    assert!(
+        lex("a::b -> c => d != e +. f {| g |} h#i")
+            == vec![
+                Identifier("a".to_string()),
+                Cons,
+                Identifier("b".to_string()),
+                Arrow,
+                Identifier("c".to_string()),
+                FatArrow,
+                Identifier("d".to_string()),
+                Neq,
+                Identifier("e".to_string()),
+                FlPlus,
+                Identifier("f".to_string()),
+                LExactBrace,
+                Identifier("g".to_string()),
+                RExactBrace,
+                Identifier("h".to_string()),
+                Hash,
+                Identifier("i".to_string()),
+            ]
+    );
+    // `<-` is only an arrow in the clauses of a list comprehension, and
+    // elsewhere is a `<` followed by a negative number
+    assert!(lex("3<-1") == vec![IntLiteral(3), Lt, IntLiteral(-1)]);
+    assert!(
+        lex("[x | x <- [y<-1]]")
+            == vec![
+                LBracket,
+                Identifier("x".to_string()),
+                Bar,
+                Identifier("x".to_string()),
+                LeftArrow,
+                LBracket,
+                Identifier("y".to_string()),
+                Lt,
+                IntLiteral(-1),
+                RBracket,
+                RBracket,
+            ]
+    );
+    // Brackets within the clauses are not comprehensions
+    let toks = lex("[x | (y<-1), z<-1]");
+    assert!(toks[4..7] == [Identifier("y".to_string()), Lt, IntLiteral(-1)]);
+    assert!(toks[9..12] == [Identifier("z".to_string()), LeftArrow, IntLiteral(1)]);
+    assert!(lex("[ ] [\n] []") == vec![EmptyList, EmptyList, EmptyList]);
+    assert!(lex("[ 4]") == vec![LBracket, IntLiteral(4), RBracket]);
+    assert!(
+        lex("'a Num'b")
+            == vec![
+                Polytype("a".to_string()),
+                BoundedPolytype("Num".to_string(), "b".to_string())
+            ]
+    );
+
+    // Spans are byte positions
+    assert!(
+        Lexer::new(0, "x  ⓓ")
+            .map(Result::unwrap)
+            .collect::<Vec<_>>()
+            == vec![
+                (0, Identifier("x".to_string()), 1),
+                (3, Error("Unexpected character `ⓓ`".to_string()), 6)
+            ]
+    );
+}
+
+#[test]
+fn test_lex_keywords() {
+    assert!(lex("with in let rec int none") == vec![With, In, Let, Rec, IntType, NoneType]);
+    assert!(lex("import open type") == vec![Import, Open, Type]);
+    assert!(lex("let rec and") == vec![Let, Rec, And]);
+    assert!(lex("_") == vec![Underscore]);
+    // Keywords are only matched as whole words
+    assert!(
+        lex("within _in lets val")
+            == vec![
+                Identifier("within".to_string()),
+                Identifier("_in".to_string()),
+                Identifier("lets".to_string()),
+                Identifier("val".to_string()),
+            ]
+    );
+}
+
+#[test]
+fn test_lex_numbers() {
+    assert!(lex("0 42 -7") == vec![IntLiteral(0), IntLiteral(42), IntLiteral(-7)]);
+    assert!(
+        lex("1.5 -0.25 2.0e-3 3.1E2")
+            == vec![
+                FloatLiteral(to_of64(1.5)),
+                FloatLiteral(to_of64(-0.25)),
+                FloatLiteral(to_of64(2.0e-3)),
+                FloatLiteral(to_of64(3.1e2)),
+            ]
+    );
+    // A `-` only belongs to a number if a digit follows it directly
+    assert!(lex("- 7") == vec![Minus, IntLiteral(7)]);
+    assert!(lex("4.") == vec![IntLiteral(4), Dot]);
+    assert!(
+        lex("x.y")
+            == vec![
+                Identifier("x".to_string()),
+                Dot,
+                Identifier("y".to_string())
+            ]
+    );
+
+    assert!(
+        lex_err("0527")
+            == (
+                "Number literal 0527 cannot have leading zeros".to_string(),
+                Span::new(0, 0, 4)
+            )
+    );
+    assert!(lex_err("x = 99999999999999999999").1 == Span::new(0, 4, 24));
+}
+
+#[test]
+fn test_lex_number_bases() {
+    assert!(
+        lex("0xff 0o17 0b101 -0x10 0xDead_Beef")
+            == vec![
+                IntLiteral(255),
+                IntLiteral(15),
+                IntLiteral(5),
+                IntLiteral(-16),
+                IntLiteral(0xdead_beef)
+            ]
+    );
+    assert!(
+        lex("1_000_000 -2_5 1_000.000_1 1e3 2E-2 5e+1 1_0e1_0")
+            == vec![
+                IntLiteral(1_000_000),
+                IntLiteral(-25),
+                FloatLiteral(to_of64(1_000.000_1)),
+                FloatLiteral(to_of64(1e3)),
+                FloatLiteral(to_of64(2e-2)),
+                FloatLiteral(to_of64(5e1)),
+                FloatLiteral(to_of64(1e11)),
+            ]
+    );
+    assert!(lex("0x7fff_ffff_ffff_ffff") == vec![IntLiteral(i64::MAX)]);
+    assert!(lex("-0x8000_0000_0000_0000") == vec![IntLiteral(i64::MIN)]);
+    assert!(lex("-9223372036854775808") == vec![IntLiteral(i64::MIN)]);
+    // An `e` directly after the digits always starts an exponent
+    assert!(lex("2 e") == vec![IntLiteral(2), Identifier("e".to_string())]);
+    assert!(
+        lex_err("x = 1.5e")
+            == (
+                "Malformed exponent in number literal 1.5e".to_string(),
+                Span::new(0, 4, 8)
+            )
+    );
+    assert!(lex_err("1e5x").0 == "Malformed exponent in number literal 1e5x");
+    assert!(lex_err("2e").0 == "Malformed exponent in number literal 2e");
+    assert!(lex_err("2e-").0 == "Malformed exponent in number literal 2e-");
+    assert!(lex_err("2E+_1").0 == "Malformed exponent in number literal 2E+_1");
+
+    assert!(
+        lex_err("x = 0x1_0000_0000_0000_0000")
+            == (
+                "Integer literal 0x1_0000_0000_0000_0000 does not fit in 64 bits".to_string(),
+                Span::new(0, 4, 27)
+            )
+    );
+    assert!(
+        lex_err("0b102")
+            == (
+                "Invalid digit 2 in binary literal 0b102".to_string(),
+                Span::new(0, 0, 5)
+            )
+    );
+    assert!(
+        lex_err("0o")
+            == (
+                "Number literal 0o has no digits".to_string(),
+                Span::new(0, 0, 2)
+            )
+    );
+    assert!(
+        lex_err("1__000")
+            == (
+                "Digit separator `_` must be between digits in number literal 1__000".to_string(),
+                Span::new(0, 0, 6)
+            )
+    );
+    assert!(lex_err("1_").0.starts_with("Digit separator"));
+    assert!(lex_err("0x_1").0.starts_with("Digit separator"));
+    assert!(lex_err("1_.5").0.starts_with("Digit separator"));
+    assert!(lex_err("0_1").0.ends_with("cannot have leading zeros"));
+    assert!(
+        lex_err("1e400")
+            == (
+                "Float literal 1e400 is too large to be represented".to_string(),
+                Span::new(0, 0, 5)
+            )
+    );
+}
+
+#[test]
+fn test_lex_strings() {
+    assert!(lex("\"\"") == vec![StringLiteral("".to_string())]);
+    assert!(lex("\"µß 😎\"") == vec![StringLiteral("µß 😎".to_string())]);
+    assert!(
+        lex(r#""say \"hi\"\n\tand \\ leave""#)
+            == vec![StringLiteral("say \"hi\"\n\tand \\ leave".to_string())]
+    );
+    assert!(lex(r#""\u{48}\u{1F60E}\0""#) == vec![StringLiteral("H😎\0".to_string())]);
+    assert!(lex("\"a % b\"") == vec![StringLiteral("a % b".to_string())]);
+
+    assert!(
+        lex_err("\"abc")
+            == (
+                "Unterminated string literal".to_string(),
+                Span::new(0, 0, 4)
+            )
+    );
+    assert!(
+        lex_err(r#""a\qb""#)
+            == (
+                "Invalid escape sequence \\q".to_string(),
+                Span::new(0, 2, 4)
+            )
+    );
+    assert!(lex_err(r#""\u{}""#).1 == Span::new(0, 1, 5));
+    assert!(lex_err(r#""\u{1234567}""#).1 == Span::new(0, 1, 12));
+    assert!(lex_err(r#""\u{D800}""#)
+        .0
+        .contains("Invalid unicode character D800"));
+    assert!(lex_err(r#""\u48""#).0.contains("Expected `{`"));
+}
+
+#[test]
+fn test_lex_comments() {
+    assert!(lex("% a comment\n4 % another") == vec![IntLiteral(4)]);
+    assert!(lex("[% block %] 4 [%%]") == vec![IntLiteral(4)]);
+    assert!(lex("[% outer [% inner %] still outer %] 5") == vec![IntLiteral(5)]);
+    assert!(lex("[% 50% of ] %] 6") == vec![IntLiteral(6)]);
+
+    assert!(
+        lex_err("4 [% [% %]") == ("Unterminated block comment".to_string(), Span::new(0, 2, 4))
+    );
+    assert!(lex_err("' a").1 == Span::new(0, 0, 1));
+    assert!(lex_err("a § b").0 == "Unexpected character `§`");
+}
+
+#[test]
+fn test_lex_operators() {
+    let op = |s: &str| Operator(s.to_string());
+    assert!(lex("<+> >>= $ ?") == vec![op("<+>"), op(">>="), op("$"), op("?")]);
+    // Builtin symbols are only matched when they are the whole operator
+    assert!(lex("+ +. <= -> ::") == vec![Plus, FlPlus, Leq, Arrow, Cons]);
+    assert!(lex("++ <=> ->>") == vec![op("++"), op("<=>"), op("->>")]);
+    assert!(
+        lex("a<+>b")
+            == vec![
+                Identifier("a".to_string()),
+                op("<+>"),
+                Identifier("b".to_string())
+            ]
+    );
+    assert!(lex("{| a |}") == vec![LExactBrace, Identifier("a".to_string()), RExactBrace]);
+    assert!(lex("infix infixl infixr") == vec![Infix, Infixl, Infixr]);
+
+    // A builtin symbol directly followed by a negative number is not an
+    // operator, unless the program declares one
+    let x = || Identifier("x".to_string());
+    assert!(lex("x=-1") == vec![x(), Assign, IntLiteral(-1)]);
+    assert!(lex("x==-1") == vec![x(), Eq, IntLiteral(-1)]);
+    assert!(lex("x<=-1") == vec![x(), Leq, IntLiteral(-1)]);
+    assert!(lex("x::-1") == vec![x(), Cons, IntLiteral(-1)]);
+    assert!(lex("x==-y") == vec![x(), op("==-"), Identifier("y".to_string())]);
+    assert!(lex("infixl 6 ==-; x==-1")[4..] == [x(), op("==-"), IntLiteral(1)]);
+    assert!(lex("let (=-) a b = a; x=-1")[9..] == [x(), op("=-"), IntLiteral(1)]);
+}
+
+#[test]
+fn test_lex_boolean_operators() {
+    assert!(lex("&& || not if then else") == vec![AndAlso, OrElse, Not, If, Then, Else]);
+    assert!(lex("&&& |||") == vec![Operator("&&&".to_string()), Operator("|||".to_string())]);
+    // The bar of an exact record is still its own token
+    assert!(lex("{| a |}") == vec![LExactBrace, Identifier("a".to_string()), RExactBrace]);
+}
+
+#[test]
+fn test_lex_pipeline_operators() {
+    assert!(lex("|> >> <<") == vec![Pipe, ComposeForward, ComposeBackward]);
+    assert!(
+        lex(">>= <<< |>>")
+            == vec![
+                Operator(">>=".to_string()),
+                Operator("<<<".to_string()),
+                Operator("|>>".to_string())
+            ]
+    );
+}
+
+#[test]
+fn test_lex_resumes_after_error() {
+    let mut lexer = Lexer::new(0, "a § b");
+    assert!(lexer.next() == Some(Ok((0, Identifier("a".to_string()), 1))));
+    assert!(lexer.next() == Some(Ok((2, Error("Unexpected character `§`".to_string()), 4))));
+    assert!(lexer.next() == Some(Ok((5, Identifier("b".to_string()), 6))));
+    assert!(lexer.next().is_none());
+
+    // An invalid escape is reported once the whole string is consumed
+    assert!(
+        lex(r#""a\q" b "\u{41" c"#)
+            == vec![
+                Error("Invalid escape sequence \\q".to_string()),
+                Identifier("b".to_string()),
+                Error(
+                    "Unicode escape must be of the form \\u{XXXX}, with 1 to 6 hex digits"
+                        .to_string()
+                ),
+                Identifier("c".to_string()),
+            ]
+    );
+}
@@ -1,247 +1,457 @@
-// use super::*;
-
-// // Patterns
-
-// #[test]
-// fn test_parse_atomic_pattern() {
-//     let parser = grammar::PatternParser::new();
-//     // Ok atomic
-//     assert!(parser.parse("_").unwrap() == ast::Pattern::Wildcard);
-//     assert!(parser.parse("__").unwrap() == ast::Pattern::Identifier(String::from("__")));
-//     assert!(parser.parse("-6789").unwrap() == ast::Pattern::IntegerLiteral(-6789));
-//     assert!(parser.parse("9.8e3").unwrap() == ast::Pattern::FloatLiteral(OrderedFloat(9800.0)));
-//     assert!(
-//         parser.parse("\"helloⓓⓕ\"").unwrap()
-//             == ast::Pattern::StringLiteral(String::from("helloⓓⓕ"))
-//     );
-//     assert!(parser.parse("x").unwrap() == ast::Pattern::Identifier(String::from("x")));
-//     assert!(parser.parse("__o98").unwrap() == ast::Pattern::Identifier(String::from("__o98")));
-//     assert!(
-//         parser.parse("Some with x").unwrap()
-//             == ast::Pattern::TypeVariant(
-//                 String::from("Some"),
-//                 Box::new(ast::Pattern::Identifier(String::from("x")))
-//             )
-//     );
-//     assert!(
-//         parser.parse("Option with (Tree, 4)").unwrap()
-//             == ast::Pattern::TypeVariant(
-//                 String::from("Option"),
-//                 Box::new(ast::Pattern::Tuple(vec![
-//                     ast::Pattern::Identifier(String::from("Tree")),
-//                     ast::Pattern::IntegerLiteral(4)
-//                 ]))
-//             )
-//     );
-//     assert!(
-//         parser.parse("Some with 4").unwrap()
-//             == ast::Pattern::TypeVariant(
-//                 String::from("Some"),
-//                 Box::new(ast::Pattern::IntegerLiteral(4))
-//             )
-//     );
-//     assert!(parser.parse("[ ]").unwrap() == ast::Pattern::EmptyList);
-//     assert!(
-//         parser.parse("bool with (List with [_, x :: xs])").unwrap()
-//             == ast::Pattern::TypeVariant(
-//                 String::from("bool"),
-//                 Box::new(ast::Pattern::TypeVariant(
-//                     String::from("List"),
-//                     Box::new(ast::Pattern::List(vec![
-//                         ast::Pattern::Wildcard,
-//                         ast::Pattern::ListConstruction(String::from("x"), String::from("xs"))
-//                     ]))
-//                 ))
-//             )
-//     );
-//     assert!(
-//         parser.parse("Integer with (4)").unwrap()
-//             == ast::Pattern::TypeVariant(
-//                 String::from("Integer"),
-//                 Box::new(ast::Pattern::IntegerLiteral(4))
-//             )
-//     );
-//     // Err atomic
-//     assert!(parser.parse("=>").is_err());
-//     assert!(parser.parse("ⓓⓕ").is_err());
-//     assert!(parser.parse("- 5").is_err());
-//     assert!(parser.parse("__ => _").is_err());
-//     assert!(parser.parse("98x").is_err());
-//     assert!(parser.parse("Some with int").is_err());
-//     assert!(parser.parse("Option x").is_err());
-//     assert!(parser.parse("Thingy with ([x, y])").is_err());
-// }
-
-// #[test]
-// fn test_parse_compound_pattern() {
-//     let parser = grammar::PatternParser::new();
-//     // Ok compound
-//     assert!(
-//         parser.parse("[4, _, _, []]").unwrap()
-//             == ast::Pattern::List(vec![
-//                 ast::Pattern::IntegerLiteral(4),
-//                 ast::Pattern::Wildcard,
-//                 ast::Pattern::Wildcard,
-//                 ast::Pattern::EmptyList
-//             ])
-//     );
-//     assert!(
-//         parser
-//             .parse("Integer with (Option with y, x :: xs)")
-//             .unwrap()
-//             == ast::Pattern::TypeVariant(
-//                 String::from("Integer"),
-//                 Box::new(ast::Pattern::Tuple(vec![
-//                     ast::Pattern::TypeVariant(
-//                         String::from("Option"),
-//                         Box::new(ast::Pattern::Identifier(String::from("y")))
-//                     ),
-//                     ast::Pattern::ListConstruction(String::from("x"), String::from("xs"))
-//                 ]))
-//             )
-//     );
-//     assert!(
-//         parser.parse("Float with (5.7,)").unwrap()
-//             == ast::Pattern::TypeVariant(
-//                 String::from("Float"),
-//                 Box::new(ast::Pattern::Tuple(vec![ast::Pattern::FloatLiteral(
-//                     OrderedFloat(5.7)
-//                 )]))
-//             )
-//     );
-//     assert!(
-//         parser.parse("(_, _, v)").unwrap()
-//             == ast::Pattern::Tuple(vec![
-//                 ast::Pattern::Wildcard,
-//                 ast::Pattern::Wildcard,
-//                 ast::Pattern::Identifier(String::from("v"))
-//             ])
-//     );
-//     assert!(
-//         parser.parse("[4, -5.6, _]").unwrap()
-//             == ast::Pattern::List(vec![
-//                 ast::Pattern::IntegerLiteral(4),
-//                 ast::Pattern::FloatLiteral(OrderedFloat(-5.6)),
-//                 ast::Pattern::Wildcard
-//             ])
-//     );
-//     assert!(
-//         parser.parse("(7, )").unwrap()
-//             == ast::Pattern::Tuple(vec![ast::Pattern::IntegerLiteral(7)])
-//     );
-//     // Err compound
-//     assert!(parser.parse("(_, 4").is_err());
-//     assert!(parser.parse("( )").is_err());
-//     assert!(parser.parse("[x, y").is_err());
-//     assert!(parser.parse("[4,]").is_err());
-//     assert!(parser.parse("(x)with y").is_err());
-//     assert!(parser.parse("with 4").is_err());
-//     assert!(parser.parse("[[Some with x]]").is_err());
-//     assert!(parser.parse("(x, _, (4, 5))").is_err());
-//     assert!(parser.parse("([4, -5.6, _], (7, ), x, )").is_err());
-// }
-
-// #[test]
-// fn test_parse_complex_pattern() {
-//     let parser = grammar::PatternParser::new();
-//     // Ok Pattern union
-//     assert!(
-//         parser.parse("x | y").unwrap()
-//             == ast::Pattern::Union(vec![
-//                 ast::Pattern::Identifier(String::from("x")),
-//                 ast::Pattern::Identifier(String::from("y"))
-//             ])
-//     );
-//     assert!(
-//         parser.parse("(4) | 5.0 | \"hello\"").unwrap()
-//             == ast::Pattern::Union(vec![
-//                 ast::Pattern::IntegerLiteral(4),
-//                 ast::Pattern::FloatLiteral(OrderedFloat(5.0)),
-//                 ast::Pattern::StringLiteral(String::from("hello"))
-//             ])
-//     );
-//     assert!(
-//         parser.parse("x|(y)").unwrap()
-//             == ast::Pattern::Union(vec![
-//                 ast::Pattern::Identifier(String::from("x")),
-//                 ast::Pattern::Identifier(String::from("y"))
-//             ])
-//     );
-//     // Err pattern union
-//     assert!(parser.parse("[a, b] | 4").is_err());
-//     assert!(parser.parse("b|(x,)").is_err());
-//     assert!(parser.parse("4 |").is_err());
-//     assert!(parser.parse("|").is_err());
-//     assert!(parser.parse("4 | 5 | ").is_err());
-//     assert!(parser.parse("| 6.0 | 7").is_err());
-//     assert!(parser.parse("([x, y]) | (4)").is_err());
-//     // Ok Pattern complement
-//     assert!(
-//         parser.parse("~4").unwrap()
-//             == ast::Pattern::Complement(Box::new(ast::Pattern::IntegerLiteral(4)))
-//     );
-//     assert!(
-//         parser.parse("~[_, 4]").unwrap()
-//             == ast::Pattern::Complement(Box::new(ast::Pattern::List(vec![
-//                 ast::Pattern::Wildcard,
-//                 ast::Pattern::IntegerLiteral(4)
-//             ])))
-//     );
-//     assert!(
-//         parser.parse("~Option with 4").unwrap()
-//             == ast::Pattern::Complement(Box::new(ast::Pattern::TypeVariant(
-//                 String::from("Option"),
-//                 Box::new(ast::Pattern::IntegerLiteral(4))
-//             )))
-//     );
-//     assert!(
-//         parser.parse("~(Option with (2, 3, x, ))").unwrap()
-//             == ast::Pattern::Complement(Box::new(ast::Pattern::TypeVariant(
-//                 String::from("Option"),
-//                 Box::new(ast::Pattern::Tuple(vec![
-//                     ast::Pattern::IntegerLiteral(2),
-//                     ast::Pattern::IntegerLiteral(3),
-//                     ast::Pattern::Identifier(String::from("x"))
-//                 ]))
-//             )))
-//     );
-//     // Err Pattern complement
-//     assert!(parser.parse("!").is_err());
-//     assert!(parser.parse("~").is_err());
-//     assert!(parser.parse("~~4").is_err());
-//     assert!(parser.parse("~(~4)").is_err());
-//     assert!(parser.parse("4~").is_err());
-//     assert!(parser.parse("5 | ~6").is_err());
-//     assert!(parser.parse("~ 5 | 4").is_err());
-//     // Ok guarded pattern
-//     assert!(
-//         parser.parse("x | y if true").unwrap()
-//             == ast::Pattern::Guarded(
-//                 Box::new(ast::Pattern::Union(vec![
-//                     ast::Pattern::Identifier(String::from("x")),
-//                     ast::Pattern::Identifier(String::from("y"))
-//                 ])),
-//                 ast::Expression::Identifier(String::from("true"))
-//             )
-//     );
-//     assert!(
-//         parser.parse("x :: xs if f x").unwrap()
-//             == ast::Pattern::Guarded(
-//                 Box::new(ast::Pattern::ListConstruction(
-//                     String::from("x"),
-//                     String::from("xs")
-//                 )),
-//                 ast::Expression::FuncApplication(
-//                     Box::new(ast::Expression::Identifier(String::from("f"))),
-//                     Box::new(ast::Expression::Identifier(String::from("x")))
-//                 )
-//             )
-//     );
-//     // Err guarded pattern
-//     assert!(parser.parse("if").is_err());
-//     assert!(parser.parse("x | 4if true").is_err());
-//     assert!(parser.parse("x | yif true").is_err());
-//     assert!(parser.parse("4 | 5 if(f g)").is_err());
-//     assert!(parser.parse("[a, b] if").is_err());
-//     assert!(parser.parse("(f g)").is_err());
-// }
+use super::ast::Expression;
+use super::ast::Pattern::*;
+use super::lexer::Lexer;
+use super::span::{Span, UnSpan};
+use super::*;
+use crate::test_util::to_of64;
+use crate::types::structure::Flex;
+use lalrpop_util::ParseError;
+use std::collections::BTreeMap;
+
+fn parse(parser: &grammar::PatternParser, inp: &'static str) -> ast::Pattern {
+    parser.parse(0, Lexer::new(0, inp)).unwrap().unspanned()
+}
+
+// Patterns
+
+#[test]
+fn test_parse_atomic_pattern() {
+    let parser = grammar::PatternParser::new();
+
+    assert!(parse(&parser, "_") == Wildcard(Span::dummy()));
+    assert!(parse(&parser, "__") == Identifier("__".to_string(), Span::dummy()));
+    assert!(parse(&parser, "-6789") == IntLiteral(-6789, Span::dummy()));
+    assert!(parse(&parser, "9.8e3") == FloatLiteral(to_of64(9800.0), Span::dummy()));
+    assert!(parse(&parser, "\"helloⓓⓕ\"") == StringLiteral("helloⓓⓕ".to_string(), Span::dummy()));
+    assert!(parse(&parser, "x") == Identifier("x".to_string(), Span::dummy()));
+    assert!(parse(&parser, "__o98") == Identifier("__o98".to_string(), Span::dummy()));
+    assert!(parse(&parser, "None") == Identifier("None".to_string(), Span::dummy()));
+    assert!(parse(&parser, "[ ]") == EmptyList(Span::dummy()));
+    assert!(parse(&parser, "[]") == EmptyList(Span::dummy()));
+    assert!(parse(&parser, "(x)") == Identifier("x".to_string(), Span::dummy()));
+
+    assert!(parser.parse(0, Lexer::new(0, "=>")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "ⓓⓕ")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "- 5")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "__ => _")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "98x")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "int")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "()")).is_err());
+}
+
+#[test]
+fn test_parse_variant_pattern() {
+    let parser = grammar::PatternParser::new();
+
+    assert!(
+        parse(&parser, "Some with x")
+            == TypeVariant(
+                "Some".to_string(),
+                Some(Box::new(Identifier("x".to_string(), Span::dummy()))),
+                Span::dummy()
+            )
+    );
+    assert!(
+        parse(&parser, "Some with 4")
+            == TypeVariant(
+                "Some".to_string(),
+                Some(Box::new(IntLiteral(4, Span::dummy()))),
+                Span::dummy()
+            )
+    );
+    assert!(
+        parse(&parser, "Integer with (4)")
+            == TypeVariant(
+                "Integer".to_string(),
+                Some(Box::new(IntLiteral(4, Span::dummy()))),
+                Span::dummy()
+            )
+    );
+    assert!(
+        parse(&parser, "Option with (Tree, 4)")
+            == TypeVariant(
+                "Option".to_string(),
+                Some(Box::new(Tuple(
+                    vec![
+                        Identifier("Tree".to_string(), Span::dummy()),
+                        IntLiteral(4, Span::dummy())
+                    ],
+                    Span::dummy()
+                ))),
+                Span::dummy()
+            )
+    );
+    assert!(
+        parse(&parser, "Wrap with Some with _")
+            == TypeVariant(
+                "Wrap".to_string(),
+                Some(Box::new(TypeVariant(
+                    "Some".to_string(),
+                    Some(Box::new(Wildcard(Span::dummy()))),
+                    Span::dummy()
+                ))),
+                Span::dummy()
+            )
+    );
+    assert!(
+        parse(&parser, "bool with (List with [_, x :: xs])")
+            == TypeVariant(
+                "bool".to_string(),
+                Some(Box::new(TypeVariant(
+                    "List".to_string(),
+                    Some(Box::new(List(
+                        vec![
+                            Wildcard(Span::dummy()),
+                            ListCons("x".to_string(), "xs".to_string(), Span::dummy())
+                        ],
+                        Span::dummy()
+                    ))),
+                    Span::dummy()
+                ))),
+                Span::dummy()
+            )
+    );
+
+    assert!(parser.parse(0, Lexer::new(0, "Some with int")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "Option x")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "with 4")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "Some with")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "Some with x :: xs")).is_err());
+    assert!(matches!(
+        parser.parse(0, Lexer::new(0, "Some with(x)")).err().unwrap(),
+        ParseError::User { error: (e, s) }
+        if e.contains("Space required") && s == Span::new(0, 5, 12)
+    ));
+}
+
+#[test]
+fn test_parse_compound_pattern() {
+    let parser = grammar::PatternParser::new();
+
+    assert!(
+        parse(&parser, "x :: xs") == ListCons("x".to_string(), "xs".to_string(), Span::dummy())
+    );
+    assert!(
+        parse(&parser, "[4, _, _, []]")
+            == List(
+                vec![
+                    IntLiteral(4, Span::dummy()),
+                    Wildcard(Span::dummy()),
+                    Wildcard(Span::dummy()),
+                    EmptyList(Span::dummy())
+                ],
+                Span::dummy()
+            )
+    );
+    assert!(
+        parse(&parser, "[4, -5.6, _]")
+            == List(
+                vec![
+                    IntLiteral(4, Span::dummy()),
+                    FloatLiteral(to_of64(-5.6), Span::dummy()),
+                    Wildcard(Span::dummy())
+                ],
+                Span::dummy()
+            )
+    );
+    assert!(
+        parse(&parser, "[[Some with x]]")
+            == List(
+                vec![List(
+                    vec![TypeVariant(
+                        "Some".to_string(),
+                        Some(Box::new(Identifier("x".to_string(), Span::dummy()))),
+                        Span::dummy()
+                    )],
+                    Span::dummy()
+                )],
+                Span::dummy()
+            )
+    );
+    assert!(
+        parse(&parser, "Integer with (Option with y, x :: xs)")
+            == TypeVariant(
+                "Integer".to_string(),
+                Some(Box::new(Tuple(
+                    vec![
+                        TypeVariant(
+                            "Option".to_string(),
+                            Some(Box::new(Identifier("y".to_string(), Span::dummy()))),
+                            Span::dummy()
+                        ),
+                        ListCons("x".to_string(), "xs".to_string(), Span::dummy())
+                    ],
+                    Span::dummy()
+                ))),
+                Span::dummy()
+            )
+    );
+    assert!(
+        parse(&parser, "Float with (5.7,)")
+            == TypeVariant(
+                "Float".to_string(),
+                Some(Box::new(Tuple(
+                    vec![FloatLiteral(to_of64(5.7), Span::dummy())],
+                    Span::dummy()
+                ))),
+                Span::dummy()
+            )
+    );
+    assert!(
+        parse(&parser, "(_, _, v)")
+            == Tuple(
+                vec![
+                    Wildcard(Span::dummy()),
+                    Wildcard(Span::dummy()),
+                    Identifier("v".to_string(), Span::dummy())
+                ],
+                Span::dummy()
+            )
+    );
+    assert!(parse(&parser, "(7, )") == Tuple(vec![IntLiteral(7, Span::dummy())], Span::dummy()));
+    assert!(
+        parse(&parser, "(x, _, (4, 5))")
+            == Tuple(
+                vec![
+                    Identifier("x".to_string(), Span::dummy()),
+                    Wildcard(Span::dummy()),
+                    Tuple(
+                        vec![IntLiteral(4, Span::dummy()), IntLiteral(5, Span::dummy())],
+                        Span::dummy()
+                    )
+                ],
+                Span::dummy()
+            )
+    );
+
+    assert!(parser.parse(0, Lexer::new(0, "(_, 4")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "( )")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "[x, y")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "[4,]")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "(x)with y")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "x :: 4")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "x :: y :: z")).is_err());
+}
+
+#[test]
+fn test_parse_record_pattern() {
+    let parser = grammar::PatternParser::new();
+
+    assert!(
+        parse(&parser, "{ a: x }")
+            == Record(
+                BTreeMap::from([(
+                    "a".to_string(),
+                    (Identifier("x".to_string(), Span::dummy()), Span::dummy())
+                )]),
+                Flex::Exact,
+                Span::dummy()
+            )
+    );
+    assert!(
+        parse(&parser, "{ a: 4, b: [], }")
+            == Record(
+                BTreeMap::from([
+                    (
+                        "a".to_string(),
+                        (IntLiteral(4, Span::dummy()), Span::dummy())
+                    ),
+                    ("b".to_string(), (EmptyList(Span::dummy()), Span::dummy()))
+                ]),
+                Flex::Exact,
+                Span::dummy()
+            )
+    );
+    assert!(
+        parse(&parser, "{ val: v, left: Node with l, _ }")
+            == Record(
+                BTreeMap::from([
+                    (
+                        "val".to_string(),
+                        (Identifier("v".to_string(), Span::dummy()), Span::dummy())
+                    ),
+                    (
+                        "left".to_string(),
+                        (
+                            TypeVariant(
+                                "Node".to_string(),
+                                Some(Box::new(Identifier("l".to_string(), Span::dummy()))),
+                                Span::dummy()
+                            ),
+                            Span::dummy()
+                        )
+                    )
+                ]),
+                Flex::Permissive,
+                Span::dummy()
+            )
+    );
+    assert!(
+        parse(&parser, "{ a: _, _, }")
+            == Record(
+                BTreeMap::from([("a".to_string(), (Wildcard(Span::dummy()), Span::dummy()))]),
+                Flex::Permissive,
+                Span::dummy()
+            )
+    );
+
+    assert!(parser.parse(0, Lexer::new(0, "{}")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "{ _ }")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "{ a: x, _, b: y }")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "{ 4: x }")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "{ a: x if true }")).is_err());
+    assert!(matches!(
+        parser.parse(0, Lexer::new(0, "{ a: x, a: y }")).err().unwrap(),
+        ParseError::User { error: (e, s) }
+        if e.contains("Duplicate field a") && s == Span::new(0, 8, 12)
+    ));
+}
+
+#[test]
+fn test_parse_complex_pattern() {
+    let parser = grammar::PatternParser::new();
+
+    // Pattern union
+    assert!(
+        parse(&parser, "x | y")
+            == Union(
+                vec![
+                    Identifier("x".to_string(), Span::dummy()),
+                    Identifier("y".to_string(), Span::dummy())
+                ],
+                Span::dummy()
+            )
+    );
+    assert!(
+        parse(&parser, "(4) | 5.0 | \"hello\"")
+            == Union(
+                vec![
+                    IntLiteral(4, Span::dummy()),
+                    FloatLiteral(to_of64(5.0), Span::dummy()),
+                    StringLiteral("hello".to_string(), Span::dummy())
+                ],
+                Span::dummy()
+            )
+    );
+    assert!(
+        parse(&parser, "x|(y)")
+            == Union(
+                vec![
+                    Identifier("x".to_string(), Span::dummy()),
+                    Identifier("y".to_string(), Span::dummy())
+                ],
+                Span::dummy()
+            )
+    );
+    assert!(
+        parse(&parser, "5 | ~6")
+            == Union(
+                vec![
+                    IntLiteral(5, Span::dummy()),
+                    Complement(Box::new(IntLiteral(6, Span::dummy())), Span::dummy())
+                ],
+                Span::dummy()
+            )
+    );
+    assert!(parser.parse(0, Lexer::new(0, "4 |")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "|")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "4 | 5 | ")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "| 6.0 | 7")).is_err());
+
+    // Pattern complement
+    assert!(
+        parse(&parser, "~4") == Complement(Box::new(IntLiteral(4, Span::dummy())), Span::dummy())
+    );
+    assert!(
+        parse(&parser, "~[_, 4]")
+            == Complement(
+                Box::new(List(
+                    vec![Wildcard(Span::dummy()), IntLiteral(4, Span::dummy())],
+                    Span::dummy()
+                )),
+                Span::dummy()
+            )
+    );
+    assert!(
+        parse(&parser, "~Option with 4")
+            == Complement(
+                Box::new(TypeVariant(
+                    "Option".to_string(),
+                    Some(Box::new(IntLiteral(4, Span::dummy()))),
+                    Span::dummy()
+                )),
+                Span::dummy()
+            )
+    );
+    assert!(
+        parse(&parser, "~(x | y)")
+            == Complement(
+                Box::new(Union(
+                    vec![
+                        Identifier("x".to_string(), Span::dummy()),
+                        Identifier("y".to_string(), Span::dummy())
+                    ],
+                    Span::dummy()
+                )),
+                Span::dummy()
+            )
+    );
+    assert!(parser.parse(0, Lexer::new(0, "!")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "~")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "~~4")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "4~")).is_err());
+
+    // Guarded pattern
+    assert!(
+        parse(&parser, "x | y if true")
+            == Guarded {
+                pattern: Box::new(Union(
+                    vec![
+                        Identifier("x".to_string(), Span::dummy()),
+                        Identifier("y".to_string(), Span::dummy())
+                    ],
+                    Span::dummy()
+                )),
+                guard: Expression::Identifier("true".to_string(), Span::dummy()),
+                span: Span::dummy(),
+            }
+    );
+    assert!(
+        parse(&parser, "x :: xs if f x")
+            == Guarded {
+                pattern: Box::new(ListCons("x".to_string(), "xs".to_string(), Span::dummy())),
+                guard: Expression::FuncApplication(
+                    Box::new(Expression::Identifier("f".to_string(), Span::dummy())),
+                    vec![Expression::Identifier("x".to_string(), Span::dummy())],
+                    Span::dummy()
+                ),
+                span: Span::dummy(),
+            }
+    );
+    assert!(parser.parse(0, Lexer::new(0, "if")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "[a, b] if")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "x if y if z")).is_err());
+    assert!(matches!(
+        parser.parse(0, Lexer::new(0, "x | 4if true")).err().unwrap(),
+        ParseError::User { error: (e, s) }
+        if e.contains("Space required") && s == Span::new(0, 0, 7)
+    ));
+
+    // Case pattern
+    assert!(
+        parse(&parser, "case x < 1")
+            == Case(
+                Expression::FuncApplication(
+                    Box::new(Expression::BinaryOp(ast::BinaryOp::Lt, Span::dummy())),
+                    vec![
+                        Expression::Identifier("x".to_string(), Span::dummy()),
+                        Expression::IntLiteral(1, Span::dummy())
+                    ],
+                    Span::dummy()
+                ),
+                Span::dummy()
+            )
+    );
+    assert!(parser.parse(0, Lexer::new(0, "case")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "case x | y")).is_err());
+}
@@ -0,0 +1,225 @@
+use super::ast::Expression::*;
+use super::ast::Statement;
+use super::span::{Span, SpanlessEq, UnSpan};
+use super::*;
+
+#[test]
+fn test_parse_program() {
+    let (prog, errors) = parse_program(0, "4; x; struct P { a: int };");
+    assert!(errors.is_empty());
+    assert!(prog.len() == 3);
+    assert!(prog[0].unspanned() == Statement::Expression(IntLiteral(4, Span::dummy())));
+    assert!(
+        prog[1].unspanned() == Statement::Expression(Identifier("x".to_string(), Span::dummy()))
+    );
+
+    assert!(!parse_program(0, "").1.is_empty());
+    assert!(!parse_program(0, "4").1.is_empty());
+    assert!(!parse_program(0, "4;;").1.is_empty());
+}
+
+#[test]
+fn test_parse_program_spans() {
+    // Every node carries the file it was parsed from
+    let (prog, _) = parse_program(3, "let f x = x + 1;");
+    let Statement::Expression(Let(varwithval, None, span)) = &prog[0] else {
+        panic!("Input is not a let");
+    };
+    assert!(*span == Span::new(3, 0, 15));
+    assert!(varwithval.name.1 == Span::new(3, 4, 5));
+
+    // The same program in another file and with different spacing is only
+    // equal when spans are ignored
+    let (other, _) = parse_program(1, "let  f x  =  x+1 ;");
+    assert!(prog != other);
+    assert!(prog.spanless_eq(&other));
+    assert!(!prog.spanless_eq(&parse_program(3, "let f x = x + 2;").0));
+}
+
+#[test]
+fn test_parse_program_recovery() {
+    // Every erroneous statement is reported, and the rest are kept
+    let (prog, errors) = parse_program(0, "let = 4; 5; (6 7; 8;");
+    assert!(errors.len() == 2);
+    assert!(
+        errors[0]
+            == (
+                "Unexpected token `=`, expected one of identifier, `rec`, `(`".to_string(),
+                Span::new(0, 4, 7)
+            )
+    );
+    // The operand before the `;` could still be followed by an operator
+    let unclosed_paren =
+        "Unexpected token `;`, expected one of operator, `)`, `,`, `+`, `-`, `*`, \
+                          `/`, `+.`, `-.`, `*.`, `/.`, `<`, `>`, `<=`, `>=`, `==`, `!=`, `::`, \
+                          `|>`, `>>`, `<<`, `&&`, `||`";
+    assert!(errors[1] == (unclosed_paren.to_string(), Span::new(0, 16, 17)));
+    assert!(
+        prog.iter().map(|s| s.unspanned()).collect::<Vec<_>>()
+            == vec![
+                Statement::Error(errors[0].0.clone(), Span::dummy()),
+                Statement::Expression(IntLiteral(5, Span::dummy())),
+                Statement::Error(errors[1].0.clone(), Span::dummy()),
+                Statement::Expression(IntLiteral(8, Span::dummy())),
+            ]
+    );
+    assert!(prog[0] == Statement::Error(errors[0].0.clone(), Span::new(0, 4, 7)));
+
+    // Errors raised by grammar actions replace the statement they are in
+    let (prog, errors) = parse_program(0, "4; struct S { a: int, a: int }; 4;");
+    assert!(prog.len() == 3);
+    assert!(prog[1] == Statement::Error(errors[0].0.clone(), Span::new(0, 22, 28)));
+    assert!(
+        errors
+            == vec![(
+                "Duplicate member a in struct".to_string(),
+                Span::new(0, 22, 28)
+            )]
+    );
+
+    // Syntax errors and errors raised by grammar actions are all reported
+    let (prog, errors) = parse_program(0, "let = 4; let r = {a: 1, a: 2}; 5; (6 7; A.b with(1);");
+    assert!(
+        errors
+            .iter()
+            .map(|(msg, _)| msg.as_str())
+            .collect::<Vec<_>>()
+            == vec![
+                "Unexpected token `=`, expected one of identifier, `rec`, `(`",
+                "Duplicate field a in structural record",
+                unclosed_paren,
+                "Space required between `with` token and enum field",
+            ]
+    );
+    assert!(prog.len() == 5);
+    assert!(prog[2].unspanned() == Statement::Expression(IntLiteral(5, Span::dummy())));
+
+    // An error in the final statement, without a `;` to recover at
+    let (prog, errors) = parse_program(0, "4; 5 +");
+    assert!(prog.len() == 2);
+    assert!(errors.len() == 1);
+    assert!(errors[0]
+        .0
+        .contains("Unexpected end of input, expected one of identifier, integer literal"));
+    assert!(errors[0].1 == Span::new(0, 6, 6));
+
+    // Lexer errors are recovered from like syntax errors
+    let (prog, errors) = parse_program(0, "4; ⏰; 5; let a = 0x; 6;");
+    assert!(prog.len() == 5);
+    assert!(
+        errors
+            == vec![
+                ("Unexpected character `⏰`".to_string(), Span::new(0, 3, 6)),
+                (
+                    "Number literal 0x has no digits".to_string(),
+                    Span::new(0, 19, 21)
+                ),
+            ]
+    );
+    assert!(prog[4].unspanned() == Statement::Expression(IntLiteral(6, Span::dummy())));
+
+    // Operators declared anywhere are still known after recovering
+    let (prog, errors) = parse_program(0, "let = 4; a <+> b; infixl 6 <+>;");
+    assert!(errors.len() == 1);
+    assert!(prog.len() == 3);
+    assert!(!matches!(prog[1], Statement::Error(..)));
+}
+
+#[test]
+fn test_resolve_fixity() {
+    let id = |name: &str| Identifier(name.to_string(), Span::dummy());
+    let app = |op: &str, lhs, rhs| FuncApplication(Box::new(id(op)), vec![lhs, rhs], Span::dummy());
+    let add = |lhs, rhs| {
+        FuncApplication(
+            Box::new(BinaryOp(ast::BinaryOp::Add, Span::dummy())),
+            vec![lhs, rhs],
+            Span::dummy(),
+        )
+    };
+    // The last expression statement of a program without errors
+    let resolved = |inp: &'static str| {
+        let (prog, errors) = parse_program(0, inp);
+        assert!(errors.is_empty(), "{:?}", errors);
+        prog.iter()
+            .rev()
+            .find_map(|stmt| match stmt.unspanned() {
+                Statement::Expression(e) => Some(e),
+                _ => None,
+            })
+            .unwrap()
+    };
+
+    assert!(
+        resolved("infixl 6 <+>; a <+> b <+> c;")
+            == app("<+>", app("<+>", id("a"), id("b")), id("c"))
+    );
+    assert!(
+        resolved("infixr 5 ++; a ++ b ++ c;") == app("++", id("a"), app("++", id("b"), id("c")))
+    );
+    // Declarations apply to the whole file, and are compared with the
+    // precedences of builtin operators
+    assert!(resolved("a <*> b + c; infixl 7 <*>;") == add(app("<*>", id("a"), id("b")), id("c")));
+    assert!(resolved("infixl 5 <+>; a <+> b + c;") == app("<+>", id("a"), add(id("b"), id("c"))));
+    // Undeclared operators bind tighter than every builtin operator
+    assert!(resolved("a + b <?> c;") == add(id("a"), app("<?>", id("b"), id("c"))));
+    // Parentheses group as usual
+    assert!(resolved("(a + b) <?> c;") == app("<?>", add(id("a"), id("b")), id("c")));
+    // Chains inside other expressions are resolved too
+    assert!(
+        resolved("infixr 1 <|; [x <| y <| z];")
+            == List(
+                vec![app("<|", id("x"), app("<|", id("y"), id("z")))],
+                Span::dummy()
+            )
+    );
+    assert!(
+        resolved("let (<+>) a b = a; let f x = x <+> x;")
+            == Let(
+                ast::VarWithValue {
+                    name: ("f".to_string(), Span::dummy()),
+                    args: vec![("x".to_string(), None, Span::dummy())],
+                    output_type: None,
+                    rec: false,
+                    expr: Box::new(app("<+>", id("x"), id("x"))),
+                },
+                None,
+                Span::dummy()
+            )
+    );
+}
+
+#[test]
+fn test_resolve_fixity_errors() {
+    let (prog, errors) = parse_program(0, "infix 4 ===; a === b === c; 1;");
+    assert!(
+        errors
+            == vec![(
+                "Cannot mix === (infix 4) and === (infix 4) in the same infix expression without parentheses"
+                    .to_string(),
+                Span::new(0, 21, 24)
+            )]
+    );
+    // Only the statement with the error is dropped
+    assert!(prog.len() == 3);
+    assert!(prog[2].unspanned() == Statement::Expression(IntLiteral(1, Span::dummy())));
+
+    assert!(
+        parse_program(0, "infixl 6 <+>; infixr 6 +>; a <+> b +> c;").1
+            == vec![(
+                "Cannot mix <+> (infixl 6) and +> (infixr 6) in the same infix expression without parentheses"
+                    .to_string(),
+                Span::new(0, 35, 37)
+            )]
+    );
+    assert!(
+        parse_program(0, "infixl 6 <+>; infixr 2 $ <+>;").1
+            == vec![(
+                "Fixity of <+> is already declared".to_string(),
+                Span::new(0, 25, 28)
+            )]
+    );
+    // Parentheses resolve the ambiguity
+    assert!(parse_program(0, "infix 4 ===; (a === b) === c;")
+        .1
+        .is_empty());
+}
@@ -1,13 +1,18 @@
 use super::ast::PolytypeVar;
-use super::ast::Statement::EnumDecl;
-use super::span::UnSpan;
+use super::ast::Statement::{
+    EnumDecl, Fixity, Import, InterfaceDecl, InterfaceImpl, Open, StructDecl, TypeAlias,
+};
+use super::ast::{Assoc, AttrSet, Expression, VarWithValue};
+use super::lexer::Lexer;
+use super::span::{Span, UnSpan};
 use super::*;
 use crate::types::structure::{Flex, Structure};
 use crate::types::Type;
+use lalrpop_util::ParseError;
 use std::collections::BTreeMap;
 
 fn parse_enum_decl(parser: &grammar::StatementParser, inp: &'static str) -> ast::Statement {
-    let out = parser.parse(inp).unwrap().unspanned();
+    let out = parser.parse(0, Lexer::new(0, inp)).unwrap().unspanned();
     if let EnumDecl {
         name: _,
         type_args: _,
@@ -28,37 +33,37 @@ fn test_parse_enum_decl() {
     assert!(
         parse_enum_decl(&parser, "enum A = B")
             == EnumDecl {
-                name: ("A".to_string(), None),
+                name: ("A".to_string(), Span::dummy()),
                 type_args: vec![],
-                variants: vec![("B".to_string(), None, None)],
-                span: None,
+                variants: vec![("B".to_string(), None, Span::dummy())],
+                span: Span::dummy(),
             }
     );
 
     assert!(
         parse_enum_decl(&parser, "enum bool = false | true")
             == EnumDecl {
-                name: ("bool".to_string(), None),
+                name: ("bool".to_string(), Span::dummy()),
                 type_args: vec![],
                 variants: vec![
-                    ("false".to_string(), None, None),
-                    ("true".to_string(), None, None),
+                    ("false".to_string(), None, Span::dummy()),
+                    ("true".to_string(), None, Span::dummy()),
                 ],
-                span: None,
+                span: Span::dummy(),
             }
     );
 
     assert!(
         parse_enum_decl(&parser, "enum A = B | C | D")
             == EnumDecl {
-                name: ("A".to_string(), None),
+                name: ("A".to_string(), Span::dummy()),
                 type_args: vec![],
                 variants: vec![
-                    ("B".to_string(), None, None),
-                    ("C".to_string(), None, None),
-                    ("D".to_string(), None, None)
+                    ("B".to_string(), None, Span::dummy()),
+                    ("C".to_string(), None, Span::dummy()),
+                    ("D".to_string(), None, Span::dummy())
                 ],
-                span: None,
+                span: Span::dummy(),
             }
     );
 
@@ -70,33 +75,33 @@ fn test_parse_enum_decl() {
     | Var3 with float
     | Var4 with [string]"
         ) == EnumDecl {
-            name: ("Thing".to_string(), None),
+            name: ("Thing".to_string(), Span::dummy()),
             type_args: vec![],
             variants: vec![
-                ("Var1".to_string(), None, None),
-                ("Var2".to_string(), Some(Type::Int), None),
-                ("Var3".to_string(), Some(Type::Float), None),
+                ("Var1".to_string(), None, Span::dummy()),
+                ("Var2".to_string(), Some(Type::Int), Span::dummy()),
+                ("Var3".to_string(), Some(Type::Float), Span::dummy()),
                 (
                     "Var4".to_string(),
                     Some(Type::List(Box::new(Type::String))),
-                    None
+                    Span::dummy()
                 ),
             ],
-            span: None,
+            span: Span::dummy(),
         }
     );
 
     assert!(
         parse_enum_decl(&parser, "enum 'a bin_tree = Leaf | Node with { val: 'a, left: 'a bin_tree, right: 'a bin_tree }")
         == EnumDecl {
-            name: ("bin_tree".to_string(), None),
+            name: ("bin_tree".to_string(), Span::dummy()),
             type_args: vec![PolytypeVar {
                 name: "a".to_string(),
                 bound: None,
-                span: None,
+                span: Span::dummy(),
             }],
             variants: vec![
-                ("Leaf".to_string(), None, None),
+                ("Leaf".to_string(), None, Span::dummy()),
                 ("Node".to_string(), Some(Type::Record(Structure {
                     methods: BTreeMap::new(),
                     values: BTreeMap::from([
@@ -105,30 +110,30 @@ fn test_parse_enum_decl() {
                         ("right".to_string(), Type::TypeId("bin_tree".to_string(), vec![Type::Poly("a".to_string(), None)])),
                     ]),
                     flex: Flex::Permissive,
-                })), None)
+                })), Span::dummy())
             ],
-            span: None,
+            span: Span::dummy(),
         }
     );
 
     assert!(
         parse_enum_decl(&parser, "enum 'a Option = None | Some with 'a")
             == EnumDecl {
-                name: ("Option".to_string(), None),
+                name: ("Option".to_string(), Span::dummy()),
                 type_args: vec![PolytypeVar {
                     name: "a".to_string(),
                     bound: None,
-                    span: None,
+                    span: Span::dummy(),
                 },],
                 variants: vec![
-                    ("None".to_string(), None, None),
+                    ("None".to_string(), None, Span::dummy()),
                     (
                         "Some".to_string(),
                         Some(Type::Poly("a".to_string(), None)),
-                        None
+                        Span::dummy()
                     ),
                 ],
-                span: None,
+                span: Span::dummy(),
             }
     );
 
@@ -137,13 +142,13 @@ fn test_parse_enum_decl() {
             &parser,
             "enum X = Y with int Option | Z with Num'a 'b Matrix"
         ) == EnumDecl {
-            name: ("X".to_string(), None),
+            name: ("X".to_string(), Span::dummy()),
             type_args: vec![],
             variants: vec![
                 (
                     "Y".to_string(),
                     Some(Type::TypeId("Option".to_string(), vec![Type::Int])),
-                    None
+                    Span::dummy()
                 ),
                 (
                     "Z".to_string(),
@@ -154,23 +159,794 @@ fn test_parse_enum_decl() {
                             Type::Poly("b".to_string(), None),
                         ]
                     )),
-                    None
+                    Span::dummy()
                 )
             ],
-            span: None,
+            span: Span::dummy(),
+        }
+    );
+
+    assert!(parser.parse(0, Lexer::new(0, "enum A = B, C")).is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "enum A = B with [int, string]"))
+        .is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "enum A = B with (int, string)"))
+        .is_ok());
+    assert!(parser.parse(0, Lexer::new(0, "A = B | C")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "A | B | C")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "enum A = B C | D")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "enum X = A B C")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "enum A = ")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "enum A B = C")).is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "enum 'a -> 'b Option = Some | None"))
+        .is_err());
+    assert!(parser.parse(0, Lexer::new(0, "enumbool = Thing")).is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "let enum A = B | C"))
+        .is_err());
+}
+
+fn parse_struct_decl(parser: &grammar::StatementParser, inp: &'static str) -> ast::Statement {
+    let out = parser.parse(0, Lexer::new(0, inp)).unwrap().unspanned();
+    if let StructDecl { .. } = out {
+        out
+    } else {
+        panic!("Input is not a struct declaration.")
+    }
+}
+
+#[test]
+fn test_parse_type_alias() {
+    let parser = grammar::StatementParser::new();
+
+    assert!(
+        parser
+            .parse(0, Lexer::new(0, "type Ints = [int]"))
+            .unwrap()
+            .unspanned()
+            == TypeAlias {
+                name: ("Ints".to_string(), Span::dummy()),
+                type_args: vec![],
+                typ: Type::List(Box::new(Type::Int)),
+                span: Span::dummy(),
+            }
+    );
+    assert!(
+        parser
+            .parse(0, Lexer::new(0, "type 'a 'b Pair = ('a, 'b)"))
+            .unwrap()
+            == TypeAlias {
+                name: ("Pair".to_string(), Span::new(0, 11, 15)),
+                type_args: vec![
+                    PolytypeVar {
+                        name: "a".to_string(),
+                        bound: None,
+                        span: Span::new(0, 5, 7),
+                    },
+                    PolytypeVar {
+                        name: "b".to_string(),
+                        bound: None,
+                        span: Span::new(0, 8, 10),
+                    },
+                ],
+                typ: Type::Tuple(vec![
+                    Type::Poly("a".to_string(), None),
+                    Type::Poly("b".to_string(), None)
+                ]),
+                span: Span::new(0, 0, 26),
+            }
+    );
+    assert!(
+        parser
+            .parse(0, Lexer::new(0, "type 'a Op = 'a -> 'a -> 'a"))
+            .unwrap()
+            .unspanned()
+            == TypeAlias {
+                name: ("Op".to_string(), Span::dummy()),
+                type_args: vec![PolytypeVar {
+                    name: "a".to_string(),
+                    bound: None,
+                    span: Span::dummy(),
+                }],
+                typ: Type::Function(
+                    Box::new(Type::Poly("a".to_string(), None)),
+                    Box::new(Type::Function(
+                        Box::new(Type::Poly("a".to_string(), None)),
+                        Box::new(Type::Poly("a".to_string(), None))
+                    ))
+                ),
+                span: Span::dummy(),
+            }
+    );
+
+    assert!(parser.parse(0, Lexer::new(0, "type = int")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "type 'a T")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "type T a = int")).is_err());
+    // Parameters can also come after the name, in the same order
+    assert!(
+        parser
+            .parse(0, Lexer::new(0, "type Pair 'a 'b = ('a, 'b)"))
+            .unwrap()
+            .unspanned()
+            == parser
+                .parse(0, Lexer::new(0, "type 'a 'b Pair = ('a, 'b)"))
+                .unwrap()
+                .unspanned()
+    );
+    assert!(
+        parser.parse(0, Lexer::new(0, "type 'a T 'b = ('a, 'b)")) == Err(ParseError::User {
+            error: (
+                "Parameters of type alias T must either all come before its name or all after it"
+                    .to_string(),
+                Span::new(0, 5, 12)
+            )
+        })
+    );
+    // `type` is a keyword
+    assert!(parser.parse(0, Lexer::new(0, "let type = 4")).is_err());
+}
+
+#[test]
+fn test_parse_import_open() {
+    let parser = grammar::StatementParser::new();
+
+    assert!(
+        parser.parse(0, Lexer::new(0, "import List")).unwrap()
+            == Import {
+                path: vec![("List".to_string(), Span::new(0, 7, 11))],
+                span: Span::new(0, 0, 11),
+            }
+    );
+    assert!(
+        parser
+            .parse(0, Lexer::new(0, "import util.text.Strings"))
+            .unwrap()
+            .unspanned()
+            == Import {
+                path: vec![
+                    ("util".to_string(), Span::dummy()),
+                    ("text".to_string(), Span::dummy()),
+                    ("Strings".to_string(), Span::dummy())
+                ],
+                span: Span::dummy(),
+            }
+    );
+    assert!(
+        parser.parse(0, Lexer::new(0, "open List")).unwrap()
+            == Open {
+                module: ("List".to_string(), Span::new(0, 5, 9)),
+                span: Span::new(0, 0, 9),
+            }
+    );
+
+    assert!(parser.parse(0, Lexer::new(0, "import")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "import util.")).is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "import \"List.wye\""))
+        .is_err());
+    assert!(parser.parse(0, Lexer::new(0, "open util.List")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "let open = 1")).is_err());
+}
+
+#[test]
+fn test_parse_fixity() {
+    let parser = grammar::StatementParser::new();
+
+    assert!(
+        parser.parse(0, Lexer::new(0, "infixl 6 <+>")).unwrap()
+            == Fixity {
+                assoc: Assoc::Left,
+                precedence: 6,
+                operators: vec![("<+>".to_string(), Span::new(0, 9, 12))],
+                span: Span::new(0, 0, 12),
+            }
+    );
+    assert!(
+        parser
+            .parse(0, Lexer::new(0, "infixr 0 $ ++"))
+            .unwrap()
+            .unspanned()
+            == Fixity {
+                assoc: Assoc::Right,
+                precedence: 0,
+                operators: vec![
+                    ("$".to_string(), Span::dummy()),
+                    ("++".to_string(), Span::dummy())
+                ],
+                span: Span::dummy(),
+            }
+    );
+    assert!(
+        parser
+            .parse(0, Lexer::new(0, "infix 4 ==="))
+            .unwrap()
+            .unspanned()
+            == Fixity {
+                assoc: Assoc::None,
+                precedence: 4,
+                operators: vec![("===".to_string(), Span::dummy())],
+                span: Span::dummy(),
+            }
+    );
+
+    assert!(parser.parse(0, Lexer::new(0, "infixl 6")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "infixl <+>")).is_err());
+    // Builtin operators have fixed fixities
+    assert!(parser.parse(0, Lexer::new(0, "infixl 6 +")).is_err());
+    assert!(matches!(
+        parser.parse(0, Lexer::new(0, "infixl 10 <+>")).err().unwrap(),
+        ParseError::User { error: (e, s) }
+        if e == "Operator precedence must be between 0 and 9, but got 10" && s == Span::new(0, 7, 9)
+    ));
+}
+
+#[test]
+fn test_parse_struct_decl() {
+    let parser = grammar::StatementParser::new();
+
+    assert!(
+        parse_struct_decl(&parser, "struct A { a: int }")
+            == StructDecl {
+                name: ("A".to_string(), Span::dummy()),
+                type_args: vec![],
+                members: vec![("a".to_string(), Type::Int, Span::dummy())],
+                span: Span::dummy(),
+            }
+    );
+
+    assert!(
+        parse_struct_decl(
+            &parser,
+            "struct Sequence {
+        len: int,
+        _sequence: [int],
+    }"
+        ) == StructDecl {
+            name: ("Sequence".to_string(), Span::dummy()),
+            type_args: vec![],
+            members: vec![
+                ("len".to_string(), Type::Int, Span::dummy()),
+                (
+                    "_sequence".to_string(),
+                    Type::List(Box::new(Type::Int)),
+                    Span::dummy()
+                ),
+            ],
+            span: Span::dummy(),
+        }
+    );
+
+    assert!(
+        parse_struct_decl(
+            &parser,
+            "struct Num'a Matrix { data: [['a]], dims: (int, int) }"
+        ) == StructDecl {
+            name: ("Matrix".to_string(), Span::dummy()),
+            type_args: vec![PolytypeVar {
+                name: "a".to_string(),
+                bound: Some("Num".to_string()),
+                span: Span::dummy(),
+            }],
+            members: vec![
+                (
+                    "data".to_string(),
+                    Type::List(Box::new(Type::List(Box::new(Type::Poly(
+                        "a".to_string(),
+                        None
+                    ))))),
+                    Span::dummy()
+                ),
+                (
+                    "dims".to_string(),
+                    Type::Tuple(vec![Type::Int, Type::Int]),
+                    Span::dummy()
+                ),
+            ],
+            span: Span::dummy(),
+        }
+    );
+
+    assert!(
+        parse_struct_decl(
+            &parser,
+            "struct Hash'k 'v HashTable { members: ['v LinkedList], hash: 'k -> int }"
+        ) == StructDecl {
+            name: ("HashTable".to_string(), Span::dummy()),
+            type_args: vec![
+                PolytypeVar {
+                    name: "k".to_string(),
+                    bound: Some("Hash".to_string()),
+                    span: Span::dummy(),
+                },
+                PolytypeVar {
+                    name: "v".to_string(),
+                    bound: None,
+                    span: Span::dummy(),
+                }
+            ],
+            members: vec![
+                (
+                    "members".to_string(),
+                    Type::List(Box::new(Type::TypeId(
+                        "LinkedList".to_string(),
+                        vec![Type::Poly("v".to_string(), None)]
+                    ))),
+                    Span::dummy()
+                ),
+                (
+                    "hash".to_string(),
+                    Type::Function(
+                        Box::new(Type::Poly("k".to_string(), None)),
+                        Box::new(Type::Int)
+                    ),
+                    Span::dummy()
+                ),
+            ],
+            span: Span::dummy(),
+        }
+    );
+
+    // Spans of the declaration and each member
+    assert!(
+        parser
+            .parse(0, Lexer::new(0, "struct P { x: int, y: float }"))
+            .unwrap()
+            == StructDecl {
+                name: ("P".to_string(), Span::new(0, 7, 8)),
+                type_args: vec![],
+                members: vec![
+                    ("x".to_string(), Type::Int, Span::new(0, 11, 17)),
+                    ("y".to_string(), Type::Float, Span::new(0, 19, 27)),
+                ],
+                span: Span::new(0, 0, 29),
+            }
+    );
+
+    assert!(matches!(
+        parser.parse(0, Lexer::new(0, "struct A { a: int, b: float, a: string }")).err().unwrap(),
+        ParseError::User { error: (e, s) }
+        if e.contains("Duplicate member a") && s == Span::new(0, 29, 38)
+    ));
+    assert!(parser.parse(0, Lexer::new(0, "struct A {}")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "struct A")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "struct A { a }")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "struct A { a: 4 }")).is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "struct A { a: int b: int }"))
+        .is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "struct A 'a { a: 'a }"))
+        .is_err());
+    assert!(parser.parse(0, Lexer::new(0, "struct { a: int }")).is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "struct A = { a: int }"))
+        .is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "let struct A { a: int }"))
+        .is_err());
+}
+
+fn parse_sig_decl(parser: &grammar::StatementParser, inp: &'static str) -> ast::Statement {
+    let out = parser.parse(0, Lexer::new(0, inp)).unwrap().unspanned();
+    if let InterfaceDecl { .. } = out {
+        out
+    } else {
+        panic!("Input is not an interface declaration.")
+    }
+}
+
+#[test]
+fn test_parse_sig_decl() {
+    let parser = grammar::StatementParser::new();
+
+    assert!(
+        parse_sig_decl(&parser, "sig Hash { method hash: int }")
+            == InterfaceDecl {
+                name: ("Hash".to_string(), Span::dummy()),
+                type_args: vec![],
+                requires: vec![],
+                impl_methods: vec![],
+                spec_methods: vec![("hash".to_string(), Type::Int, Span::dummy())],
+                values: vec![],
+                span: Span::dummy(),
+            }
+    );
+
+    assert!(
+        parse_sig_decl(&parser, "sig Add requires Num + Plus {}")
+            == InterfaceDecl {
+                name: ("Add".to_string(), Span::dummy()),
+                type_args: vec![],
+                requires: vec![
+                    ("Num".to_string(), Span::dummy(), vec![]),
+                    ("Plus".to_string(), Span::dummy(), vec![]),
+                ],
+                impl_methods: vec![],
+                spec_methods: vec![],
+                values: vec![],
+                span: Span::dummy(),
+            }
+    );
+
+    assert!(
+        parse_sig_decl(
+            &parser,
+            "sig 'a Ord requires 'a Eq {
+        val name: string,
+        method compare: 'a -> int,
+        method lt other = (compare other) < 0,
+    }"
+        ) == InterfaceDecl {
+            name: ("Ord".to_string(), Span::dummy()),
+            type_args: vec![PolytypeVar {
+                name: "a".to_string(),
+                bound: None,
+                span: Span::dummy(),
+            }],
+            requires: vec![(
+                "Eq".to_string(),
+                Span::dummy(),
+                vec![PolytypeVar {
+                    name: "a".to_string(),
+                    bound: None,
+                    span: Span::dummy(),
+                }]
+            )],
+            impl_methods: vec![(
+                VarWithValue {
+                    name: ("lt".to_string(), Span::dummy()),
+                    args: vec![("other".to_string(), None, Span::dummy())],
+                    output_type: None,
+                    rec: false,
+                    expr: Box::new(Expression::FuncApplication(
+                        Box::new(Expression::BinaryOp(ast::BinaryOp::Lt, Span::dummy())),
+                        vec![
+                            Expression::FuncApplication(
+                                Box::new(Expression::Identifier(
+                                    "compare".to_string(),
+                                    Span::dummy()
+                                )),
+                                vec![Expression::Identifier("other".to_string(), Span::dummy())],
+                                Span::dummy()
+                            ),
+                            Expression::IntLiteral(0, Span::dummy())
+                        ],
+                        Span::dummy()
+                    )),
+                },
+                Span::dummy()
+            )],
+            spec_methods: vec![(
+                "compare".to_string(),
+                Type::Function(
+                    Box::new(Type::Poly("a".to_string(), None)),
+                    Box::new(Type::Int)
+                ),
+                Span::dummy()
+            )],
+            values: vec![("name".to_string(), Type::String, Span::dummy())],
+            span: Span::dummy(),
+        }
+    );
+
+    assert!(
+        parse_sig_decl(
+            &parser,
+            "sig Num'a 'b Mix requires Num'a Shl + 'b Show { method describe = \\x -> x }"
+        ) == InterfaceDecl {
+            name: ("Mix".to_string(), Span::dummy()),
+            type_args: vec![
+                PolytypeVar {
+                    name: "a".to_string(),
+                    bound: Some("Num".to_string()),
+                    span: Span::dummy(),
+                },
+                PolytypeVar {
+                    name: "b".to_string(),
+                    bound: None,
+                    span: Span::dummy(),
+                }
+            ],
+            requires: vec![
+                (
+                    "Shl".to_string(),
+                    Span::dummy(),
+                    vec![PolytypeVar {
+                        name: "a".to_string(),
+                        bound: Some("Num".to_string()),
+                        span: Span::dummy(),
+                    }]
+                ),
+                (
+                    "Show".to_string(),
+                    Span::dummy(),
+                    vec![PolytypeVar {
+                        name: "b".to_string(),
+                        bound: None,
+                        span: Span::dummy(),
+                    }]
+                ),
+            ],
+            impl_methods: vec![(
+                VarWithValue {
+                    name: ("describe".to_string(), Span::dummy()),
+                    args: vec![],
+                    output_type: None,
+                    rec: false,
+                    expr: Box::new(Expression::Lambda {
+                        arg: "x".to_string(),
+                        expr: Box::new(Expression::Identifier("x".to_string(), Span::dummy())),
+                        span: Span::dummy(),
+                    }),
+                },
+                Span::dummy()
+            )],
+            spec_methods: vec![],
+            values: vec![],
+            span: Span::dummy(),
         }
     );
 
-    assert!(parser.parse("enum A = B, C").is_err());
-    assert!(parser.parse("enum A = B with [int, string]").is_err());
-    assert!(parser.parse("enum A = B with (int, string)").is_ok());
-    assert!(parser.parse("A = B | C").is_err());
-    assert!(parser.parse("A | B | C").is_err());
-    assert!(parser.parse("enum A = B C | D").is_err());
-    assert!(parser.parse("enum X = A B C").is_err());
-    assert!(parser.parse("enum A = ").is_err());
-    assert!(parser.parse("enum A B = C").is_err());
-    assert!(parser.parse("enum 'a -> 'b Option = Some | None").is_err());
-    assert!(parser.parse("enumbool = Thing").is_err());
-    assert!(parser.parse("let enum A = B | C").is_err());
+    // Spans of the declaration, requirements and items
+    assert!(
+        parser
+            .parse(
+                0,
+                Lexer::new(0, "sig S requires T { val v: int, method m: int }")
+            )
+            .unwrap()
+            == InterfaceDecl {
+                name: ("S".to_string(), Span::new(0, 4, 5)),
+                type_args: vec![],
+                requires: vec![("T".to_string(), Span::new(0, 15, 16), vec![])],
+                impl_methods: vec![],
+                spec_methods: vec![("m".to_string(), Type::Int, Span::new(0, 31, 44))],
+                values: vec![("v".to_string(), Type::Int, Span::new(0, 19, 29))],
+                span: Span::new(0, 0, 46),
+            }
+    );
+
+    assert!(matches!(
+        parser.parse(0, Lexer::new(0, "sig S { method m: int, method m x = x }")).err().unwrap(),
+        ParseError::User { error: (e, s) }
+        if e.contains("Duplicate method m") && s == Span::new(0, 23, 37)
+    ));
+    assert!(matches!(
+        parser.parse(0, Lexer::new(0, "sig S { val v: int, val v: float }")).err().unwrap(),
+        ParseError::User { error: (e, s) }
+        if e.contains("Duplicate value v") && s == Span::new(0, 20, 32)
+    ));
+    assert!(parser.parse(0, Lexer::new(0, "sig S")).is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "sig { val v: int }"))
+        .is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "sig S requires { val v: int }"))
+        .is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "sig S requires A + { val v: int }"))
+        .is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "sig S requires A B { val v: int }"))
+        .is_err());
+    assert!(parser.parse(0, Lexer::new(0, "sig S { val v }")).is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "sig S { val v = 4 }"))
+        .is_err());
+    assert!(parser.parse(0, Lexer::new(0, "sig S { v: int }")).is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "sig S { method m: int method n: int }"))
+        .is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "sig S { method: int }"))
+        .is_err());
+    assert!(matches!(
+        parser.parse(0, Lexer::new(0, "sig S { method m x }")).err().unwrap(),
+        ParseError::User { error: (e, s) }
+        if e.contains("requires either a type or an implementation") && s == Span::new(0, 15, 18)
+    ));
+    assert!(parser
+        .parse(0, Lexer::new(0, "sig S 'a { val v: 'a }"))
+        .is_err());
+}
+
+fn parse_impl_block(parser: &grammar::StatementParser, inp: &'static str) -> ast::Statement {
+    let out = parser.parse(0, Lexer::new(0, inp)).unwrap().unspanned();
+    if let InterfaceImpl { .. } = out {
+        out
+    } else {
+        panic!("Input is not an impl block.")
+    }
+}
+
+#[test]
+fn test_parse_impl_block() {
+    let parser = grammar::StatementParser::new();
+
+    assert!(
+        parse_impl_block(&parser, "impl Matrix { method size = 4 }")
+            == InterfaceImpl {
+                for_struct: ("Matrix".to_string(), Span::dummy(), vec![]),
+                impl_interface: None,
+                attr_sets: vec![],
+                method_impls: vec![(
+                    VarWithValue {
+                        name: ("size".to_string(), Span::dummy()),
+                        args: vec![],
+                        output_type: None,
+                        rec: false,
+                        expr: Box::new(Expression::IntLiteral(4, Span::dummy())),
+                    },
+                    Span::dummy()
+                )],
+                span: Span::dummy(),
+            }
+    );
+
+    assert!(
+        parse_impl_block(&parser, "impl 'a Matrix: Shl {}")
+            == InterfaceImpl {
+                for_struct: (
+                    "Matrix".to_string(),
+                    Span::dummy(),
+                    vec![PolytypeVar {
+                        name: "a".to_string(),
+                        bound: None,
+                        span: Span::dummy(),
+                    }]
+                ),
+                impl_interface: Some(("Shl".to_string(), Span::dummy(), vec![])),
+                attr_sets: vec![],
+                method_impls: vec![],
+                span: Span::dummy(),
+            }
+    );
+
+    assert!(
+        parse_impl_block(
+            &parser,
+            "impl Num'a Counter: 'a Incr {
+        set self.count = 0,
+        method incr by = self.count + by,
+        method get = self.count,
+    }"
+        ) == InterfaceImpl {
+            for_struct: (
+                "Counter".to_string(),
+                Span::dummy(),
+                vec![PolytypeVar {
+                    name: "a".to_string(),
+                    bound: Some("Num".to_string()),
+                    span: Span::dummy(),
+                }]
+            ),
+            impl_interface: Some((
+                "Incr".to_string(),
+                Span::dummy(),
+                vec![PolytypeVar {
+                    name: "a".to_string(),
+                    bound: None,
+                    span: Span::dummy(),
+                }]
+            )),
+            attr_sets: vec![(
+                AttrSet {
+                    entity: ("self".to_string(), Span::dummy()),
+                    attr: ("count".to_string(), Span::dummy()),
+                    new_expr: Box::new(Expression::IntLiteral(0, Span::dummy())),
+                },
+                Span::dummy()
+            )],
+            method_impls: vec![
+                (
+                    VarWithValue {
+                        name: ("incr".to_string(), Span::dummy()),
+                        args: vec![("by".to_string(), None, Span::dummy())],
+                        output_type: None,
+                        rec: false,
+                        expr: Box::new(Expression::FuncApplication(
+                            Box::new(Expression::BinaryOp(ast::BinaryOp::Add, Span::dummy())),
+                            vec![
+                                Expression::Projection(
+                                    Box::new(Expression::Identifier(
+                                        "self".to_string(),
+                                        Span::dummy()
+                                    )),
+                                    "count".to_string(),
+                                    Span::dummy()
+                                ),
+                                Expression::Identifier("by".to_string(), Span::dummy())
+                            ],
+                            Span::dummy()
+                        )),
+                    },
+                    Span::dummy()
+                ),
+                (
+                    VarWithValue {
+                        name: ("get".to_string(), Span::dummy()),
+                        args: vec![],
+                        output_type: None,
+                        rec: false,
+                        expr: Box::new(Expression::Projection(
+                            Box::new(Expression::Identifier("self".to_string(), Span::dummy())),
+                            "count".to_string(),
+                            Span::dummy()
+                        )),
+                    },
+                    Span::dummy()
+                ),
+            ],
+            span: Span::dummy(),
+        }
+    );
+
+    // Spans of the block, its header and every item
+    assert!(
+        parser
+            .parse(
+                0,
+                Lexer::new(0, "impl A: B { set self.x = 1, method m = 2 }")
+            )
+            .unwrap()
+            == InterfaceImpl {
+                for_struct: ("A".to_string(), Span::new(0, 5, 6), vec![]),
+                impl_interface: Some(("B".to_string(), Span::new(0, 8, 9), vec![])),
+                attr_sets: vec![(
+                    AttrSet {
+                        entity: ("self".to_string(), Span::new(0, 16, 20)),
+                        attr: ("x".to_string(), Span::new(0, 21, 22)),
+                        new_expr: Box::new(Expression::IntLiteral(1, Span::new(0, 25, 26))),
+                    },
+                    Span::new(0, 12, 26)
+                )],
+                method_impls: vec![(
+                    VarWithValue {
+                        name: ("m".to_string(), Span::new(0, 35, 36)),
+                        args: vec![],
+                        output_type: None,
+                        rec: false,
+                        expr: Box::new(Expression::IntLiteral(2, Span::new(0, 39, 40))),
+                    },
+                    Span::new(0, 28, 40)
+                )],
+                span: Span::new(0, 0, 42),
+            }
+    );
+
+    assert!(matches!(
+        parser.parse(0, Lexer::new(0, "impl A { method m = 1, method m x = x }")).err().unwrap(),
+        ParseError::User { error: (e, s) }
+        if e.contains("Duplicate method m") && s == Span::new(0, 23, 37)
+    ));
+    assert!(parser
+        .parse(0, Lexer::new(0, "impl { method m = 1 }"))
+        .is_err());
+    assert!(parser.parse(0, Lexer::new(0, "impl A")).is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "impl A: { method m = 1 }"))
+        .is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "impl A: B C { method m = 1 }"))
+        .is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "impl A { method m: int }"))
+        .is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "impl A { val v: int }"))
+        .is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "impl A { set x = 4 }"))
+        .is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "impl A { set self.x }"))
+        .is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "impl A { method m = 1 method n = 2 }"))
+        .is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "impl A 'a { method m = 1 }"))
+        .is_err());
 }
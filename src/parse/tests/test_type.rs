@@ -1,3 +1,4 @@
+use super::lexer::Lexer;
 use super::*;
 use crate::types::structure::{Flex, Structure};
 use crate::types::Type::*;
@@ -6,46 +7,58 @@ use std::collections::BTreeMap;
 #[test]
 fn test_parse_literal() {
     let parser = grammar::TypeParser::new();
-    assert!(parser.parse("none").unwrap() == None);
-    assert!(parser.parse("int").unwrap() == Int);
-    assert!(parser.parse("float").unwrap() == Float);
-    assert!(parser.parse("string").unwrap() == String);
-    assert!(parser.parse("iNT").unwrap() == TypeId("iNT".to_string(), vec![]));
-    assert!(parser.parse("_x").unwrap() == TypeId("_x".to_string(), vec![]));
-    assert!(parser.parse("flot").unwrap() == TypeId("flot".to_string(), vec![]));
-    assert!(parser.parse("(int)").unwrap() == Int);
-    assert!(parser.parse("(xello)").unwrap() == TypeId("xello".to_string(), vec![]));
+    assert!(parser.parse(0, Lexer::new(0, "none")).unwrap() == None);
+    assert!(parser.parse(0, Lexer::new(0, "int")).unwrap() == Int);
+    assert!(parser.parse(0, Lexer::new(0, "float")).unwrap() == Float);
+    assert!(parser.parse(0, Lexer::new(0, "string")).unwrap() == String);
+    assert!(parser.parse(0, Lexer::new(0, "iNT")).unwrap() == TypeId("iNT".to_string(), vec![]));
+    assert!(parser.parse(0, Lexer::new(0, "_x")).unwrap() == TypeId("_x".to_string(), vec![]));
+    assert!(parser.parse(0, Lexer::new(0, "flot")).unwrap() == TypeId("flot".to_string(), vec![]));
+    assert!(parser.parse(0, Lexer::new(0, "(int)")).unwrap() == Int);
+    assert!(
+        parser.parse(0, Lexer::new(0, "(xello)")).unwrap() == TypeId("xello".to_string(), vec![])
+    );
     // Err literal type
-    assert!(parser.parse("Un[").is_err());
-    assert!(parser.parse("()").is_err());
-    assert!(parser.parse("8").is_err());
-    assert!(parser.parse("\"hello\"").is_err());
-    assert!(parser.parse("x:int").is_err());
-    assert!(parser.parse("nothing").is_err());
+    assert!(parser.parse(0, Lexer::new(0, "Un[")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "()")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "8")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "\"hello\"")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "x:int")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "nothing")).is_err());
 }
 
 #[test]
 fn test_parse_list_tuple_type() {
     let parser = grammar::TypeParser::new();
-    assert!(parser.parse("[int]").unwrap() == List(Box::new(Int)));
+    assert!(parser.parse(0, Lexer::new(0, "[int]")).unwrap() == List(Box::new(Int)));
     assert!(
-        parser.parse("[Option]").unwrap() == List(Box::new(TypeId("Option".to_string(), vec![])))
+        parser.parse(0, Lexer::new(0, "[Option]")).unwrap()
+            == List(Box::new(TypeId("Option".to_string(), vec![])))
     );
-    assert!(parser.parse("[(int)]").unwrap() == List(Box::new(Int)));
+    assert!(parser.parse(0, Lexer::new(0, "[(int)]")).unwrap() == List(Box::new(Int)));
     assert!(
-        parser.parse("[(int, Option)]").unwrap()
+        parser.parse(0, Lexer::new(0, "[(int, Option)]")).unwrap()
             == List(Box::new(Tuple(vec![
                 Int,
                 TypeId("Option".to_string(), vec![])
             ])))
     );
-    assert!(parser.parse("[[int]]").unwrap() == List(Box::new(List(Box::new(Int)))));
-    assert!(parser.parse("[int").is_err());
-    assert!(parser.parse("[int, string]").is_err());
-    assert!(parser.parse("hel]o").is_err());
-    assert!(parser.parse("(int, string, float)").unwrap() == Tuple(vec![Int, String, Float]));
     assert!(
-        parser.parse("(none, int, Option, string)").unwrap()
+        parser.parse(0, Lexer::new(0, "[[int]]")).unwrap() == List(Box::new(List(Box::new(Int))))
+    );
+    assert!(parser.parse(0, Lexer::new(0, "[int")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "[int, string]")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "hel]o")).is_err());
+    assert!(
+        parser
+            .parse(0, Lexer::new(0, "(int, string, float)"))
+            .unwrap()
+            == Tuple(vec![Int, String, Float])
+    );
+    assert!(
+        parser
+            .parse(0, Lexer::new(0, "(none, int, Option, string)"))
+            .unwrap()
             == Tuple(vec![
                 None,
                 Int,
@@ -53,52 +66,70 @@ fn test_parse_list_tuple_type() {
                 String
             ])
     );
-    assert!(parser.parse("()").is_err());
-    assert!(parser.parse("int, string)").is_err());
-    assert!(parser.parse("int, string, 'a").is_err());
-    assert!(parser.parse("hi(, there").is_err());
-    assert!(parser.parse("(xello, int, stri)ng, )").is_err());
-    assert!(parser.parse("int string").is_err());
+    assert!(parser.parse(0, Lexer::new(0, "()")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "int, string)")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "int, string, 'a")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "hi(, there")).is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "(xello, int, stri)ng, )"))
+        .is_err());
+    assert!(parser.parse(0, Lexer::new(0, "int string")).is_err());
 }
 
 #[test]
 fn test_parse_polymorphic_type() {
     let parser = grammar::TypeParser::new();
 
-    assert!(parser.parse("'a").unwrap() == Poly("a".to_string(), Option::None));
+    assert!(parser.parse(0, Lexer::new(0, "'a")).unwrap() == Poly("a".to_string(), Option::None));
 
-    assert!(parser.parse("'_yusdf").unwrap() == Poly("_yusdf".to_string(), Option::None));
-    assert!(parser.parse("'aAbB").unwrap() == Poly("aAbB".to_string(), Option::None));
-    assert!(parser.parse("'a_1").unwrap() == Poly("a_1".to_string(), Option::None));
-    assert!(parser.parse("'Type").unwrap() == Poly("Type".to_string(), Option::None));
-    assert!(parser.parse("'___type").unwrap() == Poly("___type".to_string(), Option::None));
+    assert!(
+        parser.parse(0, Lexer::new(0, "'_yusdf")).unwrap()
+            == Poly("_yusdf".to_string(), Option::None)
+    );
+    assert!(
+        parser.parse(0, Lexer::new(0, "'aAbB")).unwrap() == Poly("aAbB".to_string(), Option::None)
+    );
+    assert!(
+        parser.parse(0, Lexer::new(0, "'a_1")).unwrap() == Poly("a_1".to_string(), Option::None)
+    );
+    assert!(
+        parser.parse(0, Lexer::new(0, "'Type")).unwrap() == Poly("Type".to_string(), Option::None)
+    );
+    assert!(
+        parser.parse(0, Lexer::new(0, "'___type")).unwrap()
+            == Poly("___type".to_string(), Option::None)
+    );
 
     // Bounded types
     assert!(
-        parser.parse("Bounded'a").unwrap() == Poly("a".to_string(), Some("Bounded".to_string()))
+        parser.parse(0, Lexer::new(0, "Bounded'a")).unwrap()
+            == Poly("a".to_string(), Some("Bounded".to_string()))
     );
-    assert!(parser.parse("__r9'a").unwrap() == Poly("a".to_string(), Some("__r9".to_string())));
-    assert!(parser.parse("95'a").is_err());
+    assert!(
+        parser.parse(0, Lexer::new(0, "__r9'a")).unwrap()
+            == Poly("a".to_string(), Some("__r9".to_string()))
+    );
+    assert!(parser.parse(0, Lexer::new(0, "95'a")).is_err());
 
-    assert!(parser.parse("' tee").is_err());
-    assert!(parser.parse("bound 'a").is_err());
-    assert!(parser.parse(";df").is_err());
-    assert!(parser.parse("'hello'").is_err());
-    assert!(parser.parse("''").is_err());
-    assert!(parser.parse("'95x").is_err());
+    assert!(parser.parse(0, Lexer::new(0, "' tee")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "bound 'a")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, ";df")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "'hello'")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "''")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "'95x")).is_err());
 }
 
 #[test]
 fn test_parse_type_identifier() {
     let parser = grammar::TypeParser::new();
 
-    assert!(parser.parse("X").unwrap() == TypeId("X".to_string(), vec![]));
+    assert!(parser.parse(0, Lexer::new(0, "X")).unwrap() == TypeId("X".to_string(), vec![]));
     assert!(
-        parser.parse("'a Y").unwrap()
+        parser.parse(0, Lexer::new(0, "'a Y")).unwrap()
             == TypeId("Y".to_string(), vec![Poly("a".to_string(), Option::None)])
     );
     assert!(
-        parser.parse("'a 'b X").unwrap()
+        parser.parse(0, Lexer::new(0, "'a 'b X")).unwrap()
             == TypeId(
                 "X".to_string(),
                 vec![
@@ -108,7 +139,7 @@ fn test_parse_type_identifier() {
             )
     );
     assert!(
-        parser.parse("'a z'b X").unwrap()
+        parser.parse(0, Lexer::new(0, "'a z'b X")).unwrap()
             == TypeId(
                 "X".to_string(),
                 vec![
@@ -118,7 +149,7 @@ fn test_parse_type_identifier() {
             )
     );
     assert!(
-        parser.parse("'a X 'b Y").unwrap()
+        parser.parse(0, Lexer::new(0, "'a X 'b Y")).unwrap()
             == TypeId(
                 "Y".to_string(),
                 vec![
@@ -130,7 +161,7 @@ fn test_parse_type_identifier() {
     );
 
     assert!(
-        parser.parse("Num'a Matrix").unwrap()
+        parser.parse(0, Lexer::new(0, "Num'a Matrix")).unwrap()
             == TypeId(
                 "Matrix".to_string(),
                 vec![Poly("a".to_string(), Some("Num".to_string()))]
@@ -138,7 +169,9 @@ fn test_parse_type_identifier() {
     );
 
     assert!(
-        parser.parse("Num'a Matrix Option").unwrap()
+        parser
+            .parse(0, Lexer::new(0, "Num'a Matrix Option"))
+            .unwrap()
             == TypeId(
                 "Option".to_string(),
                 vec![
@@ -148,7 +181,9 @@ fn test_parse_type_identifier() {
             )
     );
     assert!(
-        parser.parse("(Num'a Matrix) Option").unwrap()
+        parser
+            .parse(0, Lexer::new(0, "(Num'a Matrix) Option"))
+            .unwrap()
             == TypeId(
                 "Option".to_string(),
                 vec![TypeId(
@@ -158,19 +193,21 @@ fn test_parse_type_identifier() {
             )
     );
     assert!(
-        parser.parse("A B").unwrap()
+        parser.parse(0, Lexer::new(0, "A B")).unwrap()
             == TypeId("B".to_string(), vec![TypeId("A".to_string(), vec![])])
     );
-    assert!(parser.parse("int X").unwrap() == TypeId("X".to_string(), vec![Int]));
+    assert!(parser.parse(0, Lexer::new(0, "int X")).unwrap() == TypeId("X".to_string(), vec![Int]));
     assert!(
-        parser.parse("Tree float X").unwrap()
+        parser.parse(0, Lexer::new(0, "Tree float X")).unwrap()
             == TypeId(
                 "X".to_string(),
                 vec![TypeId("Tree".to_string(), vec![]), Float]
             )
     );
     assert!(
-        parser.parse("((float Tree) Tree) X").unwrap()
+        parser
+            .parse(0, Lexer::new(0, "((float Tree) Tree) X"))
+            .unwrap()
             == TypeId(
                 "X".to_string(),
                 vec![TypeId(
@@ -179,28 +216,32 @@ fn test_parse_type_identifier() {
                 )]
             )
     );
-    assert!(parser.parse("(int) X").unwrap() == TypeId("X".to_string(), vec![Int]));
     assert!(
-        parser.parse("[int] Matrix").unwrap()
+        parser.parse(0, Lexer::new(0, "(int) X")).unwrap() == TypeId("X".to_string(), vec![Int])
+    );
+    assert!(
+        parser.parse(0, Lexer::new(0, "[int] Matrix")).unwrap()
             == TypeId("Matrix".to_string(), vec![List(Box::new(Int))])
     );
     assert!(
-        parser.parse("([int], float, none) X").unwrap()
+        parser
+            .parse(0, Lexer::new(0, "([int], float, none) X"))
+            .unwrap()
             == TypeId(
                 "X".to_string(),
                 vec![Tuple(vec![List(Box::new(Int)), Float, None])]
             )
     );
 
-    assert!(parser.parse("X [int]").is_err());
-    assert!(parser.parse("Y 'a").is_err());
-    assert!(parser.parse("'a 9").is_err());
-    assert!(parser.parse("92__'a X").is_err());
-    assert!(parser.parse("\"hi\" Option").is_err());
-    assert!(parser.parse("(X Y").is_err());
-    assert!(parser.parse("int float").is_err());
-    assert!(parser.parse("int [y]").is_err());
-    assert!(parser.parse("X with int").is_err());
+    assert!(parser.parse(0, Lexer::new(0, "X [int]")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "Y 'a")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "'a 9")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "92__'a X")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "\"hi\" Option")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "(X Y")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "int float")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "int [y]")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "X with int")).is_err());
 }
 
 // TODO: convert these tests to tests on typed let = nothing expressions
@@ -209,23 +250,28 @@ fn test_parse_type_identifier() {
 fn test_parse_function_type() {
     let parser = grammar::TypeParser::new();
 
-    assert!(parser.parse("int -> float").unwrap() == Function(Box::new(Int), Box::new(Float),));
     assert!(
-        parser.parse("X -> Y").unwrap()
+        parser.parse(0, Lexer::new(0, "int -> float")).unwrap()
+            == Function(Box::new(Int), Box::new(Float),)
+    );
+    assert!(
+        parser.parse(0, Lexer::new(0, "X -> Y")).unwrap()
             == Function(
                 Box::new(TypeId("X".to_string(), vec![])),
                 Box::new(TypeId("Y".to_string(), vec![]))
             )
     );
     assert!(
-        parser.parse("int -> none -> string").unwrap()
+        parser
+            .parse(0, Lexer::new(0, "int -> none -> string"))
+            .unwrap()
             == Function(
                 Box::new(Int),
                 Box::new(Function(Box::new(None), Box::new(String),))
             )
     );
     assert!(
-        parser.parse("'a Tree -> int").unwrap()
+        parser.parse(0, Lexer::new(0, "'a Tree -> int")).unwrap()
             == Function(
                 Box::new(TypeId(
                     "Tree".to_string(),
@@ -234,17 +280,27 @@ fn test_parse_function_type() {
                 Box::new(Int)
             )
     );
-    assert!(parser.parse("(int) -> (float)").unwrap() == Function(Box::new(Int), Box::new(Float),));
-    assert!(parser.parse("(int -> float)").unwrap() == Function(Box::new(Int), Box::new(Float),));
     assert!(
-        parser.parse("(int -> float) -> string").unwrap()
+        parser.parse(0, Lexer::new(0, "(int) -> (float)")).unwrap()
+            == Function(Box::new(Int), Box::new(Float),)
+    );
+    assert!(
+        parser.parse(0, Lexer::new(0, "(int -> float)")).unwrap()
+            == Function(Box::new(Int), Box::new(Float),)
+    );
+    assert!(
+        parser
+            .parse(0, Lexer::new(0, "(int -> float) -> string"))
+            .unwrap()
             == Function(
                 Box::new(Function(Box::new(Int), Box::new(Float),)),
                 Box::new(String)
             )
     );
     assert!(
-        parser.parse("int->   float   ->string ->Option").unwrap()
+        parser
+            .parse(0, Lexer::new(0, "int->   float   ->string ->Option"))
+            .unwrap()
             == Function(
                 Box::new(Int),
                 Box::new(Function(
@@ -257,7 +313,7 @@ fn test_parse_function_type() {
             )
     );
     assert!(
-        parser.parse("'a -> 'b -> c").unwrap()
+        parser.parse(0, Lexer::new(0, "'a -> 'b -> c")).unwrap()
             == Function(
                 Box::new(Poly("a".to_string(), Option::None)),
                 Box::new(Function(
@@ -267,7 +323,9 @@ fn test_parse_function_type() {
             )
     );
     assert!(
-        parser.parse("int -> (float -> string) -> none").unwrap()
+        parser
+            .parse(0, Lexer::new(0, "int -> (float -> string) -> none"))
+            .unwrap()
             == Function(
                 Box::new(Int),
                 Box::new(Function(
@@ -277,7 +335,9 @@ fn test_parse_function_type() {
             )
     );
     assert!(
-        parser.parse("int -> Num'a Option -> string").unwrap()
+        parser
+            .parse(0, Lexer::new(0, "int -> Num'a Option -> string"))
+            .unwrap()
             == Function(
                 Box::new(Int),
                 Box::new(Function(
@@ -290,7 +350,9 @@ fn test_parse_function_type() {
             )
     );
     assert!(
-        parser.parse("'a -> ('a -> 'a) -> 'a").unwrap()
+        parser
+            .parse(0, Lexer::new(0, "'a -> ('a -> 'a) -> 'a"))
+            .unwrap()
             == Function(
                 Box::new(Poly("a".to_string(), Option::None)),
                 Box::new(Function(
@@ -303,13 +365,15 @@ fn test_parse_function_type() {
             )
     );
 
-    assert!(parser.parse("int (->) float").is_err());
-    assert!(parser.parse("int -> 7").is_err());
-    assert!(parser.parse("int -> string ->").is_err());
-    assert!(parser.parse("int - > float").is_err());
-    assert!(parser.parse("(a) -> (4)").is_err());
-    assert!(parser.parse("Option 'a -> int").is_err());
-    assert!(parser.parse("(x: int) -> (y: float)").is_err());
+    assert!(parser.parse(0, Lexer::new(0, "int (->) float")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "int -> 7")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "int -> string ->")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "int - > float")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "(a) -> (4)")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "Option 'a -> int")).is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "(x: int) -> (y: float)"))
+        .is_err());
 }
 
 #[test]
@@ -317,7 +381,7 @@ fn test_parse_record_type() {
     let parser = grammar::TypeParser::new();
 
     assert!(
-        parser.parse("{a: int}").unwrap()
+        parser.parse(0, Lexer::new(0, "{a: int}")).unwrap()
             == Record(Structure {
                 methods: BTreeMap::new(),
                 values: BTreeMap::from([("a".to_string(), Int)]),
@@ -325,7 +389,7 @@ fn test_parse_record_type() {
             })
     );
     assert!(
-        parser.parse("{a: float, }").unwrap()
+        parser.parse(0, Lexer::new(0, "{a: float, }")).unwrap()
             == Record(Structure {
                 methods: BTreeMap::new(),
                 values: BTreeMap::from([("a".to_string(), Float)]),
@@ -351,11 +415,15 @@ fn test_parse_record_type() {
     assert!(
         parser
             .parse(
-                "{
+                0,
+                Lexer::new(
+                    0,
+                    "{
         a: int,
         b: 'a Option,
         c: [int]
     }"
+                )
             )
             .unwrap()
             == optional_ending_comma_expected
@@ -363,11 +431,15 @@ fn test_parse_record_type() {
     assert!(
         parser
             .parse(
-                "{
+                0,
+                Lexer::new(
+                    0,
+                    "{
         a: int,
         b: 'a Option,
         c: [int],
     }"
+                )
             )
             .unwrap()
             == optional_ending_comma_expected
@@ -375,15 +447,22 @@ fn test_parse_record_type() {
 
     assert!(parser
         .parse(
-            "{
+            0,
+            Lexer::new(
+                0,
+                "{
         a: int b: float
     }"
+            )
         )
         .is_err());
     assert!(
         parser
             .parse(
-                "{|
+                0,
+                Lexer::new(
+                    0,
+                    "{|
         mem1: float,
         mem2: ({
             a: Three,
@@ -392,6 +471,7 @@ fn test_parse_record_type() {
         mem4: [int],
         mem3: {| y: (int, float, none, { method u: int }) |}
     |}"
+                )
             )
             .unwrap()
             == Record(Structure {
@@ -437,11 +517,15 @@ fn test_parse_record_type() {
     assert!(
         parser
             .parse(
-                "{|
+                0,
+                Lexer::new(
+                    0,
+                    "{|
         method a: int,
         method b: int -> int,
         u: float -> { u: string }
     |}"
+                )
             )
             .unwrap()
             == Record(Structure {
@@ -464,14 +548,16 @@ fn test_parse_record_type() {
             })
     );
 
-    assert!(parser.parse("{4: int}").is_err());
-    assert!(parser.parse("{}").is_err());
-    assert!(parser.parse("{||}").is_err());
-    assert!(parser.parse("{a: [4]}").is_err());
-    assert!(parser.parse("{one: 2}").is_err());
-    assert!(parser.parse("{int: a}").is_err());
-    assert!(parser.parse("a: int").is_err());
-    assert!(parser.parse("{a: int},").is_err());
-    assert!(parser.parse("{int}").is_err());
-    assert!(parser.parse("{a: int, a: float}").is_err());
+    assert!(parser.parse(0, Lexer::new(0, "{4: int}")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "{}")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "{||}")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "{a: [4]}")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "{one: 2}")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "{int: a}")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "a: int")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "{a: int},")).is_err());
+    assert!(parser.parse(0, Lexer::new(0, "{int}")).is_err());
+    assert!(parser
+        .parse(0, Lexer::new(0, "{a: int, a: float}"))
+        .is_err());
 }
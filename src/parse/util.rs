@@ -1,5 +1,8 @@
 use super::ast;
-use super::span::Span;
+use super::lexer::Token;
+use super::span::{FileId, Span};
+use crate::types::Type;
+use lalrpop_util::{ErrorRecovery, ParseError};
 
 pub type OptionBox<T> = Option<Box<T>>;
 
@@ -13,10 +16,7 @@ pub fn spans_overlap(spans: &Vec<Span>) -> Result<(), Span> {
         let cur_end = spans[i].end;
         let next_start = spans[i + 1].start;
         if next_start <= cur_end {
-            return Err(Span {
-                start: spans[i].start,
-                end: spans[i + 1].end,
-            });
+            return Err(spans[i].to(&spans[i + 1]));
         }
     }
 
@@ -27,13 +27,14 @@ pub fn spans_overlap(spans: &Vec<Span>) -> Result<(), Span> {
 pub fn flatten_projection(expr: &ast::Expression) -> Result<Vec<ast::Expression>, String> {
     match &expr {
         ast::Expression::Identifier(_, _) => Ok(vec![expr.clone()]),
-        ast::Expression::Projection(p, id, _) => {
+        ast::Expression::Projection(p, id, span) => {
             let prior_projections = flatten_projection(&p);
             if prior_projections.is_err() {
                 return prior_projections;
             }
             let mut projections = prior_projections.unwrap();
-            projections.push(ast::Expression::Identifier(id.clone(), None));
+            let id_span = Span::new(span.file, span.end - id.len(), span.end);
+            projections.push(ast::Expression::Identifier(id.clone(), id_span));
             Ok(projections)
         }
         // TODO(WYE-9): Use String for errors
@@ -47,3 +48,99 @@ pub enum RecordMemberness {
     Value,
     Method,
 }
+
+/// An item declared in the body of a `sig`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SigItem {
+    Value(String, Type),
+    SpecMethod(String, Type),
+    ImplMethod(ast::VarWithValue),
+}
+
+/// An item in the body of an `impl` block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImplItem {
+    AttrSet(ast::AttrSet),
+    Method(ast::VarWithValue),
+}
+
+/// Describe a terminal the parser expected in terms a user would recognize,
+/// rather than by its name in the grammar.
+fn describe_terminal(terminal: &str) -> String {
+    match terminal {
+        "Id" => "identifier".to_string(),
+        "Int" => "integer literal".to_string(),
+        "Float" => "float literal".to_string(),
+        "Str" => "string literal".to_string(),
+        "TypeVar" | "BoundedTypeVar" => "type variable".to_string(),
+        "Op" => "operator".to_string(),
+        _ => format!("`{}`", terminal.trim_matches('"').replace("\\\\", "\\")),
+    }
+}
+
+fn describe_expected(expected: &[String]) -> String {
+    let mut descriptions: Vec<String> = vec![];
+    for desc in expected.iter().map(String::as_str).map(describe_terminal) {
+        if !descriptions.contains(&desc) {
+            descriptions.push(desc);
+        }
+    }
+    descriptions.join(", ")
+}
+
+/// Describe a syntax error with a message and the span of input it concerns.
+pub fn describe_parse_error(
+    file: FileId,
+    error: ParseError<usize, Token, (String, Span)>,
+) -> (String, Span) {
+    match error {
+        // Input the lexer could not make sense of
+        ParseError::UnrecognizedToken {
+            token: (start, Token::Error(msg), end),
+            ..
+        } => (msg, Span::new(file, start, end)),
+        ParseError::InvalidToken { location } => (
+            "Invalid token".to_string(),
+            Span::new(file, location, location),
+        ),
+        ParseError::UnrecognizedEof { location, expected } => (
+            format!(
+                "Unexpected end of input, expected one of {}",
+                describe_expected(&expected)
+            ),
+            Span::new(file, location, location),
+        ),
+        ParseError::UnrecognizedToken {
+            token: (start, tok, end),
+            expected,
+        } => (
+            format!(
+                "Unexpected token `{}`, expected one of {}",
+                tok,
+                describe_expected(&expected)
+            ),
+            Span::new(file, start, end),
+        ),
+        ParseError::ExtraToken {
+            token: (start, tok, end),
+        } => (
+            format!("Extra token `{}`", tok),
+            Span::new(file, start, end),
+        ),
+        ParseError::User { error } => error,
+    }
+}
+
+/// Describe a syntax error the parser recovered from. The span extends over
+/// every token skipped during recovery.
+pub fn describe_recovered_error(
+    file: FileId,
+    recovery: ErrorRecovery<usize, Token, (String, Span)>,
+) -> (String, Span) {
+    let (msg, span) = describe_parse_error(file, recovery.error);
+    let end = recovery
+        .dropped_tokens
+        .last()
+        .map_or(span.end, |(_, _, end)| usize::max(*end, span.end));
+    (msg, Span::new(file, span.start, end))
+}
@@ -1,11 +1,14 @@
 //! Type checking
+use super::env::TypeEnv;
 use super::infer;
-use super::{collect_functype, Type};
+use super::structure::Structure;
+use super::{collect_functype, Type, TypeScheme};
+use crate::module::{Module, ModuleError};
 use crate::parse::ast;
-use crate::parse::ast::{BinaryOp, Expression, Program, Statement};
+use crate::parse::ast::{BinaryOp, Expression, Pattern, Program, Statement};
 use crate::parse::span;
 use crate::parse::span::GetSpan;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
 // TODO: are constraints about type schemes? Perhaps that is
 // Something that will only be dealt with once we have working
@@ -17,35 +20,69 @@ use std::collections::{HashMap, HashSet};
 pub(super) struct TypeContext {
     /// Next unused number for generating a new type variable or bound name.
     next_available_num: usize,
-    /// name -> type mapping for variables declared in the program
-    pub typings: HashMap<String, Type>,
+    /// Types of the variables in scope
+    pub typings: TypeEnv,
     /// var name and name of bound
     quantified_typevars: HashMap<String, Option<String>>,
     /// Collect errors here to be all reported together after type checking
     /// This can be a vec of String by the way
     pub type_errors: HashMap<span::Span, String>,
-    /// Map from enumeration name to its variants and fields
-    enumerations: HashMap<String, HashSet<(String, Option<Type>)>>,
+    /// Problems that do not stop the program from being well typed, such as
+    /// a value discarded in a block
+    pub type_warnings: HashMap<span::Span, String>,
+    /// Map from enumeration name to its type parameters, and its variants and
+    /// fields
+    enumerations: HashMap<String, EnumVariants>,
     /// Map from variant name to enumeration name and fieldtype
     enum_variant_types: HashMap<String, (String, Option<Type>)>,
+    /// Map from type alias name to its type parameters and the type it abbreviates
+    type_aliases: HashMap<String, (Vec<String>, Type)>,
+    /// Map from struct name to its type parameters and the types of its members
+    structs: HashMap<String, StructTypes>,
+    /// Map from interface name to its type parameters and the types of the
+    /// values and methods it declares
+    interfaces: HashMap<String, (Vec<String>, HashMap<String, Type>)>,
+    /// Map from the name of each module available for import to its members
+    modules: HashMap<String, ModuleTypings>,
 }
 
+/// The names a module binds at its top level, along with their types.
+pub type ModuleTypings = HashMap<String, TypeScheme>;
+
+/// The type parameters of an enumeration, and its variants with the types
+/// of their fields.
+type EnumVariants = (Vec<String>, HashSet<(String, Option<Type>)>);
+
+/// The type parameters of a struct, and the types of its members in order.
+type StructTypes = (Vec<String>, Vec<(String, Type)>);
+
 impl TypeContext {
     pub fn new() -> Self {
         let mut out = Self {
             next_available_num: 0,
-            typings: HashMap::new(),
+            typings: TypeEnv::new(),
             quantified_typevars: HashMap::new(),
             type_errors: HashMap::new(),
+            type_warnings: HashMap::new(),
             enumerations: HashMap::new(),
             enum_variant_types: HashMap::new(),
+            type_aliases: HashMap::new(),
+            structs: HashMap::new(),
+            interfaces: HashMap::new(),
+            modules: HashMap::new(),
         };
 
         // Builtins
         out.define_enumeration(
             "bool".to_string(),
+            vec![],
             [("true".to_string(), None), ("false".to_string(), None)],
         );
+        // The variants of bool can be written without naming the enum
+        for variant in ["true", "false"] {
+            out.typings
+                .insert(variant.to_string(), TypeScheme::monotype(bool_type()));
+        }
 
         out
     }
@@ -60,35 +97,238 @@ impl TypeContext {
     /// Apply a set of type constraints to the names currently declared
     /// in the type context
     pub fn ingest_subst(&mut self, subst: &HashMap<usize, Type>) {
-        for typ in self.typings.values_mut() {
-            *typ = infer::apply_subst_type(subst, &typ);
+        for scheme in self.typings.values_mut() {
+            *scheme = infer::apply_subst_scheme(subst, scheme);
+        }
+    }
+
+    /// The type variables and polytypes that occur free in the types of the
+    /// names currently declared. A let cannot generalize over these, as they
+    /// stand for the same type wherever they occur.
+    fn free_in_typings(&self) -> (BTreeSet<usize>, Vec<String>) {
+        let mut free_vars = BTreeSet::new();
+        let mut free_polys = vec![];
+        for scheme in self.typings.values() {
+            let mut vars = BTreeSet::new();
+            infer::collect_type_vars(&scheme.typ, &mut vars);
+            free_vars.extend(vars.into_iter().filter(|var| !scheme.vars.contains(var)));
+            let mut polys = vec![];
+            collect_polytypes(&scheme.typ, &mut polys);
+            for poly in polys {
+                if !scheme.polys.contains(&poly) && !free_polys.contains(&poly) {
+                    free_polys.push(poly);
+                }
+            }
         }
+        (free_vars, free_polys)
+    }
+
+    /// Replace the quantified variables and polytypes of a scheme with fresh
+    /// type variables.
+    pub fn instantiate(&mut self, scheme: &TypeScheme) -> Type {
+        let var_subst = scheme
+            .vars
+            .iter()
+            .map(|var| (*var, Type::Variable(self.genvar())))
+            .collect();
+        let poly_subst = scheme
+            .polys
+            .iter()
+            .map(|poly| (poly.clone(), Type::Variable(self.genvar())))
+            .collect();
+        substitute_polytypes(
+            &infer::apply_subst_type(&var_subst, &scheme.typ),
+            &poly_subst,
+        )
     }
 
     /// Turn an enumeration definition into the relevant data structures
     /// that allow efficient analysis
-    fn define_enumeration<I>(&mut self, enumname: String, variants: I)
+    fn define_enumeration<I>(&mut self, enumname: String, params: Vec<String>, variants: I)
     where
         I: IntoIterator<Item = (String, Option<Type>)> + Clone,
     {
-        self.enumerations
-            .insert(enumname.clone(), HashSet::from_iter(variants.clone()));
+        self.enumerations.insert(
+            enumname.clone(),
+            (params, HashSet::from_iter(variants.clone())),
+        );
         for (varname, fieldtype) in variants {
             self.enum_variant_types
                 .insert(varname.clone(), (enumname.clone(), fieldtype.clone()));
         }
     }
+
+    /// The type of a value of an enumeration, with fresh type variables as its
+    /// type arguments, along with the type of the field of one of its variants
+    /// in terms of those variables.
+    fn instantiate_variant(
+        &mut self,
+        enumname: &str,
+        field: Option<&Type>,
+    ) -> (Type, Option<Type>) {
+        let params = self.enumerations[enumname].0.clone();
+        let type_args = params
+            .iter()
+            .map(|_| Type::Variable(self.genvar()))
+            .collect::<Vec<_>>();
+        let field = field.map(|typ| {
+            substitute_polytypes(typ, &params.into_iter().zip(type_args.clone()).collect())
+        });
+        (Type::TypeId(enumname.to_string(), type_args), field)
+    }
+
+    /// Whether a type of this name is declared, as an enumeration, a struct or
+    /// a type alias.
+    fn is_type_declared(&self, name: &str) -> bool {
+        self.enumerations.contains_key(name)
+            || self.structs.contains_key(name)
+            || self.type_aliases.contains_key(name)
+    }
+
+    /// Replace every use of a type alias within a type by the type it
+    /// abbreviates.
+    pub fn expand_type_aliases(&self, typ: &Type) -> Result<Type, String> {
+        self.expand_aliases_within(typ, &mut vec![])
+    }
+
+    /// Expand type aliases, keeping track of the aliases currently being
+    /// expanded in order to detect cycles.
+    fn expand_aliases_within(
+        &self,
+        typ: &Type,
+        expanding: &mut Vec<String>,
+    ) -> Result<Type, String> {
+        let expanded = match typ {
+            Type::TypeId(name, type_args) => {
+                let type_args = type_args
+                    .iter()
+                    .map(|t| self.expand_aliases_within(t, expanding))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let Some((params, aliased)) = self.type_aliases.get(name) else {
+                    return Ok(Type::TypeId(name.clone(), type_args));
+                };
+                if let Some(pos) = expanding.iter().position(|n| n == name) {
+                    let mut cycle = expanding[pos..].to_vec();
+                    cycle.push(name.clone());
+                    return Err(format!(
+                        "Type alias {} is cyclic: {}",
+                        name,
+                        cycle.join(" -> ")
+                    ));
+                }
+                if params.len() != type_args.len() {
+                    return Err(format!(
+                        "Type alias {} expects {} type argument(s) but got {}",
+                        name,
+                        params.len(),
+                        type_args.len()
+                    ));
+                }
+                let instantiated =
+                    substitute_polytypes(aliased, &params.iter().cloned().zip(type_args).collect());
+                expanding.push(name.clone());
+                let res = self.expand_aliases_within(&instantiated, expanding);
+                expanding.pop();
+                res?
+            }
+            Type::List(t) => Type::List(Box::new(self.expand_aliases_within(t, expanding)?)),
+            Type::Tuple(elem_types) => Type::Tuple(
+                elem_types
+                    .iter()
+                    .map(|t| self.expand_aliases_within(t, expanding))
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+            Type::Record(structure) => {
+                let mut structure = structure.clone();
+                for t in structure
+                    .values
+                    .values_mut()
+                    .chain(structure.methods.values_mut())
+                {
+                    *t = self.expand_aliases_within(t, expanding)?;
+                }
+                Type::Record(structure)
+            }
+            Type::Function(arg, ret) => Type::Function(
+                Box::new(self.expand_aliases_within(arg, expanding)?),
+                Box::new(self.expand_aliases_within(ret, expanding)?),
+            ),
+            _ => typ.clone(),
+        };
+        Ok(expanded)
+    }
 }
 
-pub fn type_check_program(prog: &Program) -> Result<(), HashMap<span::Span, String>> {
-    let mut ctx = TypeContext::new();
+/// Replace the named polytypes in a type with the given types.
+fn substitute_polytypes(typ: &Type, substs: &HashMap<String, Type>) -> Type {
+    let subst = |t: &Type| substitute_polytypes(t, substs);
+    match typ {
+        Type::Poly(name, _) if substs.contains_key(name) => substs.get(name).unwrap().clone(),
+        Type::TypeId(name, type_args) => {
+            Type::TypeId(name.clone(), type_args.iter().map(subst).collect())
+        }
+        Type::List(t) => Type::List(Box::new(subst(t))),
+        Type::Tuple(elem_types) => Type::Tuple(elem_types.iter().map(subst).collect()),
+        Type::Record(structure) => {
+            let mut structure = structure.clone();
+            for t in structure
+                .values
+                .values_mut()
+                .chain(structure.methods.values_mut())
+            {
+                *t = subst(t);
+            }
+            Type::Record(structure)
+        }
+        Type::Function(arg, ret) => Type::Function(Box::new(subst(arg)), Box::new(subst(ret))),
+        _ => typ.clone(),
+    }
+}
 
-    for stmt in prog {
-        let _ = type_check_statement(stmt, &mut ctx);
+/// Quantify a type over its type variables and polytypes, except for those
+/// free in the typings of the context the type was inferred in.
+fn generalize(typ: &Type, free_vars: &BTreeSet<usize>, free_polys: &[String]) -> TypeScheme {
+    let mut vars = BTreeSet::new();
+    infer::collect_type_vars(typ, &mut vars);
+    let mut polys = vec![];
+    collect_polytypes(typ, &mut polys);
+    TypeScheme {
+        vars: vars.difference(free_vars).copied().collect(),
+        polys: polys
+            .into_iter()
+            .filter(|poly| !free_polys.contains(poly))
+            .collect(),
+        typ: typ.clone(),
     }
+}
 
-    // TODO: at the end of this, all remaining unconstrained type variables
-    // need to be generalized to polytypes.
+/// Collect the names of the polytypes occurring in a type.
+fn collect_polytypes(typ: &Type, names: &mut Vec<String>) {
+    match typ {
+        Type::Poly(name, _) if !names.contains(name) => names.push(name.clone()),
+        Type::TypeId(_, types) | Type::Tuple(types) => {
+            for t in types {
+                collect_polytypes(t, names);
+            }
+        }
+        Type::List(t) => collect_polytypes(t, names),
+        Type::Record(structure) => {
+            for t in structure.values.values().chain(structure.methods.values()) {
+                collect_polytypes(t, names);
+            }
+        }
+        Type::Function(arg, ret) => {
+            collect_polytypes(arg, names);
+            collect_polytypes(ret, names);
+        }
+        _ => {}
+    }
+}
+
+pub fn type_check_program(prog: &Program) -> Result<(), HashMap<span::Span, String>> {
+    let mut ctx = TypeContext::new();
+    type_check_statements(prog, &mut ctx);
+    type_check_main(prog, &mut ctx);
 
     if (&ctx.type_errors).is_empty() {
         Ok(())
@@ -97,7 +337,98 @@ pub fn type_check_program(prog: &Program) -> Result<(), HashMap<span::Span, Stri
     }
 }
 
-fn type_check_statement(stmt: &Statement, ctx: &mut TypeContext) -> Result<(), ()> {
+/// Type check the modules of a program, which must be in dependency order, so
+/// that each module is checked against the members of the modules it imports.
+/// If there are no errors, the warnings of every module are returned.
+pub fn type_check_modules(modules: &[Module]) -> Result<Vec<ModuleError>, Vec<ModuleError>> {
+    let mut exports: Vec<ModuleTypings> = vec![];
+    let mut errors = vec![];
+    let mut warnings = vec![];
+    let mut next_available_num = 0;
+    for (index, module) in modules.iter().enumerate() {
+        let mut ctx = TypeContext::new();
+        // Type variables in the members of imported modules must not clash
+        // with the ones generated for this module
+        ctx.next_available_num = next_available_num;
+        for (name, imported) in &module.imports {
+            ctx.modules.insert(name.clone(), exports[*imported].clone());
+        }
+
+        exports.push(type_check_statements(&module.program, &mut ctx));
+        // Only the module that is run has its entry point called
+        if index == modules.len() - 1 {
+            type_check_main(&module.program, &mut ctx);
+        }
+        next_available_num = ctx.next_available_num;
+        errors.extend(ctx.type_errors.into_iter().map(|(span, msg)| (msg, span)));
+        warnings.extend(ctx.type_warnings.into_iter().map(|(span, msg)| (msg, span)));
+    }
+
+    if errors.is_empty() {
+        warnings.sort_by(|(_, span1), (_, span2)| span1.cmp(span2));
+        Ok(warnings)
+    } else {
+        errors.sort_by(|(_, span1), (_, span2)| span1.cmp(span2));
+        Err(errors)
+    }
+}
+
+/// Type check each statement of a program, returning the types of the names
+/// it binds at the top level.
+fn type_check_statements(prog: &Program, ctx: &mut TypeContext) -> ModuleTypings {
+    let mut top_level_names = vec![];
+    for stmt in prog {
+        let _ = type_check_statement(stmt, ctx);
+        match stmt {
+            Statement::Expression(Expression::Let(varwithval, None, _)) => {
+                top_level_names.push(varwithval.name.0.clone());
+            }
+            Statement::Expression(Expression::LetGroup(group, None, _)) => {
+                top_level_names.extend(group.iter().map(|varwithval| varwithval.name.0.clone()));
+            }
+            _ => {}
+        }
+    }
+
+    top_level_names
+        .into_iter()
+        .filter_map(|name| {
+            let scheme = ctx.typings.get(&name)?.clone();
+            Some((name, scheme))
+        })
+        .collect()
+}
+
+/// Check that the entry point of a program, if it has one, takes the
+/// command-line arguments and returns an exit code.
+fn type_check_main(prog: &Program, ctx: &mut TypeContext) {
+    let Some(varwithval) = ast::main_binding(prog) else {
+        return;
+    };
+    // A main that failed to type check has already been reported
+    let Some(main_scheme) = ctx.typings.get(ast::MAIN).cloned() else {
+        return;
+    };
+    let main_type = ctx.instantiate(&main_scheme);
+    let expected = Type::Function(
+        Box::new(Type::List(Box::new(Type::String))),
+        Box::new(Type::Int),
+    );
+    let mut unif_subst = HashMap::new();
+    if let Err(e) = infer::unify(&expected, &main_type, &mut unif_subst) {
+        ctx.type_errors.insert(
+            varwithval.name.1.clone(),
+            format!(
+                "Expected main to have type [string] -> int, but got {:?}: {}",
+                main_type, e
+            ),
+        );
+        return;
+    }
+    ctx.ingest_subst(&unif_subst);
+}
+
+pub(super) fn type_check_statement(stmt: &Statement, ctx: &mut TypeContext) -> Result<(), ()> {
     match stmt {
         Statement::Expression(expr) => {
             // Expressions are responsible for pushing errors into the environment
@@ -106,9 +437,207 @@ fn type_check_statement(stmt: &Statement, ctx: &mut TypeContext) -> Result<(), (
                 return Err(());
             }
         }
-        _ => todo!(),
+        Statement::TypeAlias {
+            name: (name, _),
+            type_args,
+            typ,
+            span,
+        } => {
+            let params = type_args.iter().map(|p| p.name.clone()).collect::<Vec<_>>();
+            type_check_type_alias(name, params, typ, span.clone(), ctx)?;
+        }
+        Statement::Import { path, span } => {
+            let name = &path.last().unwrap().0;
+            if !ctx.modules.contains_key(name) {
+                ctx.type_errors
+                    .insert(span.clone(), format!("Unknown module {}", name));
+                return Err(());
+            }
+            ctx.typings
+                .insert(name.clone(), TypeScheme::monotype(Type::Module));
+        }
+        Statement::Open {
+            module: (name, _),
+            span,
+        } => {
+            if !is_module_name(name, ctx) {
+                ctx.type_errors.insert(
+                    span.clone(),
+                    format!("Module {} must be imported before it is opened", name),
+                );
+                return Err(());
+            }
+            let members = ctx.modules.get(name).unwrap().clone();
+            ctx.typings.extend(members);
+        }
+        Statement::EnumDecl {
+            name: (name, _),
+            type_args,
+            variants,
+            span,
+        } => {
+            let params = type_args.iter().map(|p| p.name.clone()).collect::<Vec<_>>();
+            type_check_enum_decl(name, params, variants, span.clone(), ctx)?;
+        }
+        Statement::StructDecl {
+            name: (name, _),
+            type_args,
+            members,
+            span,
+        } => {
+            let params = type_args.iter().map(|p| p.name.clone()).collect::<Vec<_>>();
+            type_check_struct_decl(name, params, members, span.clone(), ctx)?;
+        }
+        Statement::InterfaceDecl {
+            name: (name, _),
+            type_args,
+            spec_methods,
+            values,
+            span,
+            ..
+        } => {
+            let params = type_args.iter().map(|p| p.name.clone()).collect::<Vec<_>>();
+            let items = values.iter().chain(spec_methods);
+            type_check_interface_decl(name, params, items, span.clone(), ctx)?;
+        }
+        // Syntax errors are reported by the parser
+        Statement::Error(_, _) => {}
+        // Fixities only matter to the parser
+        Statement::Fixity { .. } => {}
+        Statement::InterfaceImpl { span, .. } => {
+            return unsupported("impl blocks", span.clone(), ctx);
+        }
+    }
+
+    Ok(())
+}
+
+/// Declare a type alias, checking that the type it abbreviates only uses
+/// the alias's own type parameters, and can be expanded without cycles.
+fn type_check_type_alias(
+    name: &String,
+    params: Vec<String>,
+    typ: &Type,
+    span: span::Span,
+    ctx: &mut TypeContext,
+) -> Result<(), ()> {
+    let error = if ctx.is_type_declared(name) {
+        Some(format!("Type {} is already declared", name))
+    } else if let Some(dup) = params
+        .iter()
+        .enumerate()
+        .find_map(|(i, p)| params[..i].contains(p).then_some(p))
+    {
+        Some(format!(
+            "Type parameter '{} appears more than once in type alias {}",
+            dup, name
+        ))
+    } else {
+        let mut polytypes = vec![];
+        collect_polytypes(typ, &mut polytypes);
+        polytypes
+            .into_iter()
+            .find(|p| !params.contains(p))
+            .map(|p| {
+                format!(
+                    "Type variable '{} is not a parameter of type alias {}",
+                    p, name
+                )
+            })
+    };
+    if let Some(msg) = error {
+        ctx.type_errors.insert(span, msg);
+        return Err(());
+    }
+
+    // Declare the alias before expanding a use of it, so that cycles through
+    // it are found
+    let alias_use = Type::TypeId(
+        name.clone(),
+        params.iter().map(|p| Type::Poly(p.clone(), None)).collect(),
+    );
+    ctx.type_aliases.insert(name.clone(), (params, typ.clone()));
+    if let Err(msg) = ctx.expand_type_aliases(&alias_use) {
+        ctx.type_aliases.remove(name);
+        ctx.type_errors.insert(span, msg);
+        return Err(());
+    }
+
+    Ok(())
+}
+
+/// Declare an enumeration, with the type aliases in the fields of its variants
+/// expanded.
+fn type_check_enum_decl(
+    name: &String,
+    params: Vec<String>,
+    variants: &[(String, Option<Type>, span::Span)],
+    span: span::Span,
+    ctx: &mut TypeContext,
+) -> Result<(), ()> {
+    if ctx.is_type_declared(name) {
+        ctx.type_errors
+            .insert(span, format!("Type {} is already declared", name));
+        return Err(());
+    }
+    let mut expanded = vec![];
+    for (variant, field, variant_span) in variants {
+        let field = field
+            .as_ref()
+            .map(|typ| expand_annotation(typ, variant_span.clone(), ctx))
+            .transpose()?;
+        expanded.push((variant.clone(), field));
+    }
+    ctx.define_enumeration(name.clone(), params, expanded);
+    Ok(())
+}
+
+/// Declare a struct, with the type aliases in the types of its members
+/// expanded.
+fn type_check_struct_decl(
+    name: &String,
+    params: Vec<String>,
+    members: &[(String, Type, span::Span)],
+    span: span::Span,
+    ctx: &mut TypeContext,
+) -> Result<(), ()> {
+    if ctx.is_type_declared(name) {
+        ctx.type_errors
+            .insert(span, format!("Type {} is already declared", name));
+        return Err(());
+    }
+    let mut expanded = vec![];
+    for (member, typ, member_span) in members {
+        let typ = expand_annotation(typ, member_span.clone(), ctx)?;
+        expanded.push((member.clone(), typ));
     }
+    ctx.structs.insert(name.clone(), (params, expanded));
+    Ok(())
+}
 
+/// Declare an interface, with the type aliases in the types of the values
+/// and methods it declares expanded.
+fn type_check_interface_decl<'a, I>(
+    name: &String,
+    params: Vec<String>,
+    items: I,
+    span: span::Span,
+    ctx: &mut TypeContext,
+) -> Result<(), ()>
+where
+    I: Iterator<Item = &'a (String, Type, span::Span)>,
+{
+    if ctx.interfaces.contains_key(name) {
+        ctx.type_errors
+            .insert(span, format!("Interface {} is already declared", name));
+        return Err(());
+    }
+    let mut expanded = HashMap::new();
+    for (item, typ, item_span) in items {
+        let typ = expand_annotation(typ, item_span.clone(), ctx)?;
+        expanded.insert(item.clone(), typ);
+    }
+    ctx.interfaces.insert(name.clone(), (params, expanded));
     Ok(())
 }
 
@@ -125,19 +654,242 @@ pub(super) fn type_check_expr(
         Expression::FloatLiteral(_, _) => Ok((Type::Float, HashMap::new())),
         Expression::StringLiteral(_, _) => Ok((Type::String, HashMap::new())),
         Expression::List(exprs, _) => type_check_list(&exprs[..], ctx),
+        Expression::Tuple(exprs, _) => type_check_tuple(exprs, ctx),
+        Expression::ListComprehension {
+            expr: elem_expr,
+            clauses,
+            span: _,
+        } => type_check_list_comprehension(elem_expr, clauses, ctx),
+        Expression::Identifier(name, _) => type_check_identifier(name, expr.get_span(), ctx),
+        Expression::Projection(base, member, _) if is_module(base, ctx) => {
+            type_check_module_member(base, member, expr.get_span(), ctx)
+        }
+        Expression::Projection(base, member, _) if is_enumeration(base, ctx) => {
+            type_check_enum_variant(base, member, expr.get_span(), ctx)
+        }
+        Expression::EnumVariant {
+            enum_id,
+            variant,
+            field,
+            span,
+        } => type_check_enum_variant_with_field(enum_id, variant, field, span.clone(), ctx),
         Expression::BinaryOp(bop, _) => type_check_binary_op(bop, ctx),
+        // Any value can be printed
+        Expression::Print(_) => Ok((
+            Type::Function(Box::new(Type::Variable(ctx.genvar())), Box::new(Type::None)),
+            HashMap::new(),
+        )),
+        // Failing never returns, so it can stand for a value of any type
+        Expression::Fail(_) => Ok((
+            Type::Function(
+                Box::new(Type::Variable(ctx.genvar())),
+                Box::new(Type::Variable(ctx.genvar())),
+            ),
+            HashMap::new(),
+        )),
+        Expression::Not(_) => Ok((
+            Type::Function(Box::new(bool_type()), Box::new(bool_type())),
+            HashMap::new(),
+        )),
+        Expression::If {
+            cond,
+            then_expr,
+            else_expr,
+            span: _,
+        } => type_check_if(cond, then_expr, else_expr, ctx),
+        Expression::Match {
+            matchand,
+            arms,
+            span: _,
+        } => type_check_match(matchand, arms, ctx),
+        Expression::Lambda { arg, expr, span: _ } => type_check_lambda(arg, expr, ctx),
         Expression::FuncApplication(func, args, _) => {
             let (func_type, func_subst) = type_check_expr(func, ctx)?;
             type_check_func_app(func_type, func_subst, func.get_span(), args, ctx)
         }
-        Expression::Let(varwithval, in_expr_opt, span) => {
+        Expression::Let(varwithval, in_expr_opt, _) => {
             if let Some(in_expr) = in_expr_opt {
-                type_check_let_in(varwithval, in_expr, span.as_ref().unwrap().clone(), ctx)
+                type_check_let_in(varwithval, in_expr, expr.get_span(), ctx)
             } else {
-                type_check_let(varwithval, span.as_ref().unwrap().clone(), ctx)
+                type_check_let(varwithval, expr.get_span(), ctx)
             }
         }
-        _ => todo!(),
+        Expression::LetGroup(group, in_expr_opt, _) => {
+            if let Some(in_expr) = in_expr_opt {
+                type_check_let_group_in(group, in_expr, expr.get_span(), ctx)
+            } else {
+                type_check_let_group(group, expr.get_span(), ctx)
+            }
+        }
+        Expression::Block(exprs, result, _) => type_check_block(exprs, result, ctx),
+        Expression::Record(..) => unsupported("record expressions", expr.get_span(), ctx),
+        Expression::Projection(..) => unsupported("record fields", expr.get_span(), ctx),
+        Expression::MethodAccess(..) => unsupported("method access", expr.get_span(), ctx),
+        Expression::NamedArgsFuncApp(..) => unsupported("named arguments", expr.get_span(), ctx),
+        Expression::Set(..) => unsupported("set expressions", expr.get_span(), ctx),
+        // Infix chains are resolved into applications by the parser
+        Expression::InfixChain(..) => unreachable!("Infix chains are resolved when parsing"),
+    }
+}
+
+/// Report a construct that the checker cannot type yet, instead of assuming
+/// a type for it.
+fn unsupported<T>(what: &str, span: span::Span, ctx: &mut TypeContext) -> Result<T, ()> {
+    ctx.type_errors
+        .insert(span, format!("Type checking {} is not supported yet", what));
+    Err(())
+}
+
+/// Look up the type of a variable declared in the program.
+fn type_check_identifier(
+    name: &String,
+    span: span::Span,
+    ctx: &mut TypeContext,
+) -> Result<(Type, HashMap<usize, Type>), ()> {
+    if let Some(scheme) = ctx.typings.get(name).cloned() {
+        Ok((ctx.instantiate(&scheme), HashMap::new()))
+    } else {
+        ctx.type_errors
+            .insert(span, format!("Unbound variable {}", name));
+        Err(())
+    }
+}
+
+/// Whether an expression names an imported module.
+fn is_module(expr: &Expression, ctx: &TypeContext) -> bool {
+    match expr {
+        Expression::Identifier(name, _) => is_module_name(name, ctx),
+        _ => false,
+    }
+}
+
+/// Whether a name is bound to an imported module.
+fn is_module_name(name: &str, ctx: &TypeContext) -> bool {
+    ctx.typings
+        .get(name)
+        .is_some_and(|scheme| scheme.typ == Type::Module)
+}
+
+/// Whether an expression names an enumeration, rather than a variable.
+fn is_enumeration(expr: &Expression, ctx: &TypeContext) -> bool {
+    match expr {
+        Expression::Identifier(name, _) => {
+            ctx.typings.get(name).is_none() && ctx.enumerations.contains_key(name)
+        }
+        _ => false,
+    }
+}
+
+/// Type check a variant without a field, as in `bool.true`.
+fn type_check_enum_variant(
+    enumeration: &Expression,
+    variant: &String,
+    span: span::Span,
+    ctx: &mut TypeContext,
+) -> Result<(Type, HashMap<usize, Type>), ()> {
+    let Expression::Identifier(name, _) = enumeration else {
+        unreachable!("Enumerations are named by identifiers")
+    };
+    let field = ctx.enumerations[name]
+        .1
+        .iter()
+        .find(|(variant_name, _)| variant_name == variant)
+        .map(|(_, field)| field);
+    let msg = match field {
+        Some(None) => {
+            let (enum_type, _) = ctx.instantiate_variant(name, None);
+            return Ok((enum_type, HashMap::new()));
+        }
+        Some(Some(_)) => format!(
+            "Variant {} of enum {} has a field, which must be given with `with`",
+            variant, name
+        ),
+        None => format!("Enum {} has no variant {}", name, variant),
+    };
+    ctx.type_errors.insert(span, msg);
+    Err(())
+}
+
+/// Type check a variant given a field with `with`, as in `Option.Some with 1`.
+/// The field must have the type the variant was declared with.
+fn type_check_enum_variant_with_field(
+    enum_id: &String,
+    variant: &String,
+    field: &Expression,
+    span: span::Span,
+    ctx: &mut TypeContext,
+) -> Result<(Type, HashMap<usize, Type>), ()> {
+    let field_type = ctx.enumerations.get(enum_id).map(|(_, variants)| {
+        variants
+            .iter()
+            .find(|(variant_name, _)| variant_name == variant)
+            .map(|(_, field_type)| field_type.clone())
+    });
+    let field_type = match field_type {
+        Some(Some(Some(field_type))) => field_type,
+        Some(Some(None)) => {
+            ctx.type_errors.insert(
+                span,
+                format!("Variant {} of enum {} has no field", variant, enum_id),
+            );
+            return Err(());
+        }
+        Some(None) => {
+            ctx.type_errors
+                .insert(span, format!("Enum {} has no variant {}", enum_id, variant));
+            return Err(());
+        }
+        None => {
+            ctx.type_errors
+                .insert(span, format!("Unknown enum {}", enum_id));
+            return Err(());
+        }
+    };
+    let (enum_type, expected) = ctx.instantiate_variant(enum_id, Some(&field_type));
+    let expected = expected.unwrap();
+
+    let (actual, mut composed_subst) = type_check_expr(field, ctx)?;
+    let mut unif_subst = HashMap::new();
+    if let Err(e) = infer::unify(&expected, &actual, &mut unif_subst) {
+        ctx.type_errors.insert(
+            field.get_span(),
+            format!(
+                "Expected field of variant {} to have type {:?}, but got {:?}: {}",
+                variant, expected, actual, e
+            ),
+        );
+        return Err(());
+    }
+    composed_subst = infer::compose_substs(&unif_subst, &composed_subst);
+    ctx.ingest_subst(&composed_subst);
+    Ok((
+        infer::apply_subst_type(&composed_subst, &enum_type),
+        composed_subst,
+    ))
+}
+
+/// Look up the type of a member of an imported module, as in `List.map`.
+fn type_check_module_member(
+    module: &Expression,
+    member: &String,
+    span: span::Span,
+    ctx: &mut TypeContext,
+) -> Result<(Type, HashMap<usize, Type>), ()> {
+    let Expression::Identifier(name, _) = module else {
+        unreachable!("Modules are only referred to by name")
+    };
+    match ctx
+        .modules
+        .get(name)
+        .and_then(|members| members.get(member))
+        .cloned()
+    {
+        Some(scheme) => Ok((ctx.instantiate(&scheme), HashMap::new())),
+        None => {
+            ctx.type_errors
+                .insert(span, format!("Module {} has no member {}", name, member));
+            Err(())
+        }
     }
 }
 
@@ -149,13 +901,10 @@ fn type_check_list(
     ctx: &mut TypeContext,
 ) -> Result<(Type, HashMap<usize, Type>), ()> {
     if exprs.len() == 0 {
-        // Empty lists always type to [t] where t is a new type variable.
+        // Empty lists always type to [t] where t is a new type variable. A
+        // let generalizes over t, so a name bound to an empty list can be
+        // used as a list of any type.
         let new_typevar = ctx.genvar();
-        // TODO: this should be a true polytype - a forall
-        // When it is referenced from the environment, it should be instantiated to
-        // a new type variable
-        // This would happen in let statements where these type variables
-        // Are elevated to polytypes with the name pi{x} or something
         return Ok((
             Type::List(Box::new(Type::Variable(new_typevar))),
             HashMap::new(),
@@ -194,6 +943,396 @@ fn type_check_list(
     Ok((Type::List(Box::new(cur_unified_type)), composed_subst))
 }
 
+/// Type check the elements of a tuple, which may each have a different type.
+fn type_check_tuple(
+    exprs: &[Expression],
+    ctx: &mut TypeContext,
+) -> Result<(Type, HashMap<usize, Type>), ()> {
+    let (elem_types, composed_subst) = type_check_nonempty_expr_slice(exprs, ctx)?;
+    // Elements checked later may have constrained the types of earlier ones
+    let elem_types = elem_types
+        .iter()
+        .map(|typ| infer::apply_subst_type(&composed_subst, typ))
+        .collect();
+    Ok((Type::Tuple(elem_types), composed_subst))
+}
+
+/// Type check a list comprehension. The variable of each generator is bound
+/// to the element type of the list it draws from, in the clauses after it and
+/// in the element expression, but not outside of the comprehension.
+fn type_check_list_comprehension(
+    elem_expr: &Expression,
+    clauses: &[ast::ComprehensionClause],
+    ctx: &mut TypeContext,
+) -> Result<(Type, HashMap<usize, Type>), ()> {
+    type_check_in_frame(ctx, |ctx| {
+        type_check_comprehension_clauses(elem_expr, clauses, ctx)
+    })
+}
+
+/// Type check within a new frame of the typing environment, so that the
+/// names bound by `check` are unbound afterwards. The bindings of the outer
+/// frames receive the substitution the check resulted in.
+fn type_check_in_frame<T>(
+    ctx: &mut TypeContext,
+    check: impl FnOnce(&mut TypeContext) -> Result<(T, HashMap<usize, Type>), ()>,
+) -> Result<(T, HashMap<usize, Type>), ()> {
+    ctx.typings.push_frame();
+    let res = check(ctx);
+    ctx.typings.pop_frame();
+    if let Ok((_, subst)) = &res {
+        ctx.ingest_subst(subst);
+    }
+    res
+}
+
+/// Type check the clauses of a list comprehension in order, then its element
+/// expression, binding the variable of each generator in the current frame.
+fn type_check_comprehension_clauses(
+    elem_expr: &Expression,
+    clauses: &[ast::ComprehensionClause],
+    ctx: &mut TypeContext,
+) -> Result<(Type, HashMap<usize, Type>), ()> {
+    let mut composed_subst = HashMap::new();
+    for clause in clauses {
+        let (clause_expr, clause_type) = match clause {
+            ast::ComprehensionClause::Generator(_, source, _) => {
+                (source, Type::List(Box::new(Type::Variable(ctx.genvar()))))
+            }
+            ast::ComprehensionClause::Guard(guard) => (guard, bool_type()),
+        };
+
+        let (actual_type, clause_subst) = type_check_expr(clause_expr, ctx)?;
+        composed_subst = infer::compose_substs(&clause_subst, &composed_subst);
+
+        let mut unif_subst = HashMap::new();
+        let unif_res = infer::unify(&clause_type, &actual_type, &mut unif_subst);
+        if let Err(e) = unif_res {
+            let msg = match clause {
+                ast::ComprehensionClause::Generator(name, _, _) => format!(
+                    "Expected generator for {} to draw from a list, but got {:?}: {}",
+                    name, actual_type, e
+                ),
+                ast::ComprehensionClause::Guard(_) => format!(
+                    "Expected comprehension guard to have type bool, but got {:?}: {}",
+                    actual_type, e
+                ),
+            };
+            ctx.type_errors.insert(clause_expr.get_span(), msg);
+            return Err(());
+        }
+        composed_subst = infer::compose_substs(&unif_subst, &composed_subst);
+        ctx.ingest_subst(&composed_subst);
+
+        if let ast::ComprehensionClause::Generator(name, _, _) = clause {
+            let Type::List(elem_type) = infer::apply_subst_type(&composed_subst, &clause_type)
+            else {
+                unreachable!("Generators draw from lists")
+            };
+            ctx.typings
+                .insert(name.clone(), TypeScheme::monotype(*elem_type));
+        }
+    }
+
+    let (elem_type, elem_subst) = type_check_expr(elem_expr, ctx)?;
+    composed_subst = infer::compose_substs(&elem_subst, &composed_subst);
+    ctx.ingest_subst(&composed_subst);
+    let elem_type = infer::apply_subst_type(&composed_subst, &elem_type);
+    Ok((Type::List(Box::new(elem_type)), composed_subst))
+}
+
+/// The type of the builtin bool enum.
+fn bool_type() -> Type {
+    Type::TypeId("bool".to_string(), vec![])
+}
+
+/// Type check a conditional expression. The condition must be a bool, and
+/// both branches must have the same type, which is the type of the whole
+/// expression.
+fn type_check_if(
+    cond: &Expression,
+    then_expr: &Expression,
+    else_expr: &Expression,
+    ctx: &mut TypeContext,
+) -> Result<(Type, HashMap<usize, Type>), ()> {
+    let (cond_type, mut composed_subst) = type_check_expr(cond, ctx)?;
+    let mut unif_subst = HashMap::new();
+    if let Err(e) = infer::unify(&bool_type(), &cond_type, &mut unif_subst) {
+        ctx.type_errors.insert(
+            cond.get_span(),
+            format!(
+                "Expected condition of if to have type bool, but got {:?}: {}",
+                cond_type, e
+            ),
+        );
+        return Err(());
+    }
+    composed_subst = infer::compose_substs(&unif_subst, &composed_subst);
+    ctx.ingest_subst(&composed_subst);
+
+    let (then_type, then_subst) = type_check_expr(then_expr, ctx)?;
+    composed_subst = infer::compose_substs(&then_subst, &composed_subst);
+    let (else_type, else_subst) = type_check_expr(else_expr, ctx)?;
+    composed_subst = infer::compose_substs(&else_subst, &composed_subst);
+
+    let then_type = infer::apply_subst_type(&composed_subst, &then_type);
+    let mut unif_subst = HashMap::new();
+    if let Err(e) = infer::unify(&then_type, &else_type, &mut unif_subst) {
+        ctx.type_errors.insert(
+            else_expr.get_span(),
+            format!(
+                "Expected branches of if to have the same type, but got {:?} and {:?}: {}",
+                then_type, else_type, e
+            ),
+        );
+        return Err(());
+    }
+    composed_subst = infer::compose_substs(&unif_subst, &composed_subst);
+    ctx.ingest_subst(&composed_subst);
+    Ok((
+        infer::apply_subst_type(&composed_subst, &else_type),
+        composed_subst,
+    ))
+}
+
+/// Type check a lambda, whose argument is only bound within its body.
+fn type_check_lambda(
+    arg: &str,
+    body: &Expression,
+    ctx: &mut TypeContext,
+) -> Result<(Type, HashMap<usize, Type>), ()> {
+    let arg_type = Type::Variable(ctx.genvar());
+    let (body_type, body_subst) = type_check_in_frame(ctx, |ctx| {
+        ctx.typings
+            .insert(arg.to_string(), TypeScheme::monotype(arg_type.clone()));
+        type_check_expr(body, ctx)
+    })?;
+    let arg_type = infer::apply_subst_type(&body_subst, &arg_type);
+    Ok((
+        Type::Function(Box::new(arg_type), Box::new(body_type)),
+        body_subst,
+    ))
+}
+
+/// Type check a match expression. The pattern of each arm must match values
+/// of the matchand's type, and its variables are only bound within the arm.
+/// Every arm must have the same type, which is the type of the whole
+/// expression.
+fn type_check_match(
+    matchand: &Expression,
+    arms: &[(Pattern, Expression, span::Span)],
+    ctx: &mut TypeContext,
+) -> Result<(Type, HashMap<usize, Type>), ()> {
+    let (matchand_type, mut composed_subst) = type_check_expr(matchand, ctx)?;
+    let mut result_type = Type::Variable(ctx.genvar());
+    for (pat, arm_expr, _) in arms {
+        let matchand_type = infer::apply_subst_type(&composed_subst, &matchand_type);
+        let (arm_type, arm_subst) = type_check_in_frame(ctx, |ctx| {
+            let pat_subst = type_check_pattern(pat, &matchand_type, ctx)?;
+            let (arm_type, arm_subst) = type_check_expr(arm_expr, ctx)?;
+            Ok((arm_type, infer::compose_substs(&arm_subst, &pat_subst)))
+        })?;
+        composed_subst = infer::compose_substs(&arm_subst, &composed_subst);
+        result_type = infer::apply_subst_type(&composed_subst, &result_type);
+
+        let mut unif_subst = HashMap::new();
+        if let Err(e) = infer::unify(&result_type, &arm_type, &mut unif_subst) {
+            ctx.type_errors.insert(
+                arm_expr.get_span(),
+                format!(
+                    "Expected arms of match to have the same type, but got {:?} and {:?}: {}",
+                    result_type, arm_type, e
+                ),
+            );
+            return Err(());
+        }
+        composed_subst = infer::compose_substs(&unif_subst, &composed_subst);
+        ctx.ingest_subst(&composed_subst);
+        result_type = infer::apply_subst_type(&composed_subst, &result_type);
+    }
+    Ok((result_type, composed_subst))
+}
+
+/// Type check a pattern against the type of the values it is matched with,
+/// binding its variables in the current frame. Returns the substitution that
+/// makes the pattern match values of that type.
+fn type_check_pattern(
+    pat: &Pattern,
+    expected: &Type,
+    ctx: &mut TypeContext,
+) -> Result<HashMap<usize, Type>, ()> {
+    let mut composed_subst = HashMap::new();
+    let pattern_type = match pat {
+        Pattern::Wildcard(_) => return Ok(composed_subst),
+        Pattern::IntLiteral(_, _) => Type::Int,
+        Pattern::FloatLiteral(_, _) => Type::Float,
+        Pattern::StringLiteral(_, _) => Type::String,
+        // Variants without fields are parsed as identifiers
+        Pattern::Identifier(name, span) if ctx.enum_variant_types.contains_key(name) => {
+            type_check_variant_pattern(name, None, span.clone(), &mut composed_subst, ctx)?
+        }
+        Pattern::TypeVariant(name, field_pat, span) => type_check_variant_pattern(
+            name,
+            field_pat.as_deref(),
+            span.clone(),
+            &mut composed_subst,
+            ctx,
+        )?,
+        Pattern::Identifier(name, _) => {
+            ctx.typings
+                .insert(name.clone(), TypeScheme::monotype(expected.clone()));
+            return Ok(composed_subst);
+        }
+        Pattern::ListCons(head, tail, _) => {
+            let elem_type = Type::Variable(ctx.genvar());
+            let list_type = Type::List(Box::new(elem_type.clone()));
+            ctx.typings
+                .insert(head.clone(), TypeScheme::monotype(elem_type));
+            ctx.typings
+                .insert(tail.clone(), TypeScheme::monotype(list_type.clone()));
+            list_type
+        }
+        Pattern::EmptyList(_) => Type::List(Box::new(Type::Variable(ctx.genvar()))),
+        Pattern::List(pats, _) => {
+            let elem_type = Type::Variable(ctx.genvar());
+            for pat in pats {
+                let elem_type = infer::apply_subst_type(&composed_subst, &elem_type);
+                let subst = type_check_pattern(pat, &elem_type, ctx)?;
+                composed_subst = infer::compose_substs(&subst, &composed_subst);
+            }
+            Type::List(Box::new(elem_type))
+        }
+        Pattern::Tuple(pats, _) => {
+            let mut elem_types = vec![];
+            for pat in pats {
+                let elem_type = Type::Variable(ctx.genvar());
+                let subst = type_check_pattern(pat, &elem_type, ctx)?;
+                composed_subst = infer::compose_substs(&subst, &composed_subst);
+                elem_types.push(elem_type);
+            }
+            Type::Tuple(elem_types)
+        }
+        Pattern::Record(field_pats, flex, _) => {
+            let mut values = BTreeMap::new();
+            for (name, (pat, _)) in field_pats {
+                let value_type = Type::Variable(ctx.genvar());
+                let subst = type_check_pattern(pat, &value_type, ctx)?;
+                composed_subst = infer::compose_substs(&subst, &composed_subst);
+                values.insert(name.clone(), value_type);
+            }
+            Type::Record(Structure::new(BTreeMap::new(), values, *flex))
+        }
+        // Every alternative matches values of the same type
+        Pattern::Union(pats, _) => {
+            for pat in pats {
+                let expected = infer::apply_subst_type(&composed_subst, expected);
+                let subst = type_check_pattern(pat, &expected, ctx)?;
+                composed_subst = infer::compose_substs(&subst, &composed_subst);
+            }
+            return Ok(composed_subst);
+        }
+        // Nothing is bound by a pattern that did not match
+        Pattern::Complement(pat, _) => {
+            let (_, subst) =
+                type_check_in_frame(ctx, |ctx| Ok(((), type_check_pattern(pat, expected, ctx)?)))?;
+            return Ok(subst);
+        }
+        Pattern::Guarded {
+            pattern,
+            guard,
+            span: _,
+        } => {
+            let subst = type_check_pattern(pattern, expected, ctx)?;
+            ctx.ingest_subst(&subst);
+            let guard_subst = type_check_condition(guard, "guard", ctx)?;
+            return Ok(infer::compose_substs(&guard_subst, &subst));
+        }
+        Pattern::Case(cond, _) => return type_check_condition(cond, "case", ctx),
+    };
+
+    let expected = infer::apply_subst_type(&composed_subst, expected);
+    let pattern_type = infer::apply_subst_type(&composed_subst, &pattern_type);
+    let mut unif_subst = HashMap::new();
+    if let Err(e) = infer::unify(&expected, &pattern_type, &mut unif_subst) {
+        ctx.type_errors.insert(
+            pat.get_span(),
+            format!(
+                "Pattern of type {:?} cannot match a value of type {:?}: {}",
+                pattern_type, expected, e
+            ),
+        );
+        return Err(());
+    }
+    composed_subst = infer::compose_substs(&unif_subst, &composed_subst);
+    ctx.ingest_subst(&composed_subst);
+    Ok(composed_subst)
+}
+
+/// Type check a variant pattern, with the pattern for its field if it is
+/// given one, returning the type of the variant's enumeration.
+fn type_check_variant_pattern(
+    variant: &String,
+    field_pat: Option<&Pattern>,
+    span: span::Span,
+    composed_subst: &mut HashMap<usize, Type>,
+    ctx: &mut TypeContext,
+) -> Result<Type, ()> {
+    let Some((enumname, field_type)) = ctx.enum_variant_types.get(variant).cloned() else {
+        ctx.type_errors
+            .insert(span, format!("Unknown variant {}", variant));
+        return Err(());
+    };
+    let (enum_type, field_type) = ctx.instantiate_variant(&enumname, field_type.as_ref());
+    match (field_pat, field_type) {
+        (None, None) => {}
+        (Some(field_pat), Some(field_type)) => {
+            *composed_subst = type_check_pattern(field_pat, &field_type, ctx)?;
+        }
+        (None, Some(_)) => {
+            ctx.type_errors.insert(
+                span,
+                format!(
+                    "Variant {} of enum {} has a field, which must be matched with `with`",
+                    variant, enumname
+                ),
+            );
+            return Err(());
+        }
+        (Some(_), None) => {
+            ctx.type_errors.insert(
+                span,
+                format!("Variant {} of enum {} has no field", variant, enumname),
+            );
+            return Err(());
+        }
+    }
+    Ok(enum_type)
+}
+
+/// Type check an expression that decides whether a match arm is taken, which
+/// must be a bool.
+fn type_check_condition(
+    cond: &Expression,
+    kind: &str,
+    ctx: &mut TypeContext,
+) -> Result<HashMap<usize, Type>, ()> {
+    let (cond_type, cond_subst) = type_check_expr(cond, ctx)?;
+    let mut unif_subst = HashMap::new();
+    if let Err(e) = infer::unify(&bool_type(), &cond_type, &mut unif_subst) {
+        ctx.type_errors.insert(
+            cond.get_span(),
+            format!(
+                "Expected {} to have type bool, but got {:?}: {}",
+                kind, cond_type, e
+            ),
+        );
+        return Err(());
+    }
+    let composed_subst = infer::compose_substs(&unif_subst, &cond_subst);
+    ctx.ingest_subst(&composed_subst);
+    Ok(composed_subst)
+}
+
 /// Produce the type of builtin binary operations
 /// BIG TODO: type variables need bounds
 fn type_check_binary_op(
@@ -201,9 +1340,12 @@ fn type_check_binary_op(
     ctx: &mut TypeContext,
 ) -> Result<(Type, HashMap<usize, Type>), ()> {
     fn binary_func_type(argtype: Type, rettype: Type) -> Type {
+        binary_func(argtype.clone(), argtype, rettype)
+    }
+    fn binary_func(lhs: Type, rhs: Type, rettype: Type) -> Type {
         Type::Function(
-            Box::new(argtype.clone()),
-            Box::new(Type::Function(Box::new(argtype), Box::new(rettype))),
+            Box::new(lhs),
+            Box::new(Type::Function(Box::new(rhs), Box::new(rettype))),
         )
     }
 
@@ -214,11 +1356,42 @@ fn type_check_binary_op(
         BinaryOp::FlAdd | BinaryOp::FlSub | BinaryOp::FlMult | BinaryOp::Div => {
             binary_func_type(Type::Float, Type::Float)
         }
-        BinaryOp::Eq | BinaryOp::Neq => binary_func_type(
-            Type::Variable(ctx.genvar()),
-            Type::TypeId("bool".to_string(), vec![]),
-        ),
-        _ => todo!(),
+        // TODO: ordering should be restricted to comparable types
+        BinaryOp::Eq
+        | BinaryOp::Neq
+        | BinaryOp::Lt
+        | BinaryOp::Gt
+        | BinaryOp::Leq
+        | BinaryOp::Geq => binary_func_type(Type::Variable(ctx.genvar()), bool_type()),
+        // 'a -> ('a -> 'b) -> 'b
+        BinaryOp::Pipe => {
+            let (a, b) = (Type::Variable(ctx.genvar()), Type::Variable(ctx.genvar()));
+            let func = Type::Function(Box::new(a.clone()), Box::new(b.clone()));
+            binary_func(a, func, b)
+        }
+        // ('a -> 'b) -> ('b -> 'c) -> 'a -> 'c, and the other way around for <<
+        BinaryOp::ComposeForward | BinaryOp::ComposeBackward => {
+            let (a, b, c) = (
+                Type::Variable(ctx.genvar()),
+                Type::Variable(ctx.genvar()),
+                Type::Variable(ctx.genvar()),
+            );
+            let first = Type::Function(Box::new(a.clone()), Box::new(b.clone()));
+            let second = Type::Function(Box::new(b), Box::new(c.clone()));
+            let composed = Type::Function(Box::new(a), Box::new(c));
+            if *bop == BinaryOp::ComposeForward {
+                binary_func(first, second, composed)
+            } else {
+                binary_func(second, first, composed)
+            }
+        }
+        BinaryOp::And | BinaryOp::Or => binary_func_type(bool_type(), bool_type()),
+        // 'a -> ['a] -> ['a]
+        BinaryOp::Cons => {
+            let a = Type::Variable(ctx.genvar());
+            let list = Type::List(Box::new(a.clone()));
+            binary_func(a, list.clone(), list)
+        }
     };
     Ok((new_type, HashMap::new()))
 }
@@ -238,6 +1411,21 @@ fn type_check_func_app(
         return Ok((func_type, input_subst));
     }
 
+    // A function whose type is not known yet, such as an argument of the
+    // enclosing function, is constrained to be a function
+    let (func_type, input_subst) = if let Type::Variable(num) = func_type {
+        let new_func_type = Type::Function(
+            Box::new(Type::Variable(ctx.genvar())),
+            Box::new(Type::Variable(ctx.genvar())),
+        );
+        let func_subst = HashMap::from([(num, new_func_type.clone())]);
+        let composed_subst = infer::compose_substs(&func_subst, &input_subst);
+        ctx.ingest_subst(&composed_subst);
+        (new_func_type, composed_subst)
+    } else {
+        (func_type, input_subst)
+    };
+
     // Expect the function expression to have Function type
     let (expected_arg_type, ret_type) = if let Type::Function(arg_type, ret_type) = func_type {
         (arg_type, ret_type)
@@ -260,12 +1448,12 @@ fn type_check_func_app(
     let mut unif_subst = HashMap::new();
     let unif_res = infer::unify(&expected_arg_type, &first_arg_type, &mut unif_subst);
     let func_and_first_arg_span = span::widest_span(&[func_span, args[0].get_span()]).unwrap();
-    if unif_res.is_err() {
+    if let Err(e) = unif_res {
         ctx.type_errors.insert(
             func_and_first_arg_span,
             format!(
-                "Could not unify expected argument type {:?} with actual {:?}",
-                expected_arg_type, first_arg_type,
+                "Could not unify expected argument type {:?} with actual {:?}: {}",
+                expected_arg_type, first_arg_type, e,
             ),
         );
         return Err(());
@@ -309,83 +1497,260 @@ fn type_check_nonempty_expr_slice(
     Ok((elem_types, composed_subst))
 }
 
-/// Type check a let that does not have an in expression.
-fn type_check_let(
+/// Expand the type aliases in a type written in the program, such as an
+/// annotation, reporting failure at `span`.
+fn expand_annotation(typ: &Type, span: span::Span, ctx: &mut TypeContext) -> Result<Type, ()> {
+    ctx.expand_type_aliases(typ).map_err(|msg| {
+        ctx.type_errors.insert(span, msg);
+    })
+}
+
+/// The type a binding is assigned before its expression is checked: the
+/// annotated types of its arguments and output, with fresh type variables for
+/// those that are not annotated. Returns the argument types, the output type,
+/// and the type of the bound name.
+fn binding_type(
     varwithval: &ast::VarWithValue,
     span: span::Span,
     ctx: &mut TypeContext,
-) -> Result<(Type, HashMap<usize, Type>), ()> {
-    let ast::VarWithValue {
-        name: (name, _),
-        args,
-        rec: _,
-        expr,
-    } = varwithval;
-
-    // Create type variables for each argument
+) -> Result<(Vec<Type>, Type, Type), ()> {
     let mut arg_types = vec![];
-    for arg in args {
+    for (_, arg_annotation, arg_span) in &varwithval.args {
         // TODO: check for duplicate argument names
-        let new_type = Type::Variable(ctx.genvar());
-        arg_types.push(new_type.clone());
-        ctx.typings.insert(arg.0.clone(), new_type);
-    }
-    // Also create a type variable for the output type of the function
-    let output_type = Type::Variable(ctx.genvar());
-    arg_types.push(output_type.clone());
-
-    // If recursion is allowed, then the current function should be added to
-    // the type context. Suppose recursion is allowed for now
-    // TODO: make recursion opt-in
-    let func_type = if args.len() == 0 {
+        arg_types.push(match arg_annotation {
+            Some(typ) => expand_annotation(typ, arg_span.clone(), ctx)?,
+            None => Type::Variable(ctx.genvar()),
+        });
+    }
+    let output_type = match &varwithval.output_type {
+        Some(typ) => expand_annotation(typ, span.clone(), ctx)?,
+        None => Type::Variable(ctx.genvar()),
+    };
+
+    let bound_type = if arg_types.is_empty() {
         output_type.clone()
     } else {
-        let func_type_res = collect_functype(&arg_types[..]);
-        if func_type_res.is_err() {
-            ctx.type_errors
-                .insert(span, func_type_res.err().unwrap().to_string());
-            return Err(());
+        let mut func_types = arg_types.clone();
+        func_types.push(output_type.clone());
+        match collect_functype(&func_types) {
+            Ok(typ) => typ,
+            Err(err) => {
+                ctx.type_errors.insert(span, err.to_string());
+                return Err(());
+            }
         }
-        func_type_res.unwrap()
     };
-    ctx.typings.insert(name.clone(), func_type);
+    Ok((arg_types, output_type, bound_type))
+}
+
+/// Type check the expression of a binding against the output type it was
+/// assigned, with its arguments in scope. The arguments are not bound after
+/// the expression.
+fn type_check_binding_expr(
+    varwithval: &ast::VarWithValue,
+    arg_types: &[Type],
+    output_type: &Type,
+    span: span::Span,
+    ctx: &mut TypeContext,
+) -> Result<HashMap<usize, Type>, ()> {
+    let ast::VarWithValue {
+        name: (name, _),
+        args,
+        output_type: output_annotation,
+        rec: _,
+        expr,
+    } = varwithval;
 
     // Type check the expression, apply the obtained substitutions to the environment
     // and to the type of the expression
-    let (expr_type, expr_subst) = type_check_expr(expr, ctx)?;
+    let (expr_type, expr_subst) = type_check_in_frame(ctx, |ctx| {
+        for ((arg_name, _, _), arg_type) in args.iter().zip(arg_types) {
+            ctx.typings
+                .insert(arg_name.clone(), TypeScheme::monotype(arg_type.clone()));
+        }
+        type_check_expr(expr, ctx)
+    })?;
     ctx.ingest_subst(&expr_subst);
     let expr_type = infer::apply_subst_type(&expr_subst, &expr_type);
+    let output_type = infer::apply_subst_type(&expr_subst, output_type);
 
     // Unify expr_type and output_type
     let mut unif_subst = HashMap::new();
     let unif_res = infer::unify(&expr_type, &output_type, &mut unif_subst);
     if unif_res.is_err() {
         // report the error to the type context
-        ctx.type_errors.insert(
-            span,
-            format!(
-                "Could not unify variable type {:?} with type of expression assigned to it {:?}: {}",
-                output_type,
-                expr_type,
-                unif_res.err().unwrap(),
-            )
-        );
+        if output_annotation.is_some() {
+            // The annotation is what the expression failed to satisfy
+            ctx.type_errors.insert(
+                expr.get_span(),
+                format!(
+                    "Expected {} to have annotated type {:?}, but the expression assigned to it has type {:?}: {}",
+                    name,
+                    output_type,
+                    expr_type,
+                    unif_res.err().unwrap(),
+                ),
+            );
+        } else {
+            ctx.type_errors.insert(
+                span,
+                format!(
+                    "Could not unify variable type {:?} with type of expression assigned to it {:?}: {}",
+                    output_type,
+                    expr_type,
+                    unif_res.err().unwrap(),
+                )
+            );
+        }
 
         return Err(());
     }
-    let final_subst = infer::compose_substs(&expr_subst, &unif_subst);
-    ctx.ingest_subst(&final_subst);
-    let final_type = ctx.typings.get(name).unwrap();
-    Ok((final_type.clone(), final_subst))
+    Ok(infer::compose_substs(&unif_subst, &expr_subst))
+}
+
+/// Type check a let expression without an in expression, binding its name.
+fn type_check_let(
+    varwithval: &ast::VarWithValue,
+    span: span::Span,
+    ctx: &mut TypeContext,
+) -> Result<(Type, HashMap<usize, Type>), ()> {
+    type_check_let_group(std::slice::from_ref(varwithval), span, ctx)
+}
+
+/// Type check a group of bindings joined by `and`, binding all of their
+/// names, and return the type of the last one.
+///
+/// Without `rec`, each expression is checked before any name of the group is
+/// bound, so none of them can refer to the group. With `rec`, every member is
+/// first bound to the monomorphic type it is assigned, and the expressions of
+/// all members are checked against those types, so they are inferred together.
+///
+/// Once every expression is checked, the names are bound to the
+/// generalizations of their types, over the type variables and polytypes that
+/// are not free in the typings the group was checked in.
+fn type_check_let_group(
+    group: &[ast::VarWithValue],
+    span: span::Span,
+    ctx: &mut TypeContext,
+) -> Result<(Type, HashMap<usize, Type>), ()> {
+    let rec = group.iter().any(|varwithval| varwithval.rec);
+    let (free_vars, free_polys) = ctx.free_in_typings();
+
+    let mut assigned = vec![];
+    for varwithval in group {
+        let (arg_types, output_type, bound_type) = binding_type(varwithval, span.clone(), ctx)?;
+        if rec {
+            ctx.typings.insert(
+                varwithval.name.0.clone(),
+                TypeScheme::monotype(bound_type.clone()),
+            );
+        }
+        assigned.push((arg_types, output_type, bound_type));
+    }
+
+    let mut group_subst = HashMap::new();
+    for (varwithval, (arg_types, output_type, _)) in group.iter().zip(&assigned) {
+        let arg_types = arg_types
+            .iter()
+            .map(|typ| infer::apply_subst_type(&group_subst, typ))
+            .collect::<Vec<_>>();
+        let output_type = infer::apply_subst_type(&group_subst, output_type);
+        let subst =
+            type_check_binding_expr(varwithval, &arg_types, &output_type, span.clone(), ctx)?;
+        group_subst = infer::compose_substs(&subst, &group_subst);
+        ctx.ingest_subst(&group_subst);
+    }
+
+    // The variables free in the typings may have been substituted while
+    // checking the group
+    let free_vars = free_vars
+        .into_iter()
+        .fold(BTreeSet::new(), |mut vars, var| {
+            let typ = infer::apply_subst_type(&group_subst, &Type::Variable(var));
+            infer::collect_type_vars(&typ, &mut vars);
+            vars
+        });
+    let mut last_type = Type::None;
+    for (varwithval, (_, _, bound_type)) in group.iter().zip(assigned) {
+        last_type = infer::apply_subst_type(&group_subst, &bound_type);
+        ctx.typings.insert(
+            varwithval.name.0.clone(),
+            generalize(&last_type, &free_vars, &free_polys),
+        );
+    }
+    Ok((last_type, group_subst))
+}
+
+/// Type check a group of bindings joined by `and` that has an in expression.
+/// The names of the group are only bound within the in expression.
+fn type_check_let_group_in(
+    group: &[ast::VarWithValue],
+    in_expr: &Expression,
+    span: span::Span,
+    ctx: &mut TypeContext,
+) -> Result<(Type, HashMap<usize, Type>), ()> {
+    type_check_in_frame(ctx, |ctx| {
+        let (_, group_subst) = type_check_let_group(group, span, ctx)?;
+        let (in_type, in_subst) = type_check_expr(in_expr, ctx)?;
+        Ok((in_type, infer::compose_substs(&in_subst, &group_subst)))
+    })
+}
+
+/// Type check a block. The names bound by a let without `in` are only bound
+/// for the rest of the block.
+fn type_check_block(
+    exprs: &[Expression],
+    result: &Option<Box<Expression>>,
+    ctx: &mut TypeContext,
+) -> Result<(Type, HashMap<usize, Type>), ()> {
+    type_check_in_frame(ctx, |ctx| type_check_block_exprs(exprs, result, ctx))
+}
+
+/// Type check the expressions of a block in order, then the expression whose
+/// value it takes. Each let binds its names in the current frame, and every
+/// other expression whose value is discarded should have type none.
+fn type_check_block_exprs(
+    exprs: &[Expression],
+    result: &Option<Box<Expression>>,
+    ctx: &mut TypeContext,
+) -> Result<(Type, HashMap<usize, Type>), ()> {
+    let mut composed_subst = HashMap::new();
+    for expr in exprs {
+        let group = match expr {
+            Expression::Let(varwithval, None, _) => std::slice::from_ref(varwithval),
+            Expression::LetGroup(group, None, _) => &group[..],
+            _ => &[],
+        };
+        let (typ, subst) = type_check_expr(expr, ctx)?;
+        composed_subst = infer::compose_substs(&subst, &composed_subst);
+        let typ = infer::apply_subst_type(&composed_subst, &typ);
+        // Values whose type is not known yet might be none
+        if group.is_empty() && !matches!(typ, Type::None | Type::Variable(_)) {
+            ctx.type_warnings.insert(
+                expr.get_span(),
+                format!("Value of type {:?} is discarded in block", typ),
+            );
+        }
+    }
+
+    let result_type = match result {
+        Some(result) => {
+            let (typ, subst) = type_check_expr(result, ctx)?;
+            composed_subst = infer::compose_substs(&subst, &composed_subst);
+            infer::apply_subst_type(&composed_subst, &typ)
+        }
+        None => Type::None,
+    };
+    Ok((result_type, composed_subst))
 }
 
-/// Type check a let expression that has an in expression
-#[allow(unused_variables)]
+/// Type check a let expression that has an in expression. The name is only
+/// bound within the in expression.
 fn type_check_let_in(
     varwithval: &ast::VarWithValue,
     in_expr: &Expression,
     span: span::Span,
     ctx: &mut TypeContext,
 ) -> Result<(Type, HashMap<usize, Type>), ()> {
-    todo!()
+    type_check_let_group_in(std::slice::from_ref(varwithval), in_expr, span, ctx)
 }
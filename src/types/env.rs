@@ -0,0 +1,67 @@
+//! Lexically scoped typing environment
+use super::TypeScheme;
+use std::collections::HashMap;
+
+/// name -> type scheme mapping for the names in scope, as a stack of frames.
+/// The bottom frame holds the top-level names of the program, and each frame
+/// above it holds the names bound by one enclosing construct, such as the
+/// arguments of a binding or the generators of a list comprehension.
+/// A name is looked up from the innermost frame outwards, so bindings in
+/// inner frames shadow those in outer ones until their frame is popped.
+#[derive(Debug, Clone)]
+pub struct TypeEnv {
+    frames: Vec<HashMap<String, TypeScheme>>,
+}
+
+impl TypeEnv {
+    /// An environment with only the top-level frame, in which nothing is bound.
+    pub fn new() -> Self {
+        Self {
+            frames: vec![HashMap::new()],
+        }
+    }
+
+    /// Open a new innermost frame.
+    pub fn push_frame(&mut self) {
+        self.frames.push(HashMap::new());
+    }
+
+    /// Close the innermost frame, unbinding the names bound in it.
+    pub fn pop_frame(&mut self) {
+        assert!(self.frames.len() > 1, "Cannot pop the top-level frame");
+        self.frames.pop();
+    }
+
+    /// The type scheme of the innermost binding of a name.
+    pub fn get(&self, name: &str) -> Option<&TypeScheme> {
+        self.frames.iter().rev().find_map(|frame| frame.get(name))
+    }
+
+    /// Bind a name in the innermost frame, returning the scheme it was
+    /// previously bound to in that frame.
+    pub fn insert(&mut self, name: String, scheme: TypeScheme) -> Option<TypeScheme> {
+        self.frames.last_mut().unwrap().insert(name, scheme)
+    }
+
+    /// The schemes of every binding, including shadowed ones.
+    pub fn values(&self) -> impl Iterator<Item = &TypeScheme> {
+        self.frames.iter().flat_map(|frame| frame.values())
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut TypeScheme> {
+        self.frames.iter_mut().flat_map(|frame| frame.values_mut())
+    }
+}
+
+impl Extend<(String, TypeScheme)> for TypeEnv {
+    /// Bind every name in the innermost frame.
+    fn extend<I: IntoIterator<Item = (String, TypeScheme)>>(&mut self, iter: I) {
+        self.frames.last_mut().unwrap().extend(iter);
+    }
+}
+
+impl Default for TypeEnv {
+    fn default() -> Self {
+        Self::new()
+    }
+}
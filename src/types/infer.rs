@@ -1,7 +1,7 @@
 use super::structure::{Flex, Structure};
 /// Utility functions for type inference
-use super::Type;
-use std::collections::HashMap;
+use super::{Type, TypeScheme};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 /// Apply a substitution set to a type
 pub fn apply_subst_type(subst: &HashMap<usize, Type>, typ: &Type) -> Type {
@@ -21,15 +21,64 @@ pub fn apply_subst_type(subst: &HashMap<usize, Type>, typ: &Type) -> Type {
         }
         Type::List(t) => Type::List(Box::new(apply_subst_type(subst, t))),
         Type::Tuple(elem_types) => Type::Tuple(apply_subst_type_vec(subst, elem_types)),
+        Type::Record(structure) => {
+            let mut structure = structure.clone();
+            for t in structure
+                .values
+                .values_mut()
+                .chain(structure.methods.values_mut())
+            {
+                *t = apply_subst_type(subst, t);
+            }
+            Type::Record(structure)
+        }
         Type::Function(arg, ret) => Type::Function(
             Box::new(apply_subst_type(subst, arg)),
             Box::new(apply_subst_type(subst, ret)),
         ),
-        // most things here are TODO!
         _ => typ.clone(),
     }
 }
 
+/// Apply a substitution set to a type scheme. Quantified variables are not
+/// substituted, as they stand for a fresh variable at each use.
+pub fn apply_subst_scheme(subst: &HashMap<usize, Type>, scheme: &TypeScheme) -> TypeScheme {
+    let mut subst = subst.clone();
+    for var in &scheme.vars {
+        subst.remove(var);
+    }
+    TypeScheme {
+        vars: scheme.vars.clone(),
+        polys: scheme.polys.clone(),
+        typ: apply_subst_type(&subst, &scheme.typ),
+    }
+}
+
+/// Collect the type variables occurring in a type.
+pub fn collect_type_vars(typ: &Type, vars: &mut BTreeSet<usize>) {
+    match typ {
+        Type::Variable(num) => {
+            vars.insert(*num);
+        }
+        Type::TypeId(_, types) | Type::Tuple(types) => {
+            for t in types {
+                collect_type_vars(t, vars);
+            }
+        }
+        Type::List(t) => collect_type_vars(t, vars),
+        Type::Record(structure) => {
+            for t in structure.values.values().chain(structure.methods.values()) {
+                collect_type_vars(t, vars);
+            }
+        }
+        Type::Function(arg, ret) => {
+            collect_type_vars(arg, vars);
+            collect_type_vars(ret, vars);
+        }
+        _ => {}
+    }
+}
+
 /// Apply a substitution set
 fn apply_subst_type_vec(subst: &HashMap<usize, Type>, type_vec: &Vec<Type>) -> Vec<Type> {
     type_vec
@@ -64,36 +113,170 @@ pub fn compose_substs(
     output
 }
 
+/// Unify two types under the substitution found so far, extending it with
+/// the bindings of type variables that make the types equal.
 pub fn unify(typ1: &Type, typ2: &Type, cur_subst: &mut HashMap<usize, Type>) -> Result<(), String> {
+    let typ1 = apply_subst_type(cur_subst, typ1);
+    let typ2 = apply_subst_type(cur_subst, typ2);
     // TODO: get rid of the returns and use expression syntax
-    match (typ1, typ2) {
+    match (&typ1, &typ2) {
         (Type::None, Type::None)
         | (Type::Int, Type::Int)
         | (Type::Float, Type::Float)
         | (Type::String, Type::String) => {}
         (Type::List(t1), Type::List(t2)) => unify(t1, t2, cur_subst)?,
+        (Type::Tuple(elems1), Type::Tuple(elems2)) => {
+            if elems1.len() != elems2.len() {
+                return Err(format!(
+                    "Cannot unify a tuple of {} elements with a tuple of {} elements",
+                    elems1.len(),
+                    elems2.len()
+                ));
+            }
+            for (i, (elem1, elem2)) in elems1.iter().zip(elems2).enumerate() {
+                unify(elem1, elem2, cur_subst)
+                    .map_err(|e| format!("Tuple elements at index {} disagree: {}", i, e))?;
+            }
+        }
+        // Named types are nominal, so they unify by name and type arguments
+        (Type::TypeId(name1, args1), Type::TypeId(name2, args2)) => {
+            if name1 != name2 {
+                return Err(format!("Cannot unify type {} with type {}", name1, name2));
+            }
+            if args1.len() != args2.len() {
+                return Err(format!(
+                    "Cannot unify type {} with {} type argument(s) with type {} with {}",
+                    name1,
+                    args1.len(),
+                    name2,
+                    args2.len()
+                ));
+            }
+            for (i, (arg1, arg2)) in args1.iter().zip(args2).enumerate() {
+                unify(arg1, arg2, cur_subst).map_err(|e| {
+                    format!("Type arguments of {} at index {} disagree: {}", name1, i, e)
+                })?;
+            }
+        }
+        // A substitution only binds type variables, and a permissive record
+        // has no variable standing for the members it does not list, so the
+        // members the records are merged into cannot be recorded here: only
+        // the bindings made by unifying their shared members are kept.
+        // Callers that need the merged record use `unify_structures`.
+        // TODO: row variables for permissive records
+        (Type::Record(struct1), Type::Record(struct2)) => {
+            unify_structures(struct1, struct2, cur_subst)?;
+        }
         (Type::Function(f1_arg, f1_ret), Type::Function(f2_arg, f2_ret)) => {
             unify(f1_arg, f2_arg, cur_subst)?;
             unify(f1_ret, f2_ret, cur_subst)?
         }
-        (Type::Variable(num1), Type::Variable(num2)) => {
-            cur_subst.insert(*num1, Type::Variable(*num2));
-        }
-        (Type::Variable(num), t) | (t, Type::Variable(num)) => {
-            cur_subst.insert(*num, t.clone());
+        // Polytypes are rigid, so they only unify with themselves
+        (Type::Poly(name1, bound1), Type::Poly(name2, bound2))
+            if name1 == name2 && bound1 == bound2 => {}
+        (Type::Variable(num1), Type::Variable(num2)) if num1 == num2 => {}
+        (Type::Variable(num), t) | (t, Type::Variable(num)) => bind_var(*num, t, cur_subst)?,
+        (Type::Poly(name, _), t) | (t, Type::Poly(name, _)) => {
+            return Err(format!(
+                "Type variable '{} is rigid and cannot be unified with {:?}",
+                name, t
+            ));
         }
-        // Actually there are still TODOs here
         _ => {
-            return Err("Unify ahh!".to_string());
+            return Err(format!("Cannot unify {:?} with {:?}", typ1, typ2));
         }
     }
 
     Ok(())
 }
 
-// TODO: this should return a result containing the unified structure, or error
-fn unify_structures(struct1: &Structure, struct2: &Structure) -> Result<Structure, String> {
-    todo!()
+/// Bind a type variable to a type in a substitution, which must not contain
+/// the variable: a type cannot be made equal to a type it is part of.
+fn bind_var(num: usize, typ: &Type, cur_subst: &mut HashMap<usize, Type>) -> Result<(), String> {
+    let mut vars = BTreeSet::new();
+    collect_type_vars(typ, &mut vars);
+    if vars.contains(&num) {
+        return Err(format!(
+            "Infinite type: {:?} cannot be unified with {:?}, which contains it",
+            Type::Variable(num),
+            typ
+        ));
+    }
+
+    // Keep the substitution from referring to variables it binds
+    let binding = HashMap::from([(num, typ.clone())]);
+    for bound in cur_subst.values_mut() {
+        *bound = apply_subst_type(&binding, bound);
+    }
+    cur_subst.insert(num, typ.clone());
+    Ok(())
+}
+
+/// Unify two record types, returning the record type they agree on.
+///
+/// An exact record has exactly the members it lists, while a permissive one
+/// has at least them. So two exact records must list the same members, a
+/// permissive record may only list members of an exact one, and two permissive
+/// records agree on the members they both list. Members listed by both records
+/// must have types that unify.
+pub fn unify_structures(
+    struct1: &Structure,
+    struct2: &Structure,
+    cur_subst: &mut HashMap<usize, Type>,
+) -> Result<Structure, String> {
+    let is_exact = |structure: &Structure| structure.flex != Flex::Permissive;
+    let values = unify_members(
+        "value",
+        (&struct1.values, is_exact(struct1)),
+        (&struct2.values, is_exact(struct2)),
+        cur_subst,
+    )?;
+    let methods = unify_members(
+        "method",
+        (&struct1.methods, is_exact(struct1)),
+        (&struct2.methods, is_exact(struct2)),
+        cur_subst,
+    )?;
+
+    let flex = if is_exact(struct1) {
+        struct1.flex
+    } else {
+        struct2.flex
+    };
+    Ok(Structure::new(methods, values, flex))
+}
+
+/// Unify the values or methods of two records, given whether each record is
+/// exact, returning the members of the unified record.
+fn unify_members(
+    kind: &str,
+    (members1, exact1): (&BTreeMap<String, Type>, bool),
+    (members2, exact2): (&BTreeMap<String, Type>, bool),
+    cur_subst: &mut HashMap<usize, Type>,
+) -> Result<BTreeMap<String, Type>, String> {
+    // A member listed by only one record must be allowed by the other
+    for (members, other_members, other_exact) in
+        [(members1, members2, exact2), (members2, members1, exact1)]
+    {
+        if let Some(name) = members
+            .keys()
+            .find(|name| other_exact && !other_members.contains_key(*name))
+        {
+            return Err(format!("Record has no {} {}", kind, name));
+        }
+    }
+
+    for (name, typ1) in members1 {
+        if let Some(typ2) = members2.get(name) {
+            unify(typ1, typ2, cur_subst)
+                .map_err(|e| format!("Types of record {} {} disagree: {}", kind, name, e))?;
+        }
+    }
+    Ok(members1
+        .iter()
+        .chain(members2)
+        .map(|(name, typ)| (name.clone(), apply_subst_type(cur_subst, typ)))
+        .collect())
 }
 
 // unify structural types
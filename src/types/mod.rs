@@ -1,4 +1,5 @@
 pub mod check;
+mod env;
 mod infer;
 pub(crate) mod structure;
 
@@ -35,6 +36,30 @@ pub enum Type {
     Module,
 }
 
+/// The type of a name bound by a let, which may be polymorphic in some of its
+/// type variables and polytypes: `let id x = x` binds `id` to
+/// `forall t. t -> t`. Each use of the name instantiates the quantified
+/// variables and polytypes with fresh type variables.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeScheme {
+    /// Quantified type variables
+    pub vars: Vec<usize>,
+    /// Quantified polytypes, such as `'a` in an annotation
+    pub polys: Vec<String>,
+    pub typ: Type,
+}
+
+impl TypeScheme {
+    /// A scheme that quantifies nothing, which every use shares.
+    pub fn monotype(typ: Type) -> Self {
+        Self {
+            vars: vec![],
+            polys: vec![],
+            typ,
+        }
+    }
+}
+
 // None, Int, Float, String, enums, structs, lists, functions, these
 // all have interfaces known statically from predefined methods and impld interfaces
 // same with records
@@ -3,4 +3,6 @@ use super::*;
 #[cfg(test)]
 mod test_check_expr;
 #[cfg(test)]
+mod test_check_stmt;
+#[cfg(test)]
 mod test_inference_funcs;
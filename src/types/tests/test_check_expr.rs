@@ -1,19 +1,12 @@
 use super::Type::*;
 use super::*;
-use crate::parse::ast::{BinaryOp, Expression};
+use crate::parse::ast::{BinaryOp, Expression, VarWithValue};
 use crate::test_util::to_of64;
 // override the imported Type::None
-use crate::parse::span::{GetSpan, Span};
+use crate::parse::span::Span;
 use check::type_check_expr;
 use Option::None;
 
-#[cfg(test)]
-impl GetSpan for Expression {
-    fn get_span(&self) -> Span {
-        return Span::new(0, 0);
-    }
-}
-
 // Assume validly formed expression
 fn test_check_expr(expr: Expression) -> Result<Type, ()> {
     let mut ctx = check::TypeContext::new();
@@ -24,19 +17,23 @@ fn test_check_expr(expr: Expression) -> Result<Type, ()> {
 #[test]
 fn test_check_literal() {
     assert_eq!(
-        test_check_expr(Expression::IntLiteral(4, None)).unwrap(),
+        test_check_expr(Expression::IntLiteral(4, Span::dummy())).unwrap(),
         Int
     );
     assert_eq!(
-        test_check_expr(Expression::StringLiteral("hello".to_string(), None)).unwrap(),
+        test_check_expr(Expression::StringLiteral(
+            "hello".to_string(),
+            Span::dummy()
+        ))
+        .unwrap(),
         String
     );
     assert_eq!(
-        test_check_expr(Expression::Nothing(None)).unwrap(),
+        test_check_expr(Expression::Nothing(Span::dummy())).unwrap(),
         Type::None
     );
     assert_eq!(
-        test_check_expr(Expression::FloatLiteral(to_of64(4.5), None)).unwrap(),
+        test_check_expr(Expression::FloatLiteral(to_of64(4.5), Span::dummy())).unwrap(),
         Float
     );
 }
@@ -47,33 +44,33 @@ fn test_check_list() {
     assert_eq!(
         test_check_expr(Expression::List(
             vec![
-                Expression::IntLiteral(4, None),
-                Expression::IntLiteral(5, None),
+                Expression::IntLiteral(4, Span::dummy()),
+                Expression::IntLiteral(5, Span::dummy()),
             ],
-            None
+            Span::dummy()
         ))
         .unwrap(),
         List(Box::new(Int))
     );
     assert_eq!(
         test_check_expr(Expression::List(
-            vec![Expression::IntLiteral(4, None),],
-            None
+            vec![Expression::IntLiteral(4, Span::dummy()),],
+            Span::dummy()
         ))
         .unwrap(),
         List(Box::new(Int))
     );
     assert_eq!(
-        test_check_expr(Expression::List(vec![], None)).unwrap(),
+        test_check_expr(Expression::List(vec![], Span::dummy())).unwrap(),
         List(Box::new(Variable(0)))
     );
 
     assert!(test_check_expr(Expression::List(
         vec![
-            Expression::IntLiteral(4, None),
-            Expression::FloatLiteral(to_of64(5.6), None),
+            Expression::IntLiteral(4, Span::dummy()),
+            Expression::FloatLiteral(to_of64(5.6), Span::dummy()),
         ],
-        None
+        Span::dummy()
     ))
     .is_err());
 }
@@ -82,12 +79,12 @@ fn test_check_list() {
 fn test_check_func_application() {
     assert_eq!(
         test_check_expr(Expression::FuncApplication(
-            Box::new(Expression::BinaryOp(BinaryOp::Add, None)),
+            Box::new(Expression::BinaryOp(BinaryOp::Add, Span::dummy())),
             vec![
-                Expression::IntLiteral(4, None),
-                Expression::IntLiteral(5, None),
+                Expression::IntLiteral(4, Span::dummy()),
+                Expression::IntLiteral(5, Span::dummy()),
             ],
-            None,
+            Span::dummy(),
         ))
         .unwrap(),
         Int
@@ -95,19 +92,19 @@ fn test_check_func_application() {
 
     assert_eq!(
         test_check_expr(Expression::FuncApplication(
-            Box::new(Expression::BinaryOp(BinaryOp::FlSub, None)),
+            Box::new(Expression::BinaryOp(BinaryOp::FlSub, Span::dummy())),
             vec![
-                Expression::FloatLiteral(to_of64(9.5), None),
-                Expression::FloatLiteral(to_of64(25.2), None),
+                Expression::FloatLiteral(to_of64(9.5), Span::dummy()),
+                Expression::FloatLiteral(to_of64(25.2), Span::dummy()),
             ],
-            None,
+            Span::dummy(),
         ))
         .unwrap(),
         Float
     );
 
     assert_eq!(
-        test_check_expr(Expression::BinaryOp(BinaryOp::Eq, None)).unwrap(),
+        test_check_expr(Expression::BinaryOp(BinaryOp::Eq, Span::dummy())).unwrap(),
         Type::Function(
             Box::new(Type::Variable(0)),
             Box::new(Type::Function(
@@ -118,12 +115,269 @@ fn test_check_func_application() {
     );
 
     assert!(test_check_expr(Expression::FuncApplication(
-        Box::new(Expression::BinaryOp(BinaryOp::Add, None)),
+        Box::new(Expression::BinaryOp(BinaryOp::Add, Span::dummy())),
         vec![
-            Expression::IntLiteral(4, None),
-            Expression::FloatLiteral(to_of64(5.0), None),
+            Expression::IntLiteral(4, Span::dummy()),
+            Expression::FloatLiteral(to_of64(5.0), Span::dummy()),
         ],
+        Span::dummy(),
+    ))
+    .is_err());
+}
+
+fn let_binding(
+    name: &str,
+    args: Vec<(&str, Option<Type>)>,
+    output_type: Option<Type>,
+    expr: Expression,
+) -> VarWithValue {
+    VarWithValue {
+        name: (name.to_string(), Span::dummy()),
+        args: args
+            .into_iter()
+            .map(|(arg, typ)| (arg.to_string(), typ, Span::dummy()))
+            .collect(),
+        output_type,
+        rec: false,
+        expr: Box::new(expr),
+    }
+}
+
+fn let_expr(
+    name: &str,
+    args: Vec<(&str, Option<Type>)>,
+    output_type: Option<Type>,
+    expr: Expression,
+) -> Expression {
+    Expression::Let(
+        let_binding(name, args, output_type, expr),
+        None,
+        Span::dummy(),
+    )
+}
+
+fn let_in_expr(
+    name: &str,
+    args: Vec<(&str, Option<Type>)>,
+    output_type: Option<Type>,
+    expr: Expression,
+    in_expr: Expression,
+) -> Expression {
+    Expression::Let(
+        let_binding(name, args, output_type, expr),
+        Some(Box::new(in_expr)),
+        Span::dummy(),
+    )
+}
+
+fn identifier(name: &str) -> Expression {
+    Expression::Identifier(name.to_string(), Span::dummy())
+}
+
+#[test]
+fn test_check_let() {
+    assert_eq!(
+        test_check_expr(let_expr(
+            "x",
+            vec![],
+            None,
+            Expression::IntLiteral(4, Span::dummy())
+        ))
+        .unwrap(),
+        Int
+    );
+    assert_eq!(
+        test_check_expr(let_expr(
+            "x",
+            vec![],
+            Some(Int),
+            Expression::IntLiteral(4, Span::dummy())
+        ))
+        .unwrap(),
+        Int
+    );
+    assert!(test_check_expr(let_expr(
+        "x",
+        vec![],
+        Some(Float),
+        Expression::IntLiteral(4, Span::dummy())
+    ))
+    .is_err());
+
+    // let f (a: int) -> (b: int) -> int = a + b
+    let add = Expression::FuncApplication(
+        Box::new(Expression::BinaryOp(BinaryOp::Add, Span::dummy())),
+        vec![
+            Expression::Identifier("a".to_string(), Span::dummy()),
+            Expression::Identifier("b".to_string(), Span::dummy()),
+        ],
+        Span::dummy(),
+    );
+    assert_eq!(
+        test_check_expr(let_expr(
+            "f",
+            vec![("a", Some(Int)), ("b", Some(Int))],
+            Some(Int),
+            add.clone()
+        ))
+        .unwrap(),
+        Function(
+            Box::new(Int),
+            Box::new(Function(Box::new(Int), Box::new(Int)))
+        )
+    );
+    // Unannotated arguments are inferred from annotated ones
+    assert_eq!(
+        test_check_expr(let_expr(
+            "f",
+            vec![("a", Some(Int)), ("b", None)],
+            None,
+            add.clone()
+        ))
+        .unwrap(),
+        Function(
+            Box::new(Int),
+            Box::new(Function(Box::new(Int), Box::new(Int)))
+        )
+    );
+    assert!(test_check_expr(let_expr(
+        "f",
+        vec![("a", Some(Float)), ("b", None)],
+        None,
+        add.clone()
+    ))
+    .is_err());
+    assert!(test_check_expr(let_expr(
+        "f",
+        vec![("a", None), ("b", None)],
+        Some(Float),
+        add
+    ))
+    .is_err());
+
+    // let id (x: 'a) -> 'a = x
+    let poly_a = Poly("a".to_string(), None);
+    assert_eq!(
+        test_check_expr(let_expr(
+            "id",
+            vec![("x", Some(poly_a.clone()))],
+            Some(poly_a.clone()),
+            Expression::Identifier("x".to_string(), Span::dummy())
+        ))
+        .unwrap(),
+        Function(Box::new(poly_a.clone()), Box::new(poly_a.clone()))
+    );
+    assert!(test_check_expr(let_expr(
+        "id",
+        vec![("x", Some(poly_a))],
+        Some(Poly("b".to_string(), None)),
+        Expression::Identifier("x".to_string(), Span::dummy())
+    ))
+    .is_err());
+}
+
+#[test]
+fn test_check_let_in() {
+    assert_eq!(
+        test_check_expr(let_in_expr(
+            "x",
+            vec![],
+            None,
+            Expression::IntLiteral(4, Span::dummy()),
+            identifier("x")
+        ))
+        .unwrap(),
+        Int
+    );
+    assert!(test_check_expr(let_in_expr(
+        "x",
+        vec![],
+        Some(Float),
+        Expression::IntLiteral(4, Span::dummy()),
+        identifier("x")
+    ))
+    .is_err());
+
+    // let f (a: int) b = a + b in f 4
+    let add = Expression::FuncApplication(
+        Box::new(Expression::BinaryOp(BinaryOp::Add, Span::dummy())),
+        vec![identifier("a"), identifier("b")],
+        Span::dummy(),
+    );
+    let apply_f = Expression::FuncApplication(
+        Box::new(identifier("f")),
+        vec![Expression::IntLiteral(4, Span::dummy())],
+        Span::dummy(),
+    );
+    assert_eq!(
+        test_check_expr(let_in_expr(
+            "f",
+            vec![("a", Some(Int)), ("b", None)],
+            None,
+            add.clone(),
+            apply_f.clone()
+        ))
+        .unwrap(),
+        Function(Box::new(Int), Box::new(Int))
+    );
+    assert!(test_check_expr(let_in_expr(
+        "f",
+        vec![("a", Some(Float)), ("b", None)],
+        None,
+        add,
+        apply_f
+    ))
+    .is_err());
+
+    // let id x = x in id id 4.5, which uses id at two types
+    assert_eq!(
+        test_check_expr(let_in_expr(
+            "id",
+            vec![("x", None)],
+            None,
+            identifier("x"),
+            Expression::FuncApplication(
+                Box::new(identifier("id")),
+                vec![
+                    identifier("id"),
+                    Expression::FloatLiteral(to_of64(4.5), Span::dummy())
+                ],
+                Span::dummy(),
+            )
+        ))
+        .unwrap(),
+        Float
+    );
+
+    // The name is only bound within the in expression, and the binding it
+    // shadows is restored afterwards
+    let mut ctx = check::TypeContext::new();
+    ctx.typings
+        .insert("x".to_string(), TypeScheme::monotype(Float));
+    let expr = let_in_expr(
+        "x",
+        vec![],
+        None,
+        Expression::IntLiteral(4, Span::dummy()),
+        identifier("x"),
+    );
+    assert!(type_check_expr(&expr, &mut ctx).unwrap().0 == Int);
+    assert!(ctx.typings.get("x") == Some(&TypeScheme::monotype(Float)));
+    let expr = let_in_expr(
+        "y",
+        vec![],
+        None,
+        Expression::IntLiteral(4, Span::dummy()),
+        identifier("y"),
+    );
+    assert!(type_check_expr(&expr, &mut ctx).is_ok());
+    assert!(ctx.typings.get("y").is_none());
+    assert!(test_check_expr(let_in_expr(
+        "x",
+        vec![],
         None,
+        Expression::IntLiteral(4, Span::dummy()),
+        identifier("y")
     ))
     .is_err());
 }
@@ -0,0 +1,538 @@
+use crate::module::load_program;
+use crate::module::tests::fixture;
+use crate::parse::parse_program;
+use crate::parse::span::Span;
+use check::type_check_program;
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::*;
+
+fn check_program(inp: &str) -> Result<(), HashMap<Span, String>> {
+    let (prog, parse_errors) = parse_program(0, inp);
+    assert!(parse_errors.is_empty(), "{:?}", parse_errors);
+    type_check_program(&prog)
+}
+
+/// Check that the program has exactly one type error, and return it
+fn single_error(inp: &str) -> (Span, String) {
+    let errors = check_program(inp).unwrap_err();
+    assert!(errors.len() == 1, "{:?}", errors);
+    errors.into_iter().next().unwrap()
+}
+
+/// Check a program that has no type errors, and return the type of `result`
+fn typing_of(inp: &str) -> Type {
+    let (prog, parse_errors) = parse_program(0, inp);
+    assert!(parse_errors.is_empty(), "{:?}", parse_errors);
+    let mut ctx = check::TypeContext::new();
+    for stmt in &prog {
+        check::type_check_statement(stmt, &mut ctx).unwrap();
+    }
+    ctx.typings.get("result").unwrap().typ.clone()
+}
+
+#[test]
+fn test_check_type_alias() {
+    assert!(check_program("type Ints = [int]; let xs: Ints = [1, 2];").is_ok());
+    assert!(check_program("type 'a L = ['a]; let xs: int L = [1];").is_ok());
+    assert!(check_program("type 'a L = ['a]; let xs: float L = [1.0];").is_ok());
+    // Parameters after the name are supplied in the same order
+    let pair = "type Pair 'a 'b = ('a, 'b);";
+    assert!(check_program(&format!("{} let p: int string Pair = (1, \"one\");", pair)).is_ok());
+    assert!(check_program(&format!("{} let p: int string Pair = (\"one\", 1);", pair)).is_err());
+    // Aliases may refer to earlier aliases
+    assert!(check_program("type F = int -> int; type G = [F]; let g (f: F) -> G = [f];").is_ok());
+
+    assert!(single_error("type Ints = [int]; let xs: Ints = [1.0];")
+        .1
+        .contains("Expected xs to have annotated type"));
+    assert!(
+        single_error("type 'a L = ['a]; let xs: L = [1];").1
+            == "Type alias L expects 1 type argument(s) but got 0"
+    );
+    assert!(
+        single_error("type Ints = [int]; let xs: int Ints = [1];").1
+            == "Type alias Ints expects 0 type argument(s) but got 1"
+    );
+
+    // Aliases are expanded in the types of declarations as well
+    assert!(check_program(
+        "type Ints = [int]; enum E = A with Ints | B; struct S { xs: Ints }; \
+         sig I { val xs: Ints, method m: Ints -> int };"
+    )
+    .is_ok());
+    let arity_error = "Type alias L expects 1 type argument(s) but got 0".to_string();
+    assert!(
+        single_error("type 'a L = ['a]; enum E = A with L;")
+            == (Span::new(0, 27, 35), arity_error.clone())
+    );
+    assert!(
+        single_error("type 'a L = ['a]; struct S { xs: L };")
+            == (Span::new(0, 29, 34), arity_error.clone())
+    );
+    assert!(single_error("type 'a L = ['a]; sig I { val xs: L };").1 == arity_error);
+    assert!(single_error("type T = int; enum T = A;").1 == "Type T is already declared");
+}
+
+#[test]
+fn test_check_type_alias_errors() {
+    assert!(
+        single_error("type T = int; type T = float;")
+            == (
+                Span::new(0, 14, 28),
+                "Type T is already declared".to_string()
+            )
+    );
+    // Builtin enumerations cannot be redeclared either
+    assert!(
+        single_error("type bool = int;")
+            == (
+                Span::new(0, 0, 15),
+                "Type bool is already declared".to_string()
+            )
+    );
+    assert!(
+        single_error("type T = ['a];")
+            == (
+                Span::new(0, 0, 13),
+                "Type variable 'a is not a parameter of type alias T".to_string()
+            )
+    );
+    assert!(
+        single_error("type 'a 'a T = ['a];")
+            == (
+                Span::new(0, 0, 19),
+                "Type parameter 'a appears more than once in type alias T".to_string()
+            )
+    );
+
+    // Cycles, both direct and through other aliases, are rejected
+    assert!(
+        single_error("type T = (int, T);")
+            == (
+                Span::new(0, 0, 17),
+                "Type alias T is cyclic: T -> T".to_string()
+            )
+    );
+    assert!(
+        single_error("type A = [B]; type B = A;")
+            == (
+                Span::new(0, 14, 24),
+                "Type alias B is cyclic: B -> A -> B".to_string()
+            )
+    );
+    // A rejected alias is not declared
+    assert!(
+        check_program("type T = ['a]; type T = int;")
+            .unwrap_err()
+            .len()
+            == 1
+    );
+}
+
+#[test]
+fn test_check_list_comprehension() {
+    let list_type = |t| Type::List(Box::new(t));
+
+    assert!(typing_of("let result = [x | x <- [1, 2]];") == list_type(Type::Int));
+    assert!(
+        typing_of("let f (x: int) -> float = 1.0; let result = [f x | x <- [3, -1], x > 0];")
+            == list_type(Type::Float)
+    );
+    assert!(
+        typing_of("let result = [[x, y] | x <- [1], y <- [x, 2]];")
+            == list_type(list_type(Type::Int))
+    );
+    // The generator variable is not bound outside of the comprehension
+    assert!(single_error("let ys = [x | x <- [1]]; let result = x;").1 == "Unbound variable x");
+    // Generators shadow variables only within the comprehension
+    assert!(typing_of("let x = 1.0; let ys = [x | x <- [1]]; let result = x;") == Type::Float);
+
+    assert!(single_error("let result = [x | x <- 4];")
+        .1
+        .starts_with("Expected generator for x to draw from a list, but got Int"));
+    assert!(single_error("let result = [x | x <- [1], x + 1];")
+        .1
+        .starts_with("Expected comprehension guard to have type bool, but got Int"));
+}
+
+#[test]
+fn test_check_let_rec() {
+    let int_to_int = Type::Function(Box::new(Type::Int), Box::new(Type::Int));
+    assert!(typing_of("let rec f x = (f (x - 1)) + 1; let result = f;") == int_to_int);
+    // The members of a group are inferred together
+    assert!(
+        typing_of("let rec f x = (g x) + 1 and g y = f (y - 1); let result = g;") == int_to_int
+    );
+    assert!(
+        typing_of("let result = (let rec f x = g x and g (y: int) -> int = y in f 2);")
+            == Type::Int
+    );
+    let even_odd = "let rec even n = match n { 0 => true, n => odd (n - 1) } \
+                    and odd n = match n { 0 => false, n => even (n - 1) };";
+    assert!(
+        typing_of(&format!("{} let result = odd;", even_odd))
+            == Type::Function(
+                Box::new(Type::Int),
+                Box::new(Type::TypeId("bool".to_string(), vec![]))
+            )
+    );
+    // Without `rec`, no member can see the group
+    assert!(typing_of("let x = 1.0; let x = 2 and y = x; let result = y;") == Type::Float);
+
+    assert!(single_error("let f x = f x;").1 == "Unbound variable f");
+    assert!(single_error("let f = 1 and g = f;").1 == "Unbound variable f");
+    // The names of a group with `in` are not bound after it
+    assert!(
+        single_error("let y = (let rec f x = g x and g x = x in f 1); let result = f;").1
+            == "Unbound variable f"
+    );
+    assert!(
+        single_error("let rec f (x: int) -> int = g x and g y = 1.5;")
+            .1
+            .starts_with("Could not unify variable type")
+    );
+}
+
+#[test]
+fn test_check_let_polymorphism() {
+    // Each use of a let-bound name gets fresh type variables
+    let defs = "let id x = x; let const x y = x;";
+    assert!(typing_of(&format!("{} let a = id 1; let result = id 2.0;", defs)) == Type::Float);
+    assert!(typing_of(&format!("{} let result = (id id) 1;", defs)) == Type::Int);
+    assert!(
+        typing_of(&format!(
+            "{} let a = const 1 2.0; let result = const 1.0 [];",
+            defs
+        )) == Type::Float
+    );
+    let Type::Function(arg, ret) = typing_of(&format!("{} let result = id;", defs)) else {
+        panic!("id is not a function");
+    };
+    assert!(matches!(*arg, Type::Variable(_)) && arg == ret);
+    // Polytypes in annotations are quantified as well
+    assert!(
+        typing_of("let id (x: 'a) -> 'a = x; let a = id 1; let result = id 2.0;") == Type::Float
+    );
+    // A name bound to an empty list is a list of any type
+    assert!(
+        typing_of("let e = []; let a = e == [1]; let result = e == [1.0];")
+            == Type::TypeId("bool".to_string(), vec![])
+    );
+    // The members of a group are generalized together
+    assert!(
+        typing_of("let rec f x = x and g y = f y; let a = g 1; let result = f 2.0;") == Type::Float
+    );
+
+    // Variables free in the typings are not generalized, so an argument is
+    // used at a single type
+    assert!(check_program("let f x = { let g = x; let a = g 1; g 2.0 };").is_err());
+    assert!(check_program("let f x = { let g y = x; let a = (g 1) + 1; g 2.0 };").is_ok());
+    // A recursive binding is monomorphic within its own group
+    assert!(check_program("let rec f x = { let a = f 1; f 2.0 };").is_err());
+}
+
+#[test]
+fn test_check_infinite_type() {
+    let (_, msg) = single_error("let rec f x = f;");
+    assert!(msg.contains("Infinite type"), "{}", msg);
+    let (_, msg) = single_error("let f x = x x;");
+    assert!(msg.starts_with("Could not unify expected argument type"));
+    assert!(msg.contains("Infinite type"), "{}", msg);
+    assert!(check_program("let rec f x = [f];").is_err());
+}
+
+#[test]
+fn test_check_operators() {
+    assert!(typing_of("let (<+>) a b = a + b; let result = 1 <+> 2;") == Type::Int);
+    assert!(
+        typing_of("infixl 5 <.>; let (<.>) (x: float) -> (n: int) -> [float] = [x]; let result = 1.0 <.> 2 + 3;")
+            == Type::List(Box::new(Type::Float))
+    );
+    assert!(
+        single_error("let (<+>) a b = a + b; let result = 1 <+> 2.0;")
+            .1
+            .contains("Float")
+    );
+}
+
+#[test]
+fn test_check_pipeline() {
+    let defs = "let inc x = x + 1; let half (n: int) -> float = 0.5;";
+    assert!(typing_of(&format!("{} let result = 3 |> inc |> inc;", defs)) == Type::Int);
+    let int_to_float = Type::Function(Box::new(Type::Int), Box::new(Type::Float));
+    assert!(typing_of(&format!("{} let result = inc >> half;", defs)) == int_to_float);
+    assert!(typing_of(&format!("{} let result = half << inc;", defs)) == int_to_float);
+    assert!(typing_of(&format!("{} let result = 1 |> inc >> half;", defs)) == Type::Float);
+    assert!(
+        typing_of(&format!(
+            "{} let add a b = a + b; let result = 1 |> add 2 >> half;",
+            defs
+        )) == Type::Float
+    );
+    assert!(check_program(&format!("{} let result = 1.0 |> inc;", defs)).is_err());
+    assert!(check_program(&format!("{} let result = half >> inc;", defs)).is_err());
+}
+
+#[test]
+fn test_check_cons() {
+    let int_list = Type::List(Box::new(Type::Int));
+    assert!(typing_of("let result = 1 :: [2];") == int_list);
+    assert!(typing_of("let result = 1 :: 2 :: [];") == int_list);
+    // The empty list is generalized, so it can be consed onto at any type
+    let defs = "let e = []; let a = 1 :: e; let b = \"x\" :: e;";
+    assert!(typing_of(&format!("{} let result = a;", defs)) == int_list);
+    assert!(typing_of(&format!("{} let result = b;", defs)) == Type::List(Box::new(Type::String)));
+    assert!(
+        typing_of("let result = (::) 1.0;")
+            == Type::Function(
+                Box::new(Type::List(Box::new(Type::Float))),
+                Box::new(Type::List(Box::new(Type::Float)))
+            )
+    );
+    assert!(check_program("let result = 1 :: [\"x\"];").is_err());
+    assert!(check_program("let result = 1 :: 2;").is_err());
+}
+
+#[test]
+fn test_check_if() {
+    let bool_type = Type::TypeId("bool".to_string(), vec![]);
+    assert!(typing_of("let result = if 1 < 2 then 1 else 2;") == Type::Int);
+    assert!(typing_of("let result = 1 < 2 && (not (2 < 1)) || 1 == 1;") == bool_type);
+    assert!(
+        typing_of("let result = (&&) (1 < 2);")
+            == Type::Function(Box::new(bool_type.clone()), Box::new(bool_type.clone()))
+    );
+    assert!(typing_of("let f x = if x then 1.0 else 2.0; let result = f (1 < 2);") == Type::Float);
+    // The variants of bool can be written with or without the enum's name
+    assert!(typing_of("let result = if true then 1 else 2;") == Type::Int);
+    assert!(typing_of("let result = true && false || bool.true;") == bool_type);
+    assert!(
+        typing_of("let result = if (not false) || bool.false then \"a\" else \"b\";")
+            == Type::String
+    );
+    assert!(single_error("let result = bool.maybe;").1 == "Enum bool has no variant maybe");
+    assert!(check_program("let result = true + 1;").is_err());
+    // Variants with a field are given it with `with`
+    let e_type = Type::TypeId("E".to_string(), vec![]);
+    assert!(typing_of("enum E = A | B with int; let result = E.B with 1;") == e_type);
+    assert!(
+        typing_of("enum E = A | B with int; let result = [E.A, E.B with 2];")
+            == Type::List(Box::new(e_type))
+    );
+    assert!(
+        typing_of("enum 'a O = S with 'a | N; let result = O.S with 1.0;")
+            == Type::TypeId("O".to_string(), vec![Type::Float])
+    );
+    assert!(
+        check_program("enum 'a O = S with 'a | N; let result = [O.S with 1, O.S with \"a\"];")
+            .is_err()
+    );
+    let (span, msg) = single_error("enum E = A | B with int; let result = E.B with 1.0;");
+    assert!(span == Span::new(0, 47, 50));
+    assert!(msg.starts_with("Expected field of variant B to have type Int, but got Float"));
+    assert!(
+        single_error("enum E = A | B with int; let result = E.A with 1;").1
+            == "Variant A of enum E has no field"
+    );
+    assert!(
+        single_error("enum E = A | B with int; let result = E.C with 1;").1
+            == "Enum E has no variant C"
+    );
+    assert!(
+        single_error("enum E = A | B with int; let result = E.B;").1
+            == "Variant B of enum E has a field, which must be given with `with`"
+    );
+
+    let (_, msg) = single_error("let result = if 1 then 1 else 2;");
+    assert!(msg.starts_with("Expected condition of if to have type bool, but got Int"));
+    let (_, msg) = single_error("let result = if 1 < 2 then 1 else 2.0;");
+    assert!(msg.starts_with("Expected branches of if to have the same type, but got Int and Float"));
+    assert!(check_program("let result = 1 && 1 < 2;").is_err());
+    assert!(check_program("let result = not 1;").is_err());
+}
+
+#[test]
+fn test_check_match() {
+    let int_list = Type::List(Box::new(Type::Int));
+    assert!(typing_of("let result = match 1 { 0 => \"zero\", n => \"many\" };") == Type::String);
+    assert!(
+        typing_of("let f xs = match xs { [] => 0, x :: rest => x + 1 }; let result = f;")
+            == Type::Function(Box::new(int_list.clone()), Box::new(Type::Int))
+    );
+    // Variables bound by a pattern are only bound within its arm
+    assert!(
+        typing_of("let x = 1.0; let result = match [1] { [x] => x, _ => 0 }; let result = x;")
+            == Type::Float
+    );
+    assert!(
+        typing_of(
+            "let result = match (1, \"a\") { (0, s) | (_, s) if s == \"b\" => s, _ => \"c\" };"
+        ) == Type::String
+    );
+    assert!(typing_of("let result = match 5 { case 5 < 3 => 1, ~0 => 2, _ => 3 };") == Type::Int);
+    assert!(typing_of("let result = match true { true => 1, false => 0 };") == Type::Int);
+    assert!(
+        typing_of(
+            "enum 'a O = S with 'a | N; \
+             let result = match (O.S with [1]) { S with (x :: rest) => x, N => 0 };"
+        ) == Type::Int
+    );
+    assert!(check_program("let f r = match r { {a: x, _} => x + 1 };").is_ok());
+    assert!(check_program("let f r = match r { {a: x, _} => x + 1, {a: 1.0} => 0 };").is_err());
+
+    let (span, msg) = single_error("let result = match 1 { 0 => 1, _ => 2.0 };");
+    assert!(span == Span::new(0, 36, 39));
+    assert!(msg.starts_with("Expected arms of match to have the same type, but got Int and Float"));
+    let (span, msg) = single_error("let result = match 1 { \"a\" => 1, _ => 2 };");
+    assert!(span == Span::new(0, 23, 26));
+    assert!(msg.starts_with("Pattern of type String cannot match a value of type Int"));
+    assert!(single_error("let result = match 1 { n if n => 1 };")
+        .1
+        .starts_with("Expected guard to have type bool, but got Int"));
+    assert!(single_error("let result = match 1 { case 1 => 1 };")
+        .1
+        .starts_with("Expected case to have type bool, but got Int"));
+    assert!(single_error("let result = match 1 { Q with x => 1 };").1 == "Unknown variant Q");
+    assert!(
+        single_error("enum E = A | B with int; let result = match E.A { B => 1 };").1
+            == "Variant B of enum E has a field, which must be matched with `with`"
+    );
+}
+
+#[test]
+fn test_check_lambda() {
+    assert!(
+        typing_of("let result = \\x -> x + 1;")
+            == Type::Function(Box::new(Type::Int), Box::new(Type::Int))
+    );
+    assert!(
+        typing_of("let apply f x = f x; let result = apply (\\x -> (x, x)) 1.0;")
+            == Type::Tuple(vec![Type::Float, Type::Float])
+    );
+    assert!(
+        typing_of("let result = (1, \"a\", [2.0]);")
+            == Type::Tuple(vec![
+                Type::Int,
+                Type::String,
+                Type::List(Box::new(Type::Float))
+            ])
+    );
+    // The argument is not bound after the lambda
+    assert!(single_error("let f = \\y -> y; let result = y;").1 == "Unbound variable y");
+    assert!(check_program("let result = (\\x -> x + 1) 1.0;").is_err());
+}
+
+#[test]
+fn test_check_unsupported() {
+    // Constructs the checker cannot type yet are reported instead of panicking
+    assert!(
+        single_error("let result = {a: 1};")
+            == (
+                Span::new(0, 13, 19),
+                "Type checking record expressions is not supported yet".to_string()
+            )
+    );
+    assert!(
+        single_error("let r = 1; let result = r#m;").1
+            == "Type checking method access is not supported yet"
+    );
+}
+
+#[test]
+fn test_check_examples() {
+    for example in ["collatz_sequence.wye", "echo.wye", "even_odd.wye"] {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("examples")
+            .join(example);
+        let (_, modules, load_errors) = load_program(&path).unwrap();
+        assert!(load_errors.is_empty(), "{:?}", load_errors);
+        assert!(check::type_check_modules(&modules).is_ok(), "{}", example);
+    }
+}
+
+#[test]
+fn test_check_block() {
+    assert!(typing_of("let result = { print 1; let x = 2.0; x };") == Type::Float);
+    assert!(typing_of("let result = { print 1; print 2; };") == Type::None);
+    // Names bound in a block are not bound after it
+    assert!(typing_of("let x = 1; let result = { let x = 2.0; x }; let result = x;") == Type::Int);
+    assert!(single_error("let result = { let y = 1; y }; let z = y;").1 == "Unbound variable y");
+
+    let warnings = |inp: &str| {
+        let (prog, parse_errors) = parse_program(0, inp);
+        assert!(parse_errors.is_empty(), "{:?}", parse_errors);
+        let mut ctx = check::TypeContext::new();
+        for stmt in &prog {
+            check::type_check_statement(stmt, &mut ctx).unwrap();
+        }
+        ctx.type_warnings.into_values().collect::<Vec<_>>()
+    };
+    assert!(
+        warnings("let f x = { print x; x + 1; x };")
+            == vec!["Value of type Int is discarded in block"]
+    );
+    assert!(warnings("let result = { print 1; let x = 2; nothing; x };").is_empty());
+}
+
+#[test]
+fn test_check_scopes() {
+    // Arguments are only bound in the expression of their binding
+    assert!(
+        single_error("let f x = x; let y = x;")
+            == (Span::new(0, 21, 22), "Unbound variable x".to_string())
+    );
+    // Arguments and inner bindings shadow outer ones without replacing them
+    assert!(typing_of("let x = 1.0; let f x = x + 1; let result = x;") == Type::Float);
+    assert!(typing_of("let x = 1.0; let f x = { let x = [x]; x }; let result = x;") == Type::Float);
+    assert!(
+        typing_of("let x = 1.0; let result = (let x = 1 in [x | x <- [x]]);")
+            == Type::List(Box::new(Type::Int))
+    );
+    assert!(
+        typing_of("let x = 1.0; let result = (let x = 1 in [x | x <- [x]]); let result = x;")
+            == Type::Float
+    );
+}
+
+#[test]
+fn test_check_main() {
+    assert!(check_program("let main (args: [string]) -> int = 0;").is_ok());
+    assert!(check_program("let main args = if args == [] then 1 else 0;").is_ok());
+    // Programs without main are run top to bottom
+    assert!(check_program("print 1;").is_ok());
+
+    let (span, msg) = single_error("let main args = 1.0;");
+    assert!(span == Span::new(0, 4, 8));
+    assert!(msg.starts_with("Expected main to have type [string] -> int"));
+    assert!(single_error("let main (n: int) -> int = n;").0 == Span::new(0, 4, 8));
+    assert!(single_error("let main = 0;")
+        .1
+        .starts_with("Expected main to have type"));
+    // Only the last binding of main is the entry point
+    assert!(check_program("let main = 0; let main (args: [string]) -> int = 0;").is_ok());
+}
+
+#[test]
+fn test_check_modules() {
+    let (_, modules, load_errors) = load_program(&fixture("main.wye")).unwrap();
+    assert!(load_errors.is_empty());
+    assert!(check::type_check_modules(&modules).is_ok());
+
+    // Errors are reported in the module they occur in
+    let (_, modules, _) = load_program(&fixture("errors.wye")).unwrap();
+    let root = modules[1].file;
+    let errors = check::type_check_modules(&modules).unwrap_err();
+    assert!(errors.iter().all(|(_, span)| span.file == root));
+    let messages = errors.iter().map(|e| e.0.as_str()).collect::<Vec<_>>();
+    assert!(messages.contains(&"Unknown module nowhere"));
+    assert!(messages.contains(&"Module List has no member nope"));
+    assert!(messages.contains(&"Module Strings must be imported before it is opened"));
+    assert!(errors.contains(&("Unknown module nowhere".to_string(), Span::new(root, 0, 14))));
+
+    // Without a loader, no modules can be imported
+    assert!(
+        single_error("import List;") == (Span::new(0, 0, 11), "Unknown module List".to_string())
+    );
+}
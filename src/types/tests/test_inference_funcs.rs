@@ -1 +1,195 @@
+use super::infer::{apply_subst_type, unify, unify_structures};
+use super::structure::{Flex, Structure};
+use super::Type::*;
+use super::*;
+use std::collections::{BTreeMap, HashMap};
 
+fn func(arg: Type, ret: Type) -> Type {
+    Function(Box::new(arg), Box::new(ret))
+}
+
+#[test]
+fn test_unify_variables() {
+    let mut subst = HashMap::new();
+    assert!(unify(&Variable(0), &Int, &mut subst).is_ok());
+    assert!(subst == HashMap::from([(0, Int)]));
+
+    // A variable unifies with itself without being bound
+    let mut subst = HashMap::new();
+    assert!(unify(&Variable(0), &Variable(0), &mut subst).is_ok());
+    assert!(subst.is_empty());
+
+    // Bindings found earlier apply to the rest of the types
+    let mut subst = HashMap::new();
+    assert!(unify(
+        &func(Variable(0), Variable(0)),
+        &func(Int, Float),
+        &mut subst
+    )
+    .is_err());
+    let mut subst = HashMap::new();
+    assert!(unify(
+        &func(Variable(0), Variable(1)),
+        &func(Variable(1), List(Box::new(Int))),
+        &mut subst
+    )
+    .is_ok());
+    assert!(apply_subst_type(&subst, &Variable(0)) == List(Box::new(Int)));
+}
+
+#[test]
+fn test_unify_occurs_check() {
+    let mut subst = HashMap::new();
+    let err = unify(&Variable(0), &List(Box::new(Variable(0))), &mut subst).unwrap_err();
+    assert!(err == "Infinite type: Variable(0) cannot be unified with List(Variable(0)), which contains it");
+    assert!(subst.is_empty());
+
+    // The variable may only occur in the type once the substitution is applied
+    let mut subst = HashMap::new();
+    assert!(unify(
+        &func(Variable(0), Variable(1)),
+        &func(Variable(1), func(Int, Variable(0))),
+        &mut subst
+    )
+    .unwrap_err()
+    .starts_with("Infinite type"));
+}
+
+#[test]
+fn test_unify_tuples() {
+    let mut subst = HashMap::new();
+    assert!(unify(
+        &Tuple(vec![Variable(0), Float]),
+        &Tuple(vec![Int, Variable(1)]),
+        &mut subst
+    )
+    .is_ok());
+    assert!(subst == HashMap::from([(0, Int), (1, Float)]));
+
+    let err = unify(
+        &Tuple(vec![Int]),
+        &Tuple(vec![Int, Int]),
+        &mut HashMap::new(),
+    )
+    .unwrap_err();
+    assert!(err == "Cannot unify a tuple of 1 elements with a tuple of 2 elements");
+    let err = unify(
+        &Tuple(vec![Int, Int]),
+        &Tuple(vec![Int, Float]),
+        &mut HashMap::new(),
+    )
+    .unwrap_err();
+    assert!(err == "Tuple elements at index 1 disagree: Cannot unify Int with Float");
+}
+
+#[test]
+fn test_unify_type_ids() {
+    let option = |t: Type| TypeId("Option".to_string(), vec![t]);
+    let mut subst = HashMap::new();
+    assert!(unify(&option(Variable(0)), &option(String), &mut subst).is_ok());
+    assert!(subst == HashMap::from([(0, String)]));
+
+    let err = unify(
+        &option(Int),
+        &TypeId("List".to_string(), vec![Int]),
+        &mut HashMap::new(),
+    )
+    .unwrap_err();
+    assert!(err == "Cannot unify type Option with type List");
+    let err = unify(
+        &option(Int),
+        &TypeId("Option".to_string(), vec![]),
+        &mut HashMap::new(),
+    )
+    .unwrap_err();
+    assert!(err == "Cannot unify type Option with 1 type argument(s) with type Option with 0");
+    let err = unify(&option(Int), &option(Float), &mut HashMap::new()).unwrap_err();
+    assert!(err == "Type arguments of Option at index 0 disagree: Cannot unify Int with Float");
+
+    let err = unify(
+        &Poly("a".to_string(), Option::None),
+        &Int,
+        &mut HashMap::new(),
+    )
+    .unwrap_err();
+    assert!(err == "Type variable 'a is rigid and cannot be unified with Int");
+}
+
+#[test]
+fn test_unify_records() {
+    let record = |values: Vec<(&str, Type)>, flex: Flex| {
+        let values = values
+            .into_iter()
+            .map(|(name, typ)| (name.to_string(), typ))
+            .collect();
+        Structure::new(BTreeMap::new(), values, flex)
+    };
+    let exact_xy = record(vec![("x", Int), ("y", Variable(0))], Flex::Exact);
+
+    // Exact records must have the same members
+    let mut subst = HashMap::new();
+    let unified = unify_structures(
+        &exact_xy,
+        &record(vec![("x", Variable(1)), ("y", Float)], Flex::Exact),
+        &mut subst,
+    )
+    .unwrap();
+    assert!(unified == record(vec![("x", Int), ("y", Float)], Flex::Exact));
+    assert!(subst == HashMap::from([(0, Float), (1, Int)]));
+    let err = unify_structures(
+        &exact_xy,
+        &record(vec![("x", Int)], Flex::Exact),
+        &mut HashMap::new(),
+    )
+    .unwrap_err();
+    assert!(err == "Record has no value y");
+
+    // A permissive record may list only some of the members of an exact one
+    let unified = unify_structures(
+        &record(vec![("y", String)], Flex::Permissive),
+        &exact_xy,
+        &mut HashMap::new(),
+    )
+    .unwrap();
+    assert!(unified == record(vec![("x", Int), ("y", String)], Flex::Exact));
+    let err = unify_structures(
+        &record(vec![("z", Int)], Flex::Permissive),
+        &exact_xy,
+        &mut HashMap::new(),
+    )
+    .unwrap_err();
+    assert!(err == "Record has no value z");
+
+    // Permissive records only need to agree on the members they share
+    let unified = unify_structures(
+        &record(vec![("x", Int)], Flex::Permissive),
+        &record(vec![("z", Float)], Flex::Permissive),
+        &mut HashMap::new(),
+    )
+    .unwrap();
+    assert!(unified == record(vec![("x", Int), ("z", Float)], Flex::Permissive));
+
+    // Unifying record types only keeps the bindings of their shared members,
+    // not the members they are merged into
+    let mut subst = HashMap::new();
+    let permissive_x = record(vec![("x", Variable(0))], Flex::Permissive);
+    unify(
+        &Record(permissive_x.clone()),
+        &Record(record(vec![("x", Int), ("z", Float)], Flex::Permissive)),
+        &mut subst,
+    )
+    .unwrap();
+    assert!(subst == HashMap::from([(0, Int)]));
+    assert!(
+        apply_subst_type(&subst, &Record(permissive_x))
+            == Record(record(vec![("x", Int)], Flex::Permissive))
+    );
+
+    let err = unify(
+        &Record(exact_xy),
+        &Record(record(vec![("x", Float)], Flex::Permissive)),
+        &mut HashMap::new(),
+    )
+    .unwrap_err();
+    assert!(err == "Types of record value x disagree: Cannot unify Int with Float");
+}